@@ -37,17 +37,32 @@ impl ShadersCompiler {
             .arg(shader_path)
             .args(&["-o", &output_file])
             .output()
-            .expect(
-                &format!(
-                    "shader compilation failed. {}",
-                    shader_path)
-            );
+            .unwrap_or_else(|err| panic!("shader compilation failed. {}: {}", shader_path, err));
 
         if output.stderr.len() > 0 {
             panic!("{:?}", output);
         }
     }
 
+    //Описания конвейеров не компилируются, а кладутся рядом с шейдерами
+    fn copy_file(
+        &self,
+        file: DirEntry,
+        additional_output: &str
+    ){
+        let file_name = file.file_name();
+        let file_name = file_name.to_str().unwrap();
+
+        let output_directory = concat_paths(
+            &self.output_directory,
+            additional_output
+        );
+
+        let output_file = concat_paths(&output_directory, file_name);
+        fs::copy(file.path(), &output_file)
+            .unwrap_or_else(|err| panic!("copying failed. {}: {}", output_file, err));
+    }
+
     fn compile_shaders(
         &self
     ) {
@@ -72,7 +87,9 @@ impl ShadersCompiler {
                 let dir_entry = dir_entry.unwrap();
                 let file_type = dir_entry.file_type().unwrap();
 
-                if file_type.is_file() {
+                if file_type.is_file() && is_pipeline_description(&dir_entry) {
+                    self.copy_file(dir_entry, &current_dir);
+                } else if file_type.is_file() {
                     self.compile_shader(dir_entry, &current_dir);
                 } else {
                     let dir_name = dir_entry.file_name();
@@ -94,6 +111,15 @@ fn concat_paths(path1: &str, path2: &str) -> String{
 
 }
 
+fn is_pipeline_description(file: &DirEntry) -> bool {
+    let path = file.path();
+    let extension = path.extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("");
+
+    matches!(extension, "ron" | "toml" | "json")
+}
+
 fn create_output_directory_if_not_exists(directory_path: String) {
     fs::create_dir_all(directory_path).unwrap();
 }
@@ -0,0 +1,83 @@
+use std::collections::HashSet;
+
+use winit::event::{ElementState, MouseButton, MouseScrollDelta, WindowEvent};
+use winit::keyboard::{KeyCode, PhysicalKey};
+
+use crate::rendering::{CameraInput, Vec2, Vec3};
+
+//Пикселей сенсорной панели на одну строку прокрутки
+const PIXELS_PER_LINE: f32 = 40.0;
+
+//Копит события окна между кадрами. Левая кнопка мыши вращает,
+//WASD - движение, E/Q - вверх/вниз, Shift - ускорение
+#[derive(Debug, Default)]
+pub struct CameraInputState {
+    pressed: HashSet<KeyCode>,
+    rotating: bool,
+    cursor: Option<Vec2>,
+    mouse_delta: Vec2,
+    scroll: f32
+}
+
+impl CameraInputState {
+    pub fn handle_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::KeyboardInput { event, .. } => {
+                if let PhysicalKey::Code(key) = event.physical_key {
+                    match event.state {
+                        ElementState::Pressed => self.pressed.insert(key),
+                        ElementState::Released => self.pressed.remove(&key)
+                    };
+                }
+            }
+            WindowEvent::MouseInput { button: MouseButton::Left, state, .. } => {
+                self.rotating = *state == ElementState::Pressed;
+            }
+            WindowEvent::CursorMoved { position, .. } => {
+                let position = Vec2::new(position.x as f32, position.y as f32);
+                if let Some(previous) = self.cursor {
+                    self.mouse_delta += position - previous;
+                }
+                self.cursor = Some(position);
+            }
+            WindowEvent::CursorLeft { .. } => {
+                self.cursor = None;
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                self.scroll += match delta {
+                    MouseScrollDelta::LineDelta(_, lines) => *lines,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32 / PIXELS_PER_LINE
+                };
+            }
+            //Отпускание клавиш вне окна не приходит
+            WindowEvent::Focused(false) => {
+                self.pressed.clear();
+                self.rotating = false;
+            }
+            _ => {}
+        }
+    }
+
+    //Ввод за прошедший кадр, накопленные смещения обнуляются
+    pub fn take(&mut self) -> CameraInput {
+        let axis = |positive: KeyCode, negative: KeyCode| {
+            self.pressed.contains(&positive) as i32 as f32 - self.pressed.contains(&negative) as i32 as f32
+        };
+
+        let input = CameraInput {
+            mouse_delta: self.mouse_delta,
+            scroll: self.scroll,
+            rotating: self.rotating,
+            movement: Vec3::new(
+                axis(KeyCode::KeyD, KeyCode::KeyA),
+                axis(KeyCode::KeyE, KeyCode::KeyQ),
+                axis(KeyCode::KeyW, KeyCode::KeyS)
+            ),
+            fast: self.pressed.contains(&KeyCode::ShiftLeft) || self.pressed.contains(&KeyCode::ShiftRight)
+        };
+
+        self.mouse_delta = Vec2::ZERO;
+        self.scroll = 0.0;
+        input
+    }
+}
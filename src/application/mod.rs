@@ -1,5 +1,6 @@
 mod window;
 mod exceptions;
+mod camera_input;
 
 pub use window::ApplicationWindow;
 pub use exceptions::ApplicationError;
@@ -1,6 +1,9 @@
-use log::debug;
+use std::time::Instant;
+
+use log::{debug, error, info};
 use winit::dpi::{LogicalSize, PhysicalSize};
 use winit::event::{
+    ElementState,
     Event,
     WindowEvent
 };
@@ -8,6 +11,10 @@ use winit::event_loop::{
     EventLoop,
     EventLoopWindowTarget
 };
+use winit::keyboard::{
+    KeyCode,
+    PhysicalKey
+};
 use winit::window::{
     Window,
     WindowBuilder
@@ -15,7 +22,8 @@ use winit::window::{
 use winit::raw_window_handle::{DisplayHandle, HandleError, HasDisplayHandle, HasWindowHandle, WindowHandle};
 
 use super::ApplicationError;
-use crate::rendering::RenderingQueue;
+use super::camera_input::CameraInputState;
+use crate::rendering::{Camera, CameraController, CullMode, DebugRasterization, FrameContext, PolygonMode, RenderingQueue, RenderingResolution};
 
 #[derive(Debug)]
 pub struct ApplicationWindow {
@@ -24,12 +32,14 @@ pub struct ApplicationWindow {
 }
 
 impl ApplicationWindow {
-    pub fn new() -> Result<Self, ApplicationError>{
+    pub fn new(transparent: bool) -> Result<Self, ApplicationError>{
         let event_loop = EventLoop::new()?;
 
         let window = WindowBuilder::new()
             .with_title("VulkanLearning")
             .with_inner_size(LogicalSize::new(1024, 768))
+            //Прозрачное окно для отрисовки поверх рабочего стола
+            .with_transparent(transparent)
             .build(&event_loop)?;
 
         Result::Ok(Self {
@@ -42,15 +52,52 @@ impl ApplicationWindow {
         return self.window.inner_size() ;
     }
 
-    pub fn run(mut self, rendering_queue: &mut RenderingQueue) -> Result<(), ApplicationError>{
+    //Камера обновляется контроллером перед каждым кадром
+    //и попадает в uniform буфер кадра. per_frame - кадр записывается
+    //заново через render_with вместо заранее записанных буферов
+    pub fn run(
+        mut self,
+        rendering_queue: &mut RenderingQueue,
+        camera: &mut Camera,
+        controller: &mut dyn CameraController,
+        per_frame: bool
+    ) -> Result<(), ApplicationError>{
         debug!("Starting main loop");
+        let mut input = CameraInputState::default();
+        let mut last_frame = Instant::now();
+        let mut statistics = FrameStatistics::default();
+
         self.event_loop.run(|event: Event<()>, target_window:&EventLoopWindowTarget<()>|{
             match event {
                 Event::AboutToWait => {
                     self.window.request_redraw();
                 },
-                Event::WindowEvent {event, ..}  =>
-                    processing_window_event(event, target_window, rendering_queue),
+                Event::WindowEvent {event, ..}  => {
+                    input.handle_event(&event);
+
+                    if event == WindowEvent::RedrawRequested {
+                        let now = Instant::now();
+                        controller.update(camera, &input.take(), (now - last_frame).as_secs_f32());
+                        last_frame = now;
+
+                        if let Err(err) = rendering_queue.update_camera(camera) {
+                            error!("camera was not updated: {:?}", err);
+                        }
+                    }
+
+                    if event == WindowEvent::RedrawRequested && per_frame && !target_window.exiting() {
+                        let recorded = rendering_queue.render_with(|frame| unsafe {
+                            frame.draw_scene();
+                            statistics.add(frame);
+                        });
+                        if let Err(err) = recorded {
+                            error!("frame was not rendered: {:?}", err);
+                        }
+                        return;
+                    }
+
+                    processing_window_event(event, target_window, rendering_queue)
+                }
                 _ => {}
             }
         })?;
@@ -58,6 +105,27 @@ impl ApplicationWindow {
     }
 }
 
+//Частота кадров в журнал раз в секунду
+#[derive(Debug, Default)]
+struct FrameStatistics {
+    frames: u32,
+    elapsed: f32
+}
+
+impl FrameStatistics {
+    fn add(&mut self, frame: &FrameContext) {
+        self.frames += 1;
+        self.elapsed += frame.delta_time;
+        if self.elapsed < 1.0 {
+            return;
+        }
+
+        debug!("{:.1} fps, image {}, frame in flight {}",
+            self.frames as f32 / self.elapsed, frame.image_index, frame.frame_index);
+        *self = Self::default();
+    }
+}
+
 fn processing_window_event(
     event: WindowEvent,
     target_window: &EventLoopWindowTarget<()>,
@@ -69,13 +137,46 @@ fn processing_window_event(
                 _ = rendering_queue.render();
             }
         }
+        WindowEvent::Resized(size) => {
+            rendering_queue.resize(RenderingResolution::from(size));
+        }
         WindowEvent::CloseRequested => {
             target_window.exit();
         }
+        WindowEvent::KeyboardInput { event, .. } if event.state == ElementState::Pressed && !event.repeat => {
+            if let PhysicalKey::Code(key) = event.physical_key {
+                switch_debug_rasterization(key, rendering_queue);
+            }
+        }
         _ => {}
     }
 }
 
+//F1 - каркас, F2 - точки, F3 - без отсечения граней, F4 - каркас поверх геометрии
+fn switch_debug_rasterization(key: KeyCode, rendering_queue: &mut RenderingQueue) {
+    let mut debug = rendering_queue.debug_rasterization();
+    match key {
+        KeyCode::F1 => debug.polygon_mode = toggle(debug.polygon_mode, PolygonMode::Line),
+        KeyCode::F2 => debug.polygon_mode = toggle(debug.polygon_mode, PolygonMode::Point),
+        KeyCode::F3 => debug.cull_mode = toggle(debug.cull_mode, CullMode::None),
+        KeyCode::F4 => debug.wireframe_overlay = toggle(debug.wireframe_overlay, DebugRasterization::OVERLAY_COLOR),
+        _ => return
+    }
+
+    match rendering_queue.set_debug_rasterization(debug) {
+        Ok(()) => info!("debug rasterization: {:?}", debug),
+        Err(err) => error!("debug rasterization was not applied: {:?}", err)
+    }
+}
+
+fn toggle<T: PartialEq>(current: Option<T>, value: T) -> Option<T> {
+    if current.as_ref() == Some(&value) {
+        None
+    } else {
+        Some(value)
+    }
+}
+
 impl HasWindowHandle for ApplicationWindow {
     fn window_handle(&self) -> Result<WindowHandle<'_>, HandleError> {
         self.window.window_handle()
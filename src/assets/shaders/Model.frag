@@ -0,0 +1,10 @@
+#version 450
+
+layout(location = 0) in vec3 fragNormal;
+
+layout(location = 0) out vec4 outColor;
+
+//Нормаль в цвет, пока у моделей нет материалов
+void main() {
+    outColor = vec4(normalize(fragNormal) * 0.5 + 0.5, 1.0);
+}
\ No newline at end of file
@@ -0,0 +1,22 @@
+#version 450
+
+//Model.vert с матрицей экземпляра InstanceTransform в location 4..7.
+//Нормаль переносится без обратной транспонированной матрицы,
+//экземплярам хватает переноса и равномерного масштаба
+layout(set = 0, binding = 0) uniform Camera {
+    mat4 view;
+    mat4 projection;
+    mat4 view_projection;
+    vec4 position;
+} camera;
+
+layout(location = 0) in vec3 inPosition;
+layout(location = 1) in vec3 inNormal;
+layout(location = 4) in mat4 inModel;
+
+layout(location = 0) out vec3 fragNormal;
+
+void main() {
+    gl_Position = camera.view_projection * (inModel * vec4(inPosition, 1.0));
+    fragNormal = (inModel * vec4(inNormal, 0.0)).xyz;
+}
@@ -0,0 +1,20 @@
+#version 450
+
+//Вершины ModelVertex: position, normal, uv, tangent в location 0..3.
+//Матрицы камеры из RenderingQueue::update_camera в set 0
+layout(set = 0, binding = 0) uniform Camera {
+    mat4 view;
+    mat4 projection;
+    mat4 view_projection;
+    vec4 position;
+} camera;
+
+layout(location = 0) in vec3 inPosition;
+layout(location = 1) in vec3 inNormal;
+
+layout(location = 0) out vec3 fragNormal;
+
+void main() {
+    gl_Position = camera.view_projection * vec4(inPosition, 1.0);
+    fragNormal = inNormal;
+}
@@ -2,7 +2,8 @@ mod application;
 mod rendering;
 
 use std::env;
-use log::LevelFilter;
+use std::path::{Path, PathBuf};
+use log::{error, warn, LevelFilter};
 use application::ApplicationWindow;
 use simple_logger::SimpleLogger;
 
@@ -14,8 +15,8 @@ use vulkanalia::vk::{
 };
 
 use winit::raw_window_handle::{HasDisplayHandle, HasWindowHandle};
-use crate::rendering::RenderingError::LoadShadersError;
-use crate::rendering::{RenderingResolution, RqResult};
+use crate::rendering::RenderingError::{CreateMeshError, LoadShadersError};
+use crate::rendering::{Camera, CameraController, FlyController, ImageFormat, ImageSize, InstanceTransform, NodeHandle, OrbitController, PipelineCache, Vec3, PipelineDescription, RenderGraph, RenderingResolution, RqResult, Transform, TransformHierarchy, ViewportRect};
 
 use self::rendering::{
     RenderingQueue
@@ -26,20 +27,25 @@ pub fn create_rendering_queue<TWindow>(
     window: &TWindow,
     use_validation_layer: bool,
     rendering_resolution: RenderingResolution,
+    transparent: bool,
+    pipeline_path: Option<PathBuf>,
+    shaders_directory: &Path
 ) -> RqResult<RenderingQueue>
     where TWindow: HasWindowHandle + HasDisplayHandle
 {
-    let mut path_to = env::current_exe()
-        .map_err(|err|LoadShadersError(String::from("Unable to get the path")))?;
-    path_to.pop();
-    path_to.push("assets/shaders");
-    path_to.push("Example.frag.spv");
+    let path_to = pipeline_path
+        .unwrap_or_else(|| shaders_directory.join("Example.pipeline.ron"));
+    let description = PipelineDescription::load(&path_to)?;
+
     let mut buffer = Vec::with_capacity(4096);
-    let frag_shader = crate::rendering::Shader::read_file(&path_to, &mut buffer)?;
-    path_to.pop();
-    path_to.push("Example.vert.spv");
-    let vert_shader = crate::rendering::Shader::read_file(&path_to, &mut buffer)?;
+    let (vert_shader, frag_shader) = description.read_shaders(&mut buffer)?;
 
+    //Для прозрачного окна цвет очистки в premultiplied виде
+    let clear_color = if transparent {
+        [0.0, 0.0, 0.0, 0.0]
+    } else {
+        [0.0, 0.0, 0.0, 1.0]
+    };
 
     let rendering_queue = RenderingQueue::builder()
         .create_entry()?
@@ -55,19 +61,117 @@ pub fn create_rendering_queue<TWindow>(
         )?
         .create_swap_chain(
             rendering_resolution,
-            vk::SwapchainKHR::null()
+            vk::SwapchainKHR::null(),
+            transparent
         )?
+        .create_color_buffer(vk::SampleCountFlags::_4, Some(0.2))?
+        .create_depth_buffer(false)?
         .create_render_pass()?
-        .add_pipeline(&vert_shader, &frag_shader)?
+        .create_pipeline_cache(PipelineCache::default_path())?
+        .add_pipeline(&description, &vert_shader, &frag_shader)?
         .create_framebuffers()?
         .create_command_pool()?
-        .create_command_buffer()?
+        .create_command_buffer(clear_color)?
         .create_sync_objects(4)?
         .build();
 
     Result::Ok(rendering_queue)
 }
 
+//Собранные шейдеры лежат рядом с исполняемым файлом и содержат только .spv.
+//Для пересборки на лету берутся исходники: GLSL компилируется рядом с собой,
+//и пути из описаний указывают на те же файлы, за которыми следит наблюдатель
+fn shaders_directory(hot_reload: bool) -> RqResult<PathBuf> {
+    let sources = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/assets/shaders");
+    if hot_reload && sources.is_dir() {
+        return Result::Ok(sources);
+    }
+
+    let mut directory = env::current_exe()
+        .map_err(|err| LoadShadersError(format!("Unable to get the path: {}", err)))?;
+    directory.pop();
+    directory.push("assets/shaders");
+    Result::Ok(directory)
+}
+
+//Кадр через граф: сцена рисуется в backbuffer с отдельной глубиной,
+//конвейеры пересобираются под проход графа
+fn use_render_graph(rendering_queue: &mut RenderingQueue, clear_color: [f32; 4]) -> RqResult<()> {
+    let mut graph = RenderGraph::new();
+    let backbuffer = graph.backbuffer();
+    let depth = graph.create_image("depth", ImageFormat::Depth, ImageSize::SwapchainRelative(1.0));
+
+    graph.add_pass(
+        "scene",
+        |pass| {
+            pass.write_color(backbuffer, Some(clear_color))
+                .write_depth(depth, Some(1.0));
+        },
+        |context| unsafe {
+            let scene_pipelines: Vec<_> = context.pipelines.iter()
+                .filter(|(_, pipeline)| pipeline.pass.as_deref() == Some("scene"))
+                .flat_map(|(_, pipeline)| pipeline.variants().map(move |variant| (pipeline, variant)))
+                .collect();
+
+            for viewport in context.viewports {
+                context.device.cmd_set_viewport(context.command_buffer, 0, &[viewport.to_viewport(context.extent)]);
+                context.device.cmd_set_scissor(context.command_buffer, 0, &[viewport.to_scissor(context.extent)]);
+
+                for (pipeline, variant) in &scene_pipelines {
+                    pipeline.bind_uniform_set(context.device, context.command_buffer, context.uniform_sets, context.frame_index);
+                    context.device.cmd_bind_pipeline(context.command_buffer, vk::PipelineBindPoint::GRAPHICS, *variant);
+                    pipeline.draw_meshes(context.device, context.command_buffer, context.meshes);
+                }
+            }
+        }
+    );
+
+    rendering_queue.set_render_graph(Some(graph))?;
+
+    let descriptions: Vec<PipelineDescription> = rendering_queue.pipelines().iter()
+        .map(|(_, pipeline)| pipeline.description.clone())
+        .collect();
+
+    let mut buffer = Vec::with_capacity(4096);
+    for mut description in descriptions {
+        let (vert_shader, frag_shader) = description.read_shaders(&mut buffer)?;
+        description.name = format!("{}_scene", description.name);
+        rendering_queue.add_pass_pipeline("scene", &description, &vert_shader, &frag_shader)?;
+    }
+
+    Result::Ok(())
+}
+
+//Экземпляры сеткой count x count с шагом 2 по X и Z вокруг начала координат.
+//Ячейки - потомки корня, сетка центрируется переносом корня
+fn instance_grid(count: &str) -> RqResult<Vec<InstanceTransform>> {
+    let count: u32 = count.parse()
+        .map_err(|err| CreateMeshError(format!("instance count \"{}\": {}", count, err)))?;
+    if count == 0 {
+        return Err(CreateMeshError(String::from("instance count must be positive")));
+    }
+
+    let mut grid = TransformHierarchy::new();
+    let root = grid.add(Transform::IDENTITY, None);
+    let cells: Vec<NodeHandle> = (0..count * count)
+        .map(|index| grid.add(
+            Transform {
+                translation: Vec3::new((index % count) as f32, 0.0, (index / count) as f32) * 2.0,
+                ..Transform::IDENTITY
+            },
+            Some(root)
+        ))
+        .collect();
+
+    let half = (count - 1) as f32;
+    grid.set_local(root, Transform { translation: Vec3::new(-half, 0.0, -half), ..Transform::IDENTITY });
+    grid.update();
+
+    Result::Ok(cells.iter()
+        .map(|cell| InstanceTransform::from(grid.world(*cell)))
+        .collect())
+}
+
 fn main(){
     SimpleLogger::new()
         .with_colors(true)
@@ -77,17 +181,126 @@ fn main(){
 
     log::set_max_level(LevelFilter::Trace);
 
+    let transparent = env::args().any(|arg| arg == "--transparent");
+
+    //--instances <n> - модель повторяется сеткой n x n конвейером Model.instanced
+    let instances = env::args()
+        .skip_while(|arg| arg != "--instances")
+        .nth(1)
+        .map(|count| instance_grid(&count))
+        .transpose();
+    let instances = match instances {
+        Ok(instances) => instances,
+        Err(err) => {
+            error!("--instances: {:?}", err);
+            return;
+        }
+    };
+
+    //--pipeline <path> - описание конвейера вместо стандартного Example
+    let pipeline_path = env::args()
+        .skip_while(|arg| arg != "--pipeline")
+        .nth(1)
+        .map(PathBuf::from);
+
+    let hot_reload = env::args().any(|arg| arg == "--hot-reload");
+    let shaders_directory = shaders_directory(hot_reload)
+        .expect("shaders directory exception");
+
     let window =
-        ApplicationWindow::new()
+        ApplicationWindow::new(transparent)
             .expect("window creation exception");
 
     let mut rendering_queue = create_rendering_queue(
         &window,
         true,
-        RenderingResolution::from(window.inner_size())
+        RenderingResolution::from(window.inner_size()),
+        transparent,
+        pipeline_path,
+        &shaders_directory
     ).expect("rendering queue create exception");
 
-    window.run(&mut rendering_queue)
+    //Разделенный экран: сцена рисуется в левую и правую половины окна
+    if env::args().any(|arg| arg == "--split-screen") {
+        rendering_queue.set_viewports(&[
+            ViewportRect::new(0.0, 0.0, 0.5, 1.0),
+            ViewportRect::new(0.5, 0.0, 0.5, 1.0)
+        ]).expect("viewports setup exception");
+    }
+
+    //--model <path> - OBJ или glTF. Если файла нет по пути, он ищется в assets/models.
+    //Конвейер моделей добавляется до графа, чтобы получить вариант для его прохода
+    let model = env::args().skip_while(|arg| arg != "--model").nth(1);
+    if model.is_some() {
+        let model_pipeline = if instances.is_some() {
+            "Model.instanced.pipeline.ron"
+        } else {
+            "Model.pipeline.ron"
+        };
+        rendering_queue.load_pipeline(&shaders_directory.join(model_pipeline))
+            .expect("model pipeline loading exception");
+    }
+
+    let use_graph = env::args().any(|arg| arg == "--render-graph");
+    if use_graph {
+        let clear_color = if transparent {
+            [0.0, 0.0, 0.0, 0.0]
+        } else {
+            [0.0, 0.0, 0.0, 1.0]
+        };
+
+        use_render_graph(&mut rendering_queue, clear_color)
+            .expect("render graph setup exception");
+    }
+
+    if let Some(model) = model {
+        let mut model_path = PathBuf::from(&model);
+        if !model_path.exists() {
+            model_path = env::current_exe()
+                .expect("executable path exception");
+            model_path.pop();
+            model_path.push("assets/models");
+            model_path.push(&model);
+        }
+
+        let meshes = rendering_queue.load_model(&model_path)
+            .expect("model loading exception");
+
+        if let Some(instances) = &instances {
+            for mesh in meshes {
+                rendering_queue.set_mesh_instances(mesh, instances)
+                    .expect("mesh instances exception");
+            }
+        }
+    }
+
+    //Пересборка конвейеров при изменении шейдеров без перезапуска
+    if hot_reload {
+        rendering_queue.watch_shaders(&shaders_directory)
+            .expect("shader watcher setup exception");
+    }
+
+    //--fly - свободная камера вместо вращения вокруг начала координат,
+    //--reverse-z - ближняя плоскость в глубине 1 (конвейерам нужно сравнение Greater)
+    let mut camera = Camera::perspective(60f32.to_radians(), 0.1, 100.0)
+        .with_reverse_z(env::args().any(|arg| arg == "--reverse-z"));
+    camera.position = Vec3::new(0.0, 0.0, 3.0);
+    rendering_queue.set_clear_depth(camera.depth_clear_value())
+        .expect("depth clear setup exception");
+
+    let mut controller: Box<dyn CameraController> = if env::args().any(|arg| arg == "--fly") {
+        Box::new(FlyController::default())
+    } else {
+        Box::new(OrbitController::new(Vec3::ZERO, 3.0))
+    };
+
+    //--per-frame - команды записываются каждый кадр, с графом не работает
+    let per_frame = env::args().any(|arg| arg == "--per-frame");
+    if per_frame && use_graph {
+        warn!("--per-frame is ignored with --render-graph");
+    }
+
+    window.run(&mut rendering_queue, &mut camera, controller.as_mut(), per_frame && !use_graph)
         .expect("main loop exception");
 
 
@@ -0,0 +1,330 @@
+use log::warn;
+use vulkanalia::prelude::v1_0::*;
+
+use crate::rendering::{get_enabled_features, RenderingError, RqResult, SwapChainData};
+use crate::rendering::memory::find_memory_type_index;
+use crate::rendering::RenderingError::{CreateColorBufferError, CreateDepthBufferError, SupportError};
+
+const DEPTH_FORMATS: &[vk::Format] = &[
+    vk::Format::D32_SFLOAT,
+    vk::Format::D32_SFLOAT_S8_UINT,
+    vk::Format::D24_UNORM_S8_UINT,
+    vk::Format::D16_UNORM
+];
+
+const DEPTH_STENCIL_FORMATS: &[vk::Format] = &[
+    vk::Format::D32_SFLOAT_S8_UINT,
+    vk::Format::D24_UNORM_S8_UINT,
+    vk::Format::D16_UNORM_S8_UINT
+];
+
+const SAMPLE_COUNTS: &[vk::SampleCountFlags] = &[
+    vk::SampleCountFlags::_64,
+    vk::SampleCountFlags::_32,
+    vk::SampleCountFlags::_16,
+    vk::SampleCountFlags::_8,
+    vk::SampleCountFlags::_4,
+    vk::SampleCountFlags::_2,
+    vk::SampleCountFlags::_1
+];
+
+//Набор изображений-вложений, по одному на каждое изображение swap chain
+#[derive(Debug, Default)]
+pub struct AttachmentImages {
+    pub images: Vec<vk::Image>,
+    pub memories: Vec<vk::DeviceMemory>,
+    pub image_views: Vec<vk::ImageView>
+}
+
+impl AttachmentImages {
+    pub unsafe fn create(
+        instance: &Instance,
+        device: &Device,
+        physical_device: vk::PhysicalDevice,
+        info: &AttachmentInfo,
+        images_count: usize,
+        error: fn(vk::ErrorCode) -> RenderingError
+    ) -> RqResult<Self>
+    {
+        let mut attachments = Self {
+            images: Vec::with_capacity(images_count),
+            memories: Vec::with_capacity(images_count),
+            image_views: Vec::with_capacity(images_count)
+        };
+
+        for _ in 0..images_count {
+            let result = attachments.push_image(instance, device, physical_device, info, error);
+            if let Err(err) = result {
+                attachments.destroy(device);
+                return Result::Err(err);
+            }
+        }
+
+        Result::Ok(attachments)
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        for image_view in &self.image_views {
+            device.destroy_image_view(*image_view, None);
+        }
+        for image in &self.images {
+            device.destroy_image(*image, None);
+        }
+        for memory in &self.memories {
+            device.free_memory(*memory, None);
+        }
+    }
+
+    unsafe fn push_image(
+        &mut self,
+        instance: &Instance,
+        device: &Device,
+        physical_device: vk::PhysicalDevice,
+        info: &AttachmentInfo,
+        error: fn(vk::ErrorCode) -> RenderingError
+    ) -> RqResult<()>
+    {
+        let image_info = vk::ImageCreateInfo::builder()
+            .image_type(vk::ImageType::_2D)
+            .extent(vk::Extent3D { width: info.extent.width, height: info.extent.height, depth: 1 })
+            .mip_levels(1)
+            .array_layers(1)
+            .format(info.format)
+            .tiling(vk::ImageTiling::OPTIMAL)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .usage(info.usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .samples(info.samples);
+
+        let image = device.create_image(&image_info, None)
+            .map_err(error)?;
+        self.images.push(image);
+
+        //Вложения пересоздаются вместе с цепочкой обмена и получают отдельные
+        //выделения мимо MemoryAllocator. Для transient вложений подходит "ленивая" память
+        let requirements = device.get_image_memory_requirements(image);
+        let memory_type_index = find_memory_type_index(
+            instance,
+            physical_device,
+            requirements,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL | vk::MemoryPropertyFlags::LAZILY_ALLOCATED
+        ).or_else(|_| find_memory_type_index(
+            instance,
+            physical_device,
+            requirements,
+            vk::MemoryPropertyFlags::DEVICE_LOCAL
+        ))?;
+
+        let memory_info = vk::MemoryAllocateInfo::builder()
+            .allocation_size(requirements.size)
+            .memory_type_index(memory_type_index);
+
+        let memory = device.allocate_memory(&memory_info, None)
+            .map_err(error)?;
+        self.memories.push(memory);
+
+        device.bind_image_memory(image, memory, 0)
+            .map_err(error)?;
+
+        let subresource_range = vk::ImageSubresourceRange::builder()
+            .aspect_mask(info.aspect_mask)
+            .base_mip_level(0)
+            .level_count(1)
+            .base_array_layer(0)
+            .layer_count(1)
+            .build();
+
+        let view_info = vk::ImageViewCreateInfo::builder()
+            .image(image)
+            .view_type(vk::ImageViewType::_2D)
+            .format(info.format)
+            .subresource_range(subresource_range);
+
+        let image_view = device.create_image_view(&view_info, None)
+            .map_err(error)?;
+        self.image_views.push(image_view);
+
+        Result::Ok(())
+    }
+}
+
+pub struct AttachmentInfo {
+    pub extent: vk::Extent2D,
+    pub format: vk::Format,
+    pub samples: vk::SampleCountFlags,
+    pub usage: vk::ImageUsageFlags,
+    pub aspect_mask: vk::ImageAspectFlags
+}
+
+//Многовыборочные изображения цвета, которые затем
+//разрешаются (resolve) в изображения swap chain
+#[derive(Debug)]
+pub struct ColorBuffer {
+    pub format: vk::Format,
+    pub samples: vk::SampleCountFlags,
+    pub min_sample_shading: Option<f32>,
+    pub attachments: AttachmentImages
+}
+
+impl ColorBuffer {
+    pub unsafe fn create(
+        instance: &Instance,
+        device: &Device,
+        physical_device: vk::PhysicalDevice,
+        swap_chain: &SwapChainData,
+        samples: vk::SampleCountFlags,
+        min_sample_shading: Option<f32>
+    ) -> RqResult<Self>
+    {
+        let min_sample_shading = min_sample_shading.filter(|_| {
+            let enabled = get_enabled_features(instance, physical_device)
+                .sample_rate_shading == vk::TRUE;
+            if !enabled {
+                warn!("sample rate shading is not supported and will be disabled");
+            }
+            enabled
+        });
+
+        //При одной выборке рисуем сразу в swap chain
+        let attachments = if samples == vk::SampleCountFlags::_1 {
+            AttachmentImages::default()
+        } else {
+            let info = AttachmentInfo {
+                extent: swap_chain.extent,
+                format: swap_chain.format,
+                samples,
+                usage: vk::ImageUsageFlags::COLOR_ATTACHMENT
+                    | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT,
+                aspect_mask: vk::ImageAspectFlags::COLOR
+            };
+            AttachmentImages::create(
+                instance, device, physical_device,
+                &info, swap_chain.images.len(), CreateColorBufferError
+            )?
+        };
+
+        Result::Ok(Self {
+            format: swap_chain.format,
+            samples,
+            min_sample_shading,
+            attachments
+        })
+    }
+
+    pub fn is_multisampled(&self) -> bool {
+        self.samples != vk::SampleCountFlags::_1
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        self.attachments.destroy(device);
+    }
+}
+
+//Изображение глубины на каждое изображение swap chain
+#[derive(Debug)]
+pub struct DepthBuffer {
+    pub format: vk::Format,
+    pub use_stencil: bool,
+    pub samples: vk::SampleCountFlags,
+    pub attachments: AttachmentImages
+}
+
+impl DepthBuffer {
+    pub unsafe fn create(
+        instance: &Instance,
+        device: &Device,
+        physical_device: vk::PhysicalDevice,
+        swap_chain: &SwapChainData,
+        samples: vk::SampleCountFlags,
+        use_stencil: bool
+    ) -> RqResult<Self>
+    {
+        let format = choose_depth_format(instance, physical_device, use_stencil)
+            .ok_or(SupportError("suitable depth format was not found"))?;
+
+        let info = AttachmentInfo {
+            extent: swap_chain.extent,
+            format,
+            samples,
+            //Основной проход не сохраняет глубину (store_op DONT_CARE),
+            //поэтому ей подходит "ленивая" память, как и многовыборочному цвету
+            usage: vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT
+                | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT,
+            aspect_mask: depth_aspect_mask(use_stencil)
+        };
+
+        let attachments = AttachmentImages::create(
+            instance, device, physical_device,
+            &info, swap_chain.images.len(), CreateDepthBufferError
+        )?;
+
+        Result::Ok(Self {
+            format,
+            use_stencil,
+            samples,
+            attachments
+        })
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        self.attachments.destroy(device);
+    }
+}
+
+//Наибольшее число выборок, не превышающее запрошенное и
+//поддерживаемое одновременно для цвета и глубины
+pub unsafe fn choose_sample_count(
+    instance: &Instance,
+    physical_device: vk::PhysicalDevice,
+    requested: vk::SampleCountFlags
+) -> vk::SampleCountFlags
+{
+    let limits = instance
+        .get_physical_device_properties(physical_device)
+        .limits;
+    let supported = limits.framebuffer_color_sample_counts
+        & limits.framebuffer_depth_sample_counts;
+
+    let samples = SAMPLE_COUNTS.iter()
+        .copied()
+        .filter(|samples| samples.bits() <= requested.bits())
+        .find(|samples| supported.contains(*samples))
+        .unwrap_or(vk::SampleCountFlags::_1);
+
+    if samples != requested {
+        warn!("{:?} samples are not supported, {:?} will be used", requested, samples);
+    }
+
+    samples
+}
+
+fn depth_aspect_mask(use_stencil: bool) -> vk::ImageAspectFlags {
+    if use_stencil {
+        vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+    } else {
+        vk::ImageAspectFlags::DEPTH
+    }
+}
+
+//Первый формат из списка, который можно использовать как вложение глубины
+pub unsafe fn choose_depth_format(
+    instance: &Instance,
+    physical_device: vk::PhysicalDevice,
+    use_stencil: bool
+) -> Option<vk::Format>
+{
+    let candidates = if use_stencil {
+        DEPTH_STENCIL_FORMATS
+    } else {
+        DEPTH_FORMATS
+    };
+
+    candidates.iter()
+        .copied()
+        .find(|format| {
+            let properties = instance
+                .get_physical_device_format_properties(physical_device, *format);
+            properties.optimal_tiling_features
+                .contains(vk::FormatFeatureFlags::DEPTH_STENCIL_ATTACHMENT)
+        })
+}
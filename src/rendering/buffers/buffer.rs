@@ -0,0 +1,54 @@
+use vulkanalia::prelude::v1_0::*;
+
+use crate::rendering::{Allocation, MemoryAllocator, MemoryUsage, RqResult};
+use crate::rendering::RenderingError::CreateBufferError;
+
+//Буфер с участком памяти из общего распределителя
+#[derive(Debug)]
+pub struct Buffer {
+    pub buffer: vk::Buffer,
+    pub allocation: Allocation,
+    pub size: vk::DeviceSize
+}
+
+impl Buffer {
+    pub unsafe fn create(
+        device: &Device,
+        allocator: &mut MemoryAllocator,
+        size: vk::DeviceSize,
+        usage: vk::BufferUsageFlags,
+        memory_usage: MemoryUsage
+    ) -> RqResult<Self> {
+        let buffer_info = vk::BufferCreateInfo::builder()
+            .size(size)
+            .usage(usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE);
+
+        let buffer = device.create_buffer(&buffer_info, None)
+            .map_err(CreateBufferError)?;
+
+        let allocation = match allocator.allocate_buffer(device, buffer, memory_usage) {
+            Ok(allocation) => allocation,
+            Err(err) => {
+                device.destroy_buffer(buffer, None);
+                return Err(err);
+            }
+        };
+
+        Result::Ok(Self {
+            buffer,
+            allocation,
+            size
+        })
+    }
+
+    //None для памяти, недоступной хосту
+    pub fn mapped_ptr(&self) -> Option<*mut u8> {
+        self.allocation.mapped_ptr()
+    }
+
+    pub unsafe fn destroy(&self, device: &Device, allocator: &mut MemoryAllocator) {
+        device.destroy_buffer(self.buffer, None);
+        allocator.free(device, &self.allocation);
+    }
+}
@@ -0,0 +1,190 @@
+use std::mem::size_of;
+
+use bytemuck::cast_slice;
+use vulkanalia::prelude::v1_0::*;
+
+use crate::rendering::{get_enabled_features, Buffer, DescriptorWriter, MemoryAllocator, RqResult, UploadContext};
+use crate::rendering::RenderingError::CreateMeshError;
+
+const COMMAND_STRIDE: u32 = size_of::<vk::DrawIndexedIndirectCommand>() as u32;
+
+//Возможности устройства для косвенной отрисовки
+#[derive(Debug, Clone, Copy, Default)]
+pub struct IndirectFeatures {
+    //Несколько команд одним вызовом, иначе по вызову на команду
+    pub multi_draw: bool,
+    //first_instance != 0 в командах
+    pub first_instance: bool,
+    pub max_draw_count: u32
+}
+
+impl IndirectFeatures {
+    pub unsafe fn query(instance: &Instance, physical_device: vk::PhysicalDevice) -> Self {
+        let features = get_enabled_features(instance, physical_device);
+        let limits = instance.get_physical_device_properties(physical_device).limits;
+
+        Self {
+            multi_draw: features.multi_draw_indirect == vk::TRUE,
+            first_instance: features.draw_indirect_first_instance == vk::TRUE,
+            max_draw_count: limits.max_draw_indirect_count
+        }
+    }
+}
+
+//Аргументы cmd_draw_indexed_indirect в памяти устройства. Буфер доступен
+//как STORAGE_BUFFER, поэтому команды может перезаписать вычислительный проход
+#[derive(Debug)]
+pub struct IndirectBuffer {
+    pub buffer: Buffer,
+    pub count: u32,
+    //Конец диапазона экземпляров, которые читают команды
+    pub instance_end: u32,
+    multi_draw: bool
+}
+
+impl IndirectBuffer {
+    //index_count - число индексов сетки, instance_count - число экземпляров
+    //в InstanceBuffer сетки, если он есть. Команды не должны выходить за них.
+    //vertex_offset не проверяется: индексы со смещением должны оставаться
+    //в буфере вершин, за это отвечает вызывающий код
+    pub unsafe fn new(
+        context: &mut UploadContext,
+        features: IndirectFeatures,
+        commands: &[vk::DrawIndexedIndirectCommand],
+        index_count: u32,
+        instance_count: Option<u32>
+    ) -> RqResult<Self> {
+        let instance_end = validate_commands(features, commands, index_count, instance_count)?;
+
+        //Команда - пять 32-битных полей без промежутков
+        let words: Vec<[u32; 5]> = commands.iter()
+            .map(|command| [
+                command.index_count,
+                command.instance_count,
+                command.first_index,
+                command.vertex_offset as u32,
+                command.first_instance
+            ])
+            .collect();
+
+        let buffer = context.create_device_buffer(
+            cast_slice(&words),
+            vk::BufferUsageFlags::INDIRECT_BUFFER | vk::BufferUsageFlags::STORAGE_BUFFER
+        )?;
+
+        Result::Ok(Self {
+            buffer,
+            count: commands.len() as u32,
+            instance_end,
+            multi_draw: features.multi_draw && commands.len() as u32 <= features.max_draw_count
+        })
+    }
+
+    pub unsafe fn draw(&self, device: &Device, command_buffer: vk::CommandBuffer) {
+        if self.multi_draw {
+            device.cmd_draw_indexed_indirect(command_buffer, self.buffer.buffer, 0, self.count, COMMAND_STRIDE);
+            return;
+        }
+
+        for command in 0..self.count {
+            let offset = command as vk::DeviceSize * COMMAND_STRIDE as vk::DeviceSize;
+            device.cmd_draw_indexed_indirect(command_buffer, self.buffer.buffer, offset, 1, COMMAND_STRIDE);
+        }
+    }
+
+    pub fn descriptor_writer(&self, binding: u32) -> DescriptorWriter {
+        let mut writer = DescriptorWriter::new();
+        writer.write_buffer(
+            binding,
+            vk::DescriptorType::STORAGE_BUFFER,
+            self.buffer.buffer,
+            0,
+            self.buffer.size
+        );
+        writer
+    }
+
+    pub unsafe fn destroy(&self, device: &Device, allocator: &mut MemoryAllocator) {
+        self.buffer.destroy(device, allocator);
+    }
+}
+
+//Возвращает конец диапазона экземпляров, которые читают команды
+fn validate_commands(
+    features: IndirectFeatures,
+    commands: &[vk::DrawIndexedIndirectCommand],
+    index_count: u32,
+    instance_count: Option<u32>
+) -> RqResult<u32> {
+    if commands.is_empty() {
+        return Err(CreateMeshError(String::from("indirect buffer needs at least one command")));
+    }
+
+    let mut instance_end = 0;
+    for (index, command) in commands.iter().enumerate() {
+        if command.first_index as u64 + command.index_count as u64 > index_count as u64 {
+            return Err(CreateMeshError(format!(
+                "indirect command {} reads indices [{}, {}), mesh has {}",
+                index, command.first_index, command.first_index as u64 + command.index_count as u64, index_count
+            )));
+        }
+
+        if command.first_instance != 0 && !features.first_instance {
+            return Err(CreateMeshError(format!(
+                "indirect command {} uses first_instance {}, device does not support draw_indirect_first_instance",
+                index, command.first_instance
+            )));
+        }
+
+        let end = command.first_instance.checked_add(command.instance_count)
+            .ok_or_else(|| CreateMeshError(format!(
+                "indirect command {}: first_instance {} + instance_count {} overflows",
+                index, command.first_instance, command.instance_count
+            )))?;
+        if let Some(instance_count) = instance_count.filter(|instance_count| end > *instance_count) {
+            return Err(CreateMeshError(format!(
+                "indirect command {} reads instances [{}, {}), mesh has {}",
+                index, command.first_instance, end, instance_count
+            )));
+        }
+        instance_end = instance_end.max(end);
+    }
+
+    Result::Ok(instance_end)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FEATURES: IndirectFeatures = IndirectFeatures {
+        multi_draw: true,
+        first_instance: true,
+        max_draw_count: 1
+    };
+
+    fn command(first_instance: u32, instance_count: u32) -> vk::DrawIndexedIndirectCommand {
+        vk::DrawIndexedIndirectCommand {
+            index_count: 3,
+            instance_count,
+            first_index: 0,
+            vertex_offset: 0,
+            first_instance
+        }
+    }
+
+    #[test]
+    fn commands_must_stay_within_instances() {
+        let commands = [command(0, 2), command(2, 2)];
+        assert_eq!(validate_commands(FEATURES, &commands, 3, Some(4)).unwrap(), 4);
+        assert!(validate_commands(FEATURES, &commands, 3, Some(3)).is_err());
+        assert!(validate_commands(FEATURES, &[command(u32::MAX, 1)], 3, None).is_err());
+
+        let past_indices = vk::DrawIndexedIndirectCommand { first_index: u32::MAX, ..command(0, 1) };
+        assert!(validate_commands(FEATURES, &[past_indices], 3, None).is_err());
+
+        //Без буфера экземпляров данные экземпляров не читаются
+        assert_eq!(validate_commands(FEATURES, &commands, 3, None).unwrap(), 4);
+        assert!(validate_commands(IndirectFeatures::default(), &commands, 3, None).is_err());
+    }
+}
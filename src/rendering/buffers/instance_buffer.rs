@@ -0,0 +1,84 @@
+use std::mem::{offset_of, size_of};
+
+use bytemuck::{cast_slice, Pod, Zeroable};
+use vulkanalia::prelude::v1_0::*;
+
+use crate::rendering::{Buffer, Mat4, MemoryAllocator, RqResult, UploadContext};
+use crate::rendering::{VertexAttributeDescription, VertexBindingDescription, VertexFormat, VertexInputRate};
+use crate::rendering::RenderingError::CreateMeshError;
+
+//Данные, которые вершинный шейдер читает один раз на экземпляр.
+//Копируются в буфер побайтно, поэтому тип не должен содержать байт выравнивания
+pub trait InstanceData: Pod {
+    fn attribute_descriptions(binding: u32) -> Vec<VertexAttributeDescription>;
+
+    fn binding_description(binding: u32) -> VertexBindingDescription {
+        VertexBindingDescription {
+            binding,
+            stride: size_of::<Self>() as u32,
+            input_rate: VertexInputRate::Instance
+        }
+    }
+}
+
+//Матрица модели экземпляра по столбцам в location 4..7,
+//после атрибутов ModelVertex
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct InstanceTransform {
+    pub model: [[f32; 4]; 4]
+}
+
+//Только f32 без промежутков
+unsafe impl Zeroable for InstanceTransform {}
+unsafe impl Pod for InstanceTransform {}
+
+impl From<Mat4> for InstanceTransform {
+    fn from(model: Mat4) -> Self {
+        Self { model: model.to_cols_array_2d() }
+    }
+}
+
+impl InstanceData for InstanceTransform {
+    fn attribute_descriptions(binding: u32) -> Vec<VertexAttributeDescription> {
+        (0..4)
+            .map(|column| VertexAttributeDescription {
+                location: 4 + column,
+                binding,
+                format: VertexFormat::Vec4,
+                offset: (offset_of!(InstanceTransform, model) + column as usize * size_of::<[f32; 4]>()) as u32
+            })
+            .collect()
+    }
+}
+
+//Данные экземпляров в памяти устройства
+#[derive(Debug)]
+pub struct InstanceBuffer {
+    pub buffer: Buffer,
+    pub stride: u32,
+    //Атрибуты без учета binding, сверяются с входами конвейера
+    pub attributes: Vec<VertexAttributeDescription>,
+    pub count: u32
+}
+
+impl InstanceBuffer {
+    pub unsafe fn new<T: InstanceData>(context: &mut UploadContext, instances: &[T]) -> RqResult<Self> {
+        if instances.is_empty() {
+            return Err(CreateMeshError(String::from("instance buffer needs at least one instance")));
+        }
+
+        let buffer = context.create_device_buffer(cast_slice(instances), vk::BufferUsageFlags::VERTEX_BUFFER)?;
+
+        Result::Ok(Self {
+            buffer,
+            stride: T::binding_description(0).stride,
+            attributes: T::attribute_descriptions(0),
+            count: instances.len() as u32
+        })
+    }
+
+    pub unsafe fn destroy(&self, device: &Device, allocator: &mut MemoryAllocator) {
+        self.buffer.destroy(device, allocator);
+    }
+}
@@ -0,0 +1,148 @@
+use bytemuck::cast_slice;
+use vulkanalia::prelude::v1_0::*;
+
+use super::{Index, IndirectBuffer, InstanceBuffer, Vertex};
+use crate::rendering::{Buffer, GraphicsPipeline, MemoryAllocator, RqResult, UploadContext, VertexAttributeDescription};
+use crate::rendering::RenderingError::CreateMeshError;
+
+//Индекс сетки в очереди, действителен все время жизни очереди
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct MeshHandle(pub(crate) usize);
+
+//Вершины и индексы в памяти устройства
+#[derive(Debug)]
+pub struct Mesh {
+    pub vertices: Buffer,
+    pub indices: Buffer,
+    pub vertex_stride: u32,
+    //Атрибуты вершин в binding 0, сверяются с входами конвейера
+    pub vertex_attributes: Vec<VertexAttributeDescription>,
+    pub index_count: u32,
+    pub index_type: vk::IndexType,
+    //Данные экземпляров, None - один экземпляр
+    pub instances: Option<InstanceBuffer>,
+    //Аргументы отрисовки в памяти устройства вместо index_count
+    pub indirect: Option<IndirectBuffer>
+}
+
+impl Mesh {
+    pub unsafe fn new<V: Vertex, I: Index>(context: &mut UploadContext, vertices: &[V], indices: &[I]) -> RqResult<Self> {
+        if vertices.is_empty() || indices.is_empty() {
+            return Err(CreateMeshError(format!(
+                "mesh needs vertices and indices, got {} and {}",
+                vertices.len(), indices.len()
+            )));
+        }
+
+        if let Some(index) = indices.iter().map(|index| (*index).into()).find(|index| *index as usize >= vertices.len()) {
+            return Err(CreateMeshError(format!(
+                "index {} is out of {} vertices",
+                index, vertices.len()
+            )));
+        }
+
+        let vertices_buffer = context.create_device_buffer(cast_slice(vertices), vk::BufferUsageFlags::VERTEX_BUFFER)?;
+        let indices_buffer = match context.create_device_buffer(cast_slice(indices), vk::BufferUsageFlags::INDEX_BUFFER) {
+            Ok(buffer) => buffer,
+            Err(err) => {
+                vertices_buffer.destroy(context.device, context.allocator);
+                return Err(err);
+            }
+        };
+
+        Result::Ok(Self {
+            vertices: vertices_buffer,
+            indices: indices_buffer,
+            vertex_stride: V::binding_description(0).stride,
+            vertex_attributes: V::attribute_descriptions(0),
+            index_count: indices.len() as u32,
+            index_type: I::INDEX_TYPE,
+            instances: None,
+            indirect: None
+        })
+    }
+
+    //instance_binding - binding конвейера с данными экземпляров, None - один экземпляр
+    pub unsafe fn bind(&self, device: &Device, command_buffer: vk::CommandBuffer, instance_binding: Option<u32>) {
+        device.cmd_bind_vertex_buffers(command_buffer, 0, &[self.vertices.buffer], &[0]);
+        if let (Some(binding), Some(instances)) = (instance_binding, &self.instances) {
+            device.cmd_bind_vertex_buffers(command_buffer, binding, &[instances.buffer.buffer], &[0]);
+        }
+        device.cmd_bind_index_buffer(command_buffer, self.indices.buffer, 0, self.index_type);
+    }
+
+    //Косвенные команды задают число экземпляров сами
+    pub unsafe fn draw(&self, device: &Device, command_buffer: vk::CommandBuffer, instance_binding: Option<u32>) {
+        self.bind(device, command_buffer, instance_binding);
+
+        if let Some(indirect) = &self.indirect {
+            indirect.draw(device, command_buffer);
+            return;
+        }
+
+        let instance_count = match (instance_binding, &self.instances) {
+            (Some(_), Some(instances)) => instances.count,
+            _ => 1
+        };
+        device.cmd_draw_indexed(command_buffer, self.index_count, instance_count, 0, 0, 0);
+    }
+
+    pub unsafe fn destroy(&self, device: &Device, allocator: &mut MemoryAllocator) {
+        self.vertices.destroy(device, allocator);
+        self.indices.destroy(device, allocator);
+        if let Some(instances) = &self.instances {
+            instances.destroy(device, allocator);
+        }
+        if let Some(indirect) = &self.indirect {
+            indirect.destroy(device, allocator);
+        }
+    }
+}
+
+impl GraphicsPipeline {
+    //Сетка подходит, если ее вершины совпадают по шагу с binding 0 конвейера
+    //и содержат каждый его атрибут с тем же форматом и смещением,
+    //а для конвейера с данными экземпляров у нее есть такие же экземпляры
+    pub fn accepts(&self, mesh: &Mesh) -> bool {
+        let vertices = self.vertex_bindings.iter()
+            .find(|binding| binding.binding == 0)
+            .is_some_and(|binding| binding.stride == mesh.vertex_stride
+                && self.provides_attributes(0, &mesh.vertex_attributes));
+
+        let instances = match self.instance_binding() {
+            Some(binding) => mesh.instances.as_ref().is_some_and(|instances| instances.stride == binding.stride
+                && self.provides_attributes(binding.binding, &instances.attributes)),
+            None => true
+        };
+
+        vertices && instances
+    }
+
+    //Все атрибуты конвейера из binding есть в буфере, binding у атрибутов буфера не учитывается
+    fn provides_attributes(&self, binding: u32, attributes: &[VertexAttributeDescription]) -> bool {
+        self.vertex_attributes.iter()
+            .filter(|attribute| attribute.binding == binding)
+            .all(|attribute| attributes.iter().any(|provided| provided.location == attribute.location
+                && provided.offset == attribute.offset
+                && vk::Format::from(provided.format) == attribute.format))
+    }
+
+    pub fn instance_binding(&self) -> Option<&vk::VertexInputBindingDescription> {
+        self.vertex_bindings.iter()
+            .find(|binding| binding.input_rate == vk::VertexInputRate::INSTANCE)
+    }
+
+    //Рисует подходящие сетки уже привязанным конвейером. Конвейер без
+    //вершинных входов строит треугольник в шейдере по gl_VertexIndex
+    pub unsafe fn draw_meshes(&self, device: &Device, command_buffer: vk::CommandBuffer, meshes: &[Mesh]) {
+        if self.vertex_bindings.is_empty() {
+            device.cmd_draw(command_buffer, 3, 1, 0, 0);
+            return;
+        }
+
+        let instance_binding = self.instance_binding().map(|binding| binding.binding);
+        for mesh in meshes.iter().filter(|mesh| self.accepts(mesh)) {
+            mesh.draw(device, command_buffer, instance_binding);
+        }
+    }
+}
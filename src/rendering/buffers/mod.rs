@@ -0,0 +1,13 @@
+mod buffer;
+mod uniform_buffer;
+mod vertex;
+mod mesh;
+mod instance_buffer;
+mod indirect_buffer;
+
+pub use buffer::*;
+pub use uniform_buffer::*;
+pub use vertex::*;
+pub use mesh::*;
+pub use instance_buffer::*;
+pub use indirect_buffer::*;
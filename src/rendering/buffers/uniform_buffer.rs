@@ -0,0 +1,98 @@
+use std::ptr;
+
+use vulkanalia::prelude::v1_0::*;
+
+use super::Buffer;
+use crate::rendering::{DescriptorAllocator, DescriptorWriter, FrameDescriptorSets, MemoryAllocator, MemoryUsage, RqResult};
+
+//Копия данных на каждый кадр в одном буфере. Смещения копий выровнены
+//по min_uniform_buffer_offset_alignment, память отображена все время жизни
+#[derive(Debug)]
+pub struct UniformBuffer {
+    buffer: Buffer,
+    mapped: *mut u8,
+    data_size: vk::DeviceSize,
+    copy_size: vk::DeviceSize,
+    copies: usize
+}
+
+impl UniformBuffer {
+    pub unsafe fn create(
+        instance: &Instance,
+        device: &Device,
+        physical_device: vk::PhysicalDevice,
+        allocator: &mut MemoryAllocator,
+        data_size: vk::DeviceSize,
+        copies: usize
+    ) -> RqResult<Self> {
+        let alignment = instance.get_physical_device_properties(physical_device)
+            .limits
+            .min_uniform_buffer_offset_alignment
+            .max(1);
+        let copy_size = data_size.div_ceil(alignment) * alignment;
+
+        let buffer = Buffer::create(
+            device,
+            allocator,
+            copy_size * copies as vk::DeviceSize,
+            vk::BufferUsageFlags::UNIFORM_BUFFER,
+            MemoryUsage::Upload
+        )?;
+        let mapped = buffer.mapped_ptr().expect("upload memory is mapped");
+
+        Result::Ok(Self {
+            buffer,
+            mapped,
+            data_size,
+            copy_size,
+            copies
+        })
+    }
+
+    pub fn data_size(&self) -> vk::DeviceSize {
+        self.data_size
+    }
+
+    pub fn offset(&self, copy: usize) -> vk::DeviceSize {
+        self.copy_size * copy as vk::DeviceSize
+    }
+
+    //Копия не должна читаться командами, которые еще выполняются.
+    //Память HOST_COHERENT, сбрасывать кэш не нужно
+    pub unsafe fn write(&self, copy: usize, data: &[u8]) {
+        assert!(copy < self.copies && data.len() as vk::DeviceSize <= self.data_size);
+        ptr::copy_nonoverlapping(data.as_ptr(), self.mapped.add(self.offset(copy) as usize), data.len());
+    }
+
+    pub fn descriptor_writer(&self, binding: u32, copy: usize) -> DescriptorWriter {
+        let mut writer = DescriptorWriter::new();
+        writer.write_buffer(
+            binding,
+            vk::DescriptorType::UNIFORM_BUFFER,
+            self.buffer.buffer,
+            self.offset(copy),
+            self.data_size
+        );
+        writer
+    }
+
+    //Набор на каждую копию, binding набора кадра указывает на копию этого кадра
+    pub unsafe fn allocate_descriptor_sets(
+        &self,
+        device: &Device,
+        allocator: &mut DescriptorAllocator,
+        layout: vk::DescriptorSetLayout,
+        binding: u32
+    ) -> RqResult<FrameDescriptorSets> {
+        let sets = FrameDescriptorSets::allocate(device, allocator, layout, self.copies)?;
+        for copy in 0..self.copies {
+            sets.write_frame(device, copy, &self.descriptor_writer(binding, copy));
+        }
+
+        Result::Ok(sets)
+    }
+
+    pub unsafe fn destroy(&self, device: &Device, allocator: &mut MemoryAllocator) {
+        self.buffer.destroy(device, allocator);
+    }
+}
@@ -0,0 +1,56 @@
+use std::mem::size_of;
+
+use bytemuck::Pod;
+use vulkanalia::prelude::v1_0::*;
+
+use super::InstanceData;
+use crate::rendering::{VertexAttributeDescription, VertexBindingDescription, VertexInputDescription, VertexInputRate};
+
+//Раскладка вершины в буфере. Атрибуты описываются через offset_of!,
+//location должны совпадать с входами вершинного шейдера.
+//Вершины копируются в буфер побайтно, поэтому тип не должен содержать байт выравнивания
+pub trait Vertex: Pod {
+    fn attribute_descriptions(binding: u32) -> Vec<VertexAttributeDescription>;
+
+    fn binding_description(binding: u32) -> VertexBindingDescription {
+        VertexBindingDescription {
+            binding,
+            stride: size_of::<Self>() as u32,
+            input_rate: VertexInputRate::Vertex
+        }
+    }
+}
+
+impl VertexInputDescription {
+    //Вершины в одном чередующемся буфере (binding 0)
+    pub fn from_vertex<V: Vertex>() -> Self {
+        Self {
+            bindings: vec![V::binding_description(0)],
+            attributes: V::attribute_descriptions(0)
+        }
+    }
+
+    //Вершины в binding 0, данные экземпляров в binding 1
+    pub fn from_vertex_and_instance<V: Vertex, I: InstanceData>() -> Self {
+        let mut attributes = V::attribute_descriptions(0);
+        attributes.extend(I::attribute_descriptions(1));
+
+        Self {
+            bindings: vec![V::binding_description(0), I::binding_description(1)],
+            attributes
+        }
+    }
+}
+
+//Тип элемента индексного буфера
+pub trait Index: Pod + Into<u32> {
+    const INDEX_TYPE: vk::IndexType;
+}
+
+impl Index for u16 {
+    const INDEX_TYPE: vk::IndexType = vk::IndexType::UINT16;
+}
+
+impl Index for u32 {
+    const INDEX_TYPE: vk::IndexType = vk::IndexType::UINT32;
+}
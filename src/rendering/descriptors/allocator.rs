@@ -0,0 +1,115 @@
+use vulkanalia::prelude::v1_0::*;
+
+use crate::rendering::RqResult;
+use crate::rendering::RenderingError::{AllocateDescriptorSetError, CreateDescriptorPoolError};
+
+//Сколько дескрипторов каждого типа приходится на один набор в новом пуле
+pub const DEFAULT_POOL_RATIOS: &[(vk::DescriptorType, f32)] = &[
+    (vk::DescriptorType::UNIFORM_BUFFER, 2.0),
+    (vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC, 1.0),
+    (vk::DescriptorType::STORAGE_BUFFER, 1.0),
+    (vk::DescriptorType::COMBINED_IMAGE_SAMPLER, 2.0),
+    (vk::DescriptorType::SAMPLED_IMAGE, 1.0),
+    (vk::DescriptorType::SAMPLER, 1.0),
+    (vk::DescriptorType::STORAGE_IMAGE, 1.0),
+    (vk::DescriptorType::INPUT_ATTACHMENT, 1.0)
+];
+
+const MAX_SETS_PER_POOL: u32 = 4096;
+
+//Выделяет наборы из пулов. Когда пул заканчивается, создается новый,
+//в полтора раза больше предыдущего
+#[derive(Debug)]
+pub struct DescriptorAllocator {
+    ratios: Vec<(vk::DescriptorType, f32)>,
+    sets_per_pool: u32,
+    //Последний - текущий
+    ready_pools: Vec<vk::DescriptorPool>,
+    full_pools: Vec<vk::DescriptorPool>
+}
+
+impl DescriptorAllocator {
+    pub fn new(initial_sets: u32, ratios: &[(vk::DescriptorType, f32)]) -> Self {
+        Self {
+            ratios: ratios.to_vec(),
+            sets_per_pool: initial_sets.clamp(1, MAX_SETS_PER_POOL),
+            ready_pools: Vec::new(),
+            full_pools: Vec::new()
+        }
+    }
+
+    pub unsafe fn allocate(&mut self, device: &Device, layout: vk::DescriptorSetLayout) -> RqResult<vk::DescriptorSet> {
+        let mut pool = self.current_pool(device)?;
+
+        let layouts = &[layout];
+        let mut info = vk::DescriptorSetAllocateInfo::builder()
+            .descriptor_pool(pool)
+            .set_layouts(layouts);
+
+        let set = match device.allocate_descriptor_sets(&info) {
+            Err(vk::ErrorCode::OUT_OF_POOL_MEMORY) | Err(vk::ErrorCode::FRAGMENTED_POOL) => {
+                self.ready_pools.pop();
+                self.full_pools.push(pool);
+                pool = self.current_pool(device)?;
+                info = info.descriptor_pool(pool);
+                device.allocate_descriptor_sets(&info)
+            }
+            result => result
+        };
+
+        set.map(|sets| sets[0])
+            .map_err(AllocateDescriptorSetError)
+    }
+
+    //Все выделенные наборы становятся недействительными,
+    //пулы остаются для повторного использования
+    pub unsafe fn reset(&mut self, device: &Device) -> RqResult<()> {
+        for pool in self.ready_pools.iter().chain(&self.full_pools) {
+            device.reset_descriptor_pool(*pool, vk::DescriptorPoolResetFlags::empty())
+                .map_err(CreateDescriptorPoolError)?;
+        }
+        self.ready_pools.append(&mut self.full_pools);
+
+        Result::Ok(())
+    }
+
+    pub unsafe fn destroy(&mut self, device: &Device) {
+        for pool in self.ready_pools.drain(..).chain(self.full_pools.drain(..)) {
+            device.destroy_descriptor_pool(pool, None);
+        }
+    }
+
+    unsafe fn current_pool(&mut self, device: &Device) -> RqResult<vk::DescriptorPool> {
+        if let Some(pool) = self.ready_pools.last() {
+            return Result::Ok(*pool);
+        }
+
+        let pool = self.create_pool(device)?;
+        self.sets_per_pool = (self.sets_per_pool + self.sets_per_pool / 2).min(MAX_SETS_PER_POOL);
+        self.ready_pools.push(pool);
+
+        Result::Ok(pool)
+    }
+
+    unsafe fn create_pool(&self, device: &Device) -> RqResult<vk::DescriptorPool> {
+        let pool_sizes: Vec<vk::DescriptorPoolSize> = self.ratios.iter()
+            .map(|(descriptor_type, ratio)| vk::DescriptorPoolSize::builder()
+                .type_(*descriptor_type)
+                .descriptor_count(((ratio * self.sets_per_pool as f32).ceil() as u32).max(1))
+                .build())
+            .collect();
+
+        let info = vk::DescriptorPoolCreateInfo::builder()
+            .max_sets(self.sets_per_pool)
+            .pool_sizes(&pool_sizes);
+
+        device.create_descriptor_pool(&info, None)
+            .map_err(CreateDescriptorPoolError)
+    }
+}
+
+impl Default for DescriptorAllocator {
+    fn default() -> Self {
+        Self::new(64, DEFAULT_POOL_RATIOS)
+    }
+}
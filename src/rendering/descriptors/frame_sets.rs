@@ -0,0 +1,48 @@
+use vulkanalia::prelude::v1_0::*;
+
+use super::{DescriptorAllocator, DescriptorWriter};
+use crate::rendering::RqResult;
+
+//По набору на каждый кадр в полете: пока один кадр выполняется,
+//набор следующего можно переписать. Номер кадра - frame_index
+//из PassContext или FrameContext
+#[derive(Debug, Clone)]
+pub struct FrameDescriptorSets {
+    pub layout: vk::DescriptorSetLayout,
+    sets: Vec<vk::DescriptorSet>
+}
+
+impl FrameDescriptorSets {
+    pub unsafe fn allocate(
+        device: &Device,
+        allocator: &mut DescriptorAllocator,
+        layout: vk::DescriptorSetLayout,
+        frames_count: usize
+    ) -> RqResult<Self> {
+        let sets = (0..frames_count)
+            .map(|_| allocator.allocate(device, layout))
+            .collect::<RqResult<Vec<_>>>()?;
+
+        Result::Ok(Self { layout, sets })
+    }
+
+    pub fn get(&self, frame: usize) -> vk::DescriptorSet {
+        self.sets[frame]
+    }
+
+    pub fn len(&self) -> usize {
+        self.sets.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.sets.is_empty()
+    }
+
+    pub unsafe fn write_frame(&self, device: &Device, frame: usize, writer: &DescriptorWriter) {
+        writer.update(device, self.sets[frame]);
+    }
+
+    pub unsafe fn write_all(&self, device: &Device, writer: &DescriptorWriter) {
+        self.sets.iter().for_each(|set| writer.update(device, *set));
+    }
+}
@@ -0,0 +1,83 @@
+use std::collections::{BTreeMap, HashMap};
+
+use vulkanalia::prelude::v1_0::*;
+
+use crate::rendering::RqResult;
+use crate::rendering::RenderingError::CreateDescriptorSetLayoutError;
+
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct LayoutKey(Vec<(u32, vk::DescriptorType, u32, vk::ShaderStageFlags)>);
+
+impl LayoutKey {
+    fn new(bindings: &[vk::DescriptorSetLayoutBinding]) -> Self {
+        let mut key: Vec<_> = bindings.iter()
+            .map(|binding| (binding.binding, binding.descriptor_type, binding.descriptor_count, binding.stage_flags))
+            .collect();
+        key.sort_by_key(|(binding, ..)| *binding);
+        Self(key)
+    }
+}
+
+//Одинаковые макеты разных конвейеров создаются один раз,
+//поэтому наборы, выделенные под один конвейер, совместимы с другими.
+//Макеты живут до уничтожения кэша
+#[derive(Debug, Default)]
+pub struct DescriptorLayoutCache {
+    layouts: HashMap<LayoutKey, vk::DescriptorSetLayout>
+}
+
+impl DescriptorLayoutCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub unsafe fn get_or_create(
+        &mut self,
+        device: &Device,
+        bindings: &[vk::DescriptorSetLayoutBinding]
+    ) -> RqResult<vk::DescriptorSetLayout> {
+        let key = LayoutKey::new(bindings);
+        if let Some(layout) = self.layouts.get(&key) {
+            return Result::Ok(*layout);
+        }
+
+        let info = vk::DescriptorSetLayoutCreateInfo::builder()
+            .bindings(bindings);
+        let layout = device.create_descriptor_set_layout(&info, None)
+            .map_err(CreateDescriptorSetLayoutError)?;
+
+        self.layouts.insert(key, layout);
+        Result::Ok(layout)
+    }
+
+    //Наборы без привязок между используемыми получают пустой макет,
+    //номер набора в шейдере совпадает с индексом в макете конвейера
+    pub unsafe fn set_layouts(
+        &mut self,
+        device: &Device,
+        sets: &BTreeMap<u32, Vec<vk::DescriptorSetLayoutBinding>>
+    ) -> RqResult<Vec<vk::DescriptorSetLayout>> {
+        let sets_count = sets.keys().next_back().map_or(0, |set| set + 1);
+
+        (0..sets_count)
+            .map(|set| {
+                let bindings = sets.get(&set).map_or(&[][..], |bindings| &bindings[..]);
+                self.get_or_create(device, bindings)
+            })
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.layouts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.layouts.is_empty()
+    }
+
+    pub unsafe fn destroy(&mut self, device: &Device) {
+        for (_, layout) in self.layouts.drain() {
+            device.destroy_descriptor_set_layout(layout, None);
+        }
+    }
+}
@@ -0,0 +1,9 @@
+mod layout_cache;
+mod allocator;
+mod writer;
+mod frame_sets;
+
+pub use layout_cache::*;
+pub use allocator::*;
+pub use writer::*;
+pub use frame_sets::*;
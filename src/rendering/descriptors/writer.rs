@@ -0,0 +1,95 @@
+use vulkanalia::prelude::v1_0::*;
+
+#[derive(Debug, Clone, Copy)]
+struct BufferWrite {
+    binding: u32,
+    descriptor_type: vk::DescriptorType,
+    info: vk::DescriptorBufferInfo
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ImageWrite {
+    binding: u32,
+    descriptor_type: vk::DescriptorType,
+    info: vk::DescriptorImageInfo
+}
+
+//Собирает привязки и записывает их в набор одним вызовом
+#[derive(Debug, Clone, Default)]
+pub struct DescriptorWriter {
+    buffers: Vec<BufferWrite>,
+    images: Vec<ImageWrite>
+}
+
+impl DescriptorWriter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn write_buffer(
+        &mut self,
+        binding: u32,
+        descriptor_type: vk::DescriptorType,
+        buffer: vk::Buffer,
+        offset: vk::DeviceSize,
+        range: vk::DeviceSize
+    ) -> &mut Self {
+        self.buffers.push(BufferWrite {
+            binding,
+            descriptor_type,
+            info: vk::DescriptorBufferInfo::builder()
+                .buffer(buffer)
+                .offset(offset)
+                .range(range)
+                .build()
+        });
+        self
+    }
+
+    //sampler может быть null для SAMPLED_IMAGE и STORAGE_IMAGE
+    pub fn write_image(
+        &mut self,
+        binding: u32,
+        descriptor_type: vk::DescriptorType,
+        image_view: vk::ImageView,
+        sampler: vk::Sampler,
+        layout: vk::ImageLayout
+    ) -> &mut Self {
+        self.images.push(ImageWrite {
+            binding,
+            descriptor_type,
+            info: vk::DescriptorImageInfo::builder()
+                .image_view(image_view)
+                .sampler(sampler)
+                .image_layout(layout)
+                .build()
+        });
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.buffers.is_empty() && self.images.is_empty()
+    }
+
+    //Набор не должен использоваться командами, которые еще выполняются
+    pub unsafe fn update(&self, device: &Device, set: vk::DescriptorSet) {
+        let buffer_writes = self.buffers.iter()
+            .map(|write| vk::WriteDescriptorSet::builder()
+                .dst_set(set)
+                .dst_binding(write.binding)
+                .descriptor_type(write.descriptor_type)
+                .buffer_info(std::slice::from_ref(&write.info))
+                .build());
+
+        let image_writes = self.images.iter()
+            .map(|write| vk::WriteDescriptorSet::builder()
+                .dst_set(set)
+                .dst_binding(write.binding)
+                .descriptor_type(write.descriptor_type)
+                .image_info(std::slice::from_ref(&write.info))
+                .build());
+
+        let writes: Vec<vk::WriteDescriptorSet> = buffer_writes.chain(image_writes).collect();
+        device.update_descriptor_sets(&writes, &[] as &[vk::CopyDescriptorSet]);
+    }
+}
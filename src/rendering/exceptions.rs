@@ -17,13 +17,37 @@ pub enum RenderingError {
     CreateLogicalDeviceError(ErrorCode),
     ChoosePhysicalDeviceError(ErrorCode),
     CreateSwapChainError(ErrorCode),
+    CreateColorBufferError(ErrorCode),
+    CreateDepthBufferError(ErrorCode),
     CreatePipelineError(ErrorCode),
     LoadShadersError(String),
+    ReflectShaderError(String),
+    SpecializationError(String),
+    PushConstantsError(String),
+    LoadPipelineDescriptionError(String),
+    WatchShadersError(String),
 
     CreateRenderPassError(ErrorCode),
     CreatePipelineLayoutError(ErrorCode),
+    CreateDescriptorSetLayoutError(ErrorCode),
+    DescribeDescriptorSetsError(String),
+    CreateDescriptorPoolError(ErrorCode),
+    AllocateDescriptorSetError(ErrorCode),
+    CreateBufferError(ErrorCode),
+    UniformsError(String),
+    AllocateMemoryError(ErrorCode),
+    UploadError(ErrorCode),
+    CreateMeshError(String),
+    LoadModelError(String),
+    LoadTextureError(String),
+    CreateTextureError(ErrorCode),
+    CreateSamplerError(ErrorCode),
     BuildPipelinesError(ErrorCode),
+    CreatePipelineCacheError(ErrorCode),
+    SavePipelineCacheError(String),
     CreateFrameBufferError(ErrorCode),
+    CompileRenderGraphError(String),
+    CreateGraphImageError(ErrorCode),
     CreateCommandPoolError(ErrorCode),
     CreateCommandBufferError(ErrorCode),
 
@@ -0,0 +1,124 @@
+use bytemuck::Pod;
+use vulkanalia::prelude::v1_0::*;
+
+use super::{FrameDescriptorSets, GraphicsPipeline, Mesh, PipelineRegistry, RqResult, ViewportRect};
+use super::RenderingError::{CreateCommandBufferError, CreateCommandPoolError};
+
+//Пул и командный буфер на каждый кадр в полете. Пул сбрасывается целиком,
+//когда забор кадра уже пройден, поэтому буфер записывается заново каждый кадр
+#[derive(Debug)]
+pub struct FrameCommands {
+    pools: Vec<vk::CommandPool>,
+    command_buffers: Vec<vk::CommandBuffer>
+}
+
+impl FrameCommands {
+    pub unsafe fn create(device: &Device, queue_family: u32, flight_frames_count: u8) -> RqResult<Self> {
+        let pool_info = vk::CommandPoolCreateInfo::builder()
+            .flags(vk::CommandPoolCreateFlags::TRANSIENT)
+            .queue_family_index(queue_family);
+
+        let mut commands = Self {
+            pools: Vec::with_capacity(flight_frames_count as usize),
+            command_buffers: Vec::with_capacity(flight_frames_count as usize)
+        };
+        for _ in 0..flight_frames_count {
+            //При ошибке уже созданные пулы уничтожаются
+            if let Err(err) = commands.add_frame(device, &pool_info) {
+                commands.destroy(device);
+                return Err(err);
+            }
+        }
+
+        Result::Ok(commands)
+    }
+
+    unsafe fn add_frame(&mut self, device: &Device, pool_info: &vk::CommandPoolCreateInfo) -> RqResult<()> {
+        let pool = device.create_command_pool(pool_info, None)
+            .map_err(CreateCommandPoolError)?;
+        self.pools.push(pool);
+
+        let allocate_info = vk::CommandBufferAllocateInfo::builder()
+            .command_pool(pool)
+            .level(vk::CommandBufferLevel::PRIMARY)
+            .command_buffer_count(1);
+
+        let command_buffer = device.allocate_command_buffers(&allocate_info)
+            .map_err(CreateCommandBufferError)?[0];
+        self.command_buffers.push(command_buffer);
+
+        Result::Ok(())
+    }
+
+    //Вызывается после ожидания забора кадра frame_index
+    pub unsafe fn begin(&self, device: &Device, frame_index: usize) -> RqResult<vk::CommandBuffer> {
+        device.reset_command_pool(self.pools[frame_index], vk::CommandPoolResetFlags::empty())
+            .map_err(CreateCommandBufferError)?;
+
+        let command_buffer = self.command_buffers[frame_index];
+        let begin_info = vk::CommandBufferBeginInfo::builder()
+            .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+        device.begin_command_buffer(command_buffer, &begin_info)
+            .map_err(CreateCommandBufferError)?;
+
+        Result::Ok(command_buffer)
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        //Буферы освобождаются вместе с пулами
+        self.pools.iter().for_each(|pool| device.destroy_command_pool(*pool, None));
+    }
+}
+
+//Кадр, который записывает пользовательский код в RenderingQueue::render_with.
+//Основной проход уже начат, область вывода - все изображение
+pub struct FrameContext<'a> {
+    pub device: &'a Device,
+    pub command_buffer: vk::CommandBuffer,
+    pub image_index: usize,
+    //Кадр в полете, по нему выбираются копии uniform данных и собственные ресурсы кадра
+    pub frame_index: usize,
+    pub extent: vk::Extent2D,
+    //Секунды с прошлого кадра, 0 для первого
+    pub delta_time: f32,
+    pub pipelines: &'a PipelineRegistry,
+    pub meshes: &'a [Mesh],
+    pub viewports: &'a [ViewportRect],
+    //Uniform данные очереди, привязываются через GraphicsPipeline::bind_uniform_set
+    pub uniform_sets: Option<&'a FrameDescriptorSets>
+}
+
+impl FrameContext<'_> {
+    //Данные отрисовки (матрица, цвет) без буферов и дескрипторов, как в PassContext
+    pub unsafe fn push_constants<T: Pod>(&self, pipeline: &GraphicsPipeline, offset: u32, value: &T) -> RqResult<()> {
+        pipeline.push_constants(self.device, self.command_buffer, offset, value)
+    }
+
+    pub unsafe fn bind_descriptor_sets(&self, pipeline: &GraphicsPipeline, first_set: u32, sets: &[vk::DescriptorSet]) {
+        self.device.cmd_bind_descriptor_sets(
+            self.command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            pipeline.layout,
+            first_set,
+            sets,
+            &[]
+        );
+    }
+
+    //То же, что записывается в заранее подготовленные буферы:
+    //каждый конвейер рисует сетки в каждой области вывода
+    pub unsafe fn draw_scene(&self) {
+        for viewport in self.viewports {
+            self.device.cmd_set_viewport(self.command_buffer, 0, &[viewport.to_viewport(self.extent)]);
+            self.device.cmd_set_scissor(self.command_buffer, 0, &[viewport.to_scissor(self.extent)]);
+
+            for (_, pipeline) in self.pipelines.iter() {
+                pipeline.bind_uniform_set(self.device, self.command_buffer, self.uniform_sets, self.frame_index);
+                for variant in pipeline.variants() {
+                    self.device.cmd_bind_pipeline(self.command_buffer, vk::PipelineBindPoint::GRAPHICS, variant);
+                    pipeline.draw_meshes(self.device, self.command_buffer, self.meshes);
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use bytemuck::{Pod, Zeroable};
+use glam::{Mat4, Quat, Vec3, Vec4};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Projection {
+    //fov_y - вертикальный угол обзора в радианах
+    Perspective { fov_y: f32 },
+    //height - высота видимой области в единицах мира
+    Orthographic { height: f32 }
+}
+
+//Камера в правой системе координат мира (Y вверх, смотрит вдоль -Z).
+//Проекция приводит к соглашениям Vulkan: Y в пространстве отсечения вниз,
+//глубина 0..1. Из-за отражения Y грани моделей с обходом против часовой
+//стрелки (OBJ, glTF) лицевые при front_face: CounterClockwise
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Camera {
+    pub position: Vec3,
+    pub rotation: Quat,
+    pub projection: Projection,
+    pub near: f32,
+    pub far: f32,
+    //Ближняя плоскость в глубине 1, дальняя в 0. Требует очистки глубины
+    //в 0 (RenderingQueue::set_clear_depth) и сравнения Greater
+    pub reverse_z: bool
+}
+
+//Данные камеры в раскладке std140 для uniform буфера кадра
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct CameraUniforms {
+    pub view: Mat4,
+    pub projection: Mat4,
+    pub view_projection: Mat4,
+    //w не используется
+    pub position: Vec4
+}
+
+//Только матрицы и векторы f32 без промежутков между полями
+unsafe impl Zeroable for CameraUniforms {}
+unsafe impl Pod for CameraUniforms {}
+
+impl Camera {
+    pub fn perspective(fov_y: f32, near: f32, far: f32) -> Self {
+        Self {
+            position: Vec3::ZERO,
+            rotation: Quat::IDENTITY,
+            projection: Projection::Perspective { fov_y },
+            near,
+            far,
+            reverse_z: false
+        }
+    }
+
+    pub fn orthographic(height: f32, near: f32, far: f32) -> Self {
+        Self {
+            projection: Projection::Orthographic { height },
+            ..Self::perspective(0.0, near, far)
+        }
+    }
+
+    pub fn with_reverse_z(self, reverse_z: bool) -> Self {
+        Self { reverse_z, ..self }
+    }
+
+    //Поворачивает камеру к target, up - приблизительное направление вверх
+    pub fn look_at(&mut self, target: Vec3, up: Vec3) {
+        let forward = (target - self.position).normalize_or(Vec3::NEG_Z);
+        let view = Mat4::look_to_rh(Vec3::ZERO, forward, up);
+        self.rotation = Quat::from_mat4(&view.inverse()).normalize();
+    }
+
+    pub fn forward(&self) -> Vec3 {
+        self.rotation * Vec3::NEG_Z
+    }
+
+    pub fn right(&self) -> Vec3 {
+        self.rotation * Vec3::X
+    }
+
+    pub fn up(&self) -> Vec3 {
+        self.rotation * Vec3::Y
+    }
+
+    pub fn view_matrix(&self) -> Mat4 {
+        Mat4::from_rotation_translation(self.rotation, self.position).inverse()
+    }
+
+    pub fn projection_matrix(&self, aspect: f32) -> Mat4 {
+        let (near, far) = if self.reverse_z {
+            (self.far, self.near)
+        } else {
+            (self.near, self.far)
+        };
+
+        let mut projection = match self.projection {
+            Projection::Perspective { fov_y } => Mat4::perspective_rh(fov_y, aspect, near, far),
+            Projection::Orthographic { height } => {
+                let half_height = height * 0.5;
+                let half_width = half_height * aspect;
+                Mat4::orthographic_rh(-half_width, half_width, -half_height, half_height, near, far)
+            }
+        };
+
+        //Ось Y пространства отсечения в Vulkan направлена вниз
+        projection.y_axis.y = -projection.y_axis.y;
+        projection
+    }
+
+    pub fn depth_clear_value(&self) -> f32 {
+        if self.reverse_z { 0.0 } else { 1.0 }
+    }
+
+    pub fn uniforms(&self, aspect: f32) -> CameraUniforms {
+        let view = self.view_matrix();
+        let projection = self.projection_matrix(aspect);
+
+        CameraUniforms {
+            view,
+            projection,
+            view_projection: projection * view,
+            position: self.position.extend(1.0)
+        }
+    }
+}
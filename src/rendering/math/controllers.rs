@@ -0,0 +1,108 @@
+use std::f32::consts::FRAC_PI_2;
+
+use glam::{EulerRot, Quat, Vec2, Vec3};
+
+use super::Camera;
+
+//Ввод за кадр, собранный из событий окна
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct CameraInput {
+    //Смещение мыши в пикселях
+    pub mouse_delta: Vec2,
+    //Прокрутка колеса в строках, вверх положительная
+    pub scroll: f32,
+    //Кнопка вращения зажата
+    pub rotating: bool,
+    //Направление движения в осях камеры: x вправо, y вверх, z вперед
+    pub movement: Vec3,
+    pub fast: bool
+}
+
+pub trait CameraController {
+    //dt - время кадра в секундах
+    fn update(&mut self, camera: &mut Camera, input: &CameraInput, dt: f32);
+}
+
+//Наклон чуть меньше вертикали, чтобы look_at не вырождался
+const MAX_PITCH: f32 = FRAC_PI_2 - 0.01;
+
+//Вращение вокруг точки, колесо меняет расстояние
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OrbitController {
+    pub target: Vec3,
+    pub distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    //Радиан на пиксель
+    pub sensitivity: f32,
+    //Доля расстояния на одну строку прокрутки
+    pub zoom_speed: f32,
+    pub min_distance: f32
+}
+
+impl OrbitController {
+    pub fn new(target: Vec3, distance: f32) -> Self {
+        Self {
+            target,
+            distance,
+            yaw: 0.0,
+            pitch: 0.0,
+            sensitivity: 0.005,
+            zoom_speed: 0.1,
+            min_distance: 0.01
+        }
+    }
+}
+
+impl CameraController for OrbitController {
+    fn update(&mut self, camera: &mut Camera, input: &CameraInput, _dt: f32) {
+        if input.rotating {
+            self.yaw -= input.mouse_delta.x * self.sensitivity;
+            self.pitch = (self.pitch - input.mouse_delta.y * self.sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
+        }
+        self.distance = (self.distance * (1.0 - input.scroll * self.zoom_speed)).max(self.min_distance);
+
+        let rotation = Quat::from_euler(EulerRot::YXZ, self.yaw, self.pitch, 0.0);
+        camera.position = self.target + rotation * Vec3::Z * self.distance;
+        camera.look_at(self.target, Vec3::Y);
+    }
+}
+
+//Свободный полет: мышь поворачивает, клавиши двигают в осях камеры
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FlyController {
+    pub yaw: f32,
+    pub pitch: f32,
+    //Единиц мира в секунду
+    pub speed: f32,
+    pub fast_multiplier: f32,
+    pub sensitivity: f32
+}
+
+impl Default for FlyController {
+    fn default() -> Self {
+        Self {
+            yaw: 0.0,
+            pitch: 0.0,
+            speed: 2.0,
+            fast_multiplier: 4.0,
+            sensitivity: 0.003
+        }
+    }
+}
+
+impl CameraController for FlyController {
+    fn update(&mut self, camera: &mut Camera, input: &CameraInput, dt: f32) {
+        if input.rotating {
+            self.yaw -= input.mouse_delta.x * self.sensitivity;
+            self.pitch = (self.pitch - input.mouse_delta.y * self.sensitivity).clamp(-MAX_PITCH, MAX_PITCH);
+        }
+        camera.rotation = Quat::from_euler(EulerRot::YXZ, self.yaw, self.pitch, 0.0);
+
+        let speed = if input.fast { self.speed * self.fast_multiplier } else { self.speed };
+        let movement = camera.right() * input.movement.x
+            + camera.up() * input.movement.y
+            + camera.forward() * input.movement.z;
+        camera.position += movement.normalize_or_zero() * speed * dt;
+    }
+}
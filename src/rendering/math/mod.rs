@@ -0,0 +1,9 @@
+mod camera;
+mod controllers;
+mod transform;
+
+pub use glam::{Mat4, Quat, Vec2, Vec3};
+
+pub use camera::*;
+pub use controllers::*;
+pub use transform::*;
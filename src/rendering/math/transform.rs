@@ -0,0 +1,120 @@
+use glam::{Mat4, Quat, Vec3};
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub translation: Vec3,
+    pub rotation: Quat,
+    pub scale: Vec3
+}
+
+impl Transform {
+    pub const IDENTITY: Self = Self {
+        translation: Vec3::ZERO,
+        rotation: Quat::IDENTITY,
+        scale: Vec3::ONE
+    };
+
+    pub fn matrix(&self) -> Mat4 {
+        Mat4::from_scale_rotation_translation(self.scale, self.rotation, self.translation)
+    }
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::IDENTITY
+    }
+}
+
+//Индекс узла в иерархии, действителен все время ее жизни
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct NodeHandle(usize);
+
+#[derive(Debug, Clone)]
+struct TransformNode {
+    local: Transform,
+    parent: Option<NodeHandle>,
+    world: Mat4
+}
+
+//Родитель всегда добавляется раньше потомков, поэтому мировые матрицы
+//считаются одним проходом по порядку добавления
+#[derive(Debug, Clone, Default)]
+pub struct TransformHierarchy {
+    nodes: Vec<TransformNode>,
+    dirty: bool
+}
+
+impl TransformHierarchy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, local: Transform, parent: Option<NodeHandle>) -> NodeHandle {
+        self.nodes.push(TransformNode {
+            local,
+            parent,
+            world: Mat4::IDENTITY
+        });
+        self.dirty = true;
+
+        NodeHandle(self.nodes.len() - 1)
+    }
+
+    //Мировые матрицы пересчитываются при следующем update()
+    pub fn local_mut(&mut self, node: NodeHandle) -> &mut Transform {
+        self.dirty = true;
+        &mut self.nodes[node.0].local
+    }
+
+    pub fn set_local(&mut self, node: NodeHandle, local: Transform) {
+        *self.local_mut(node) = local;
+    }
+
+    //Пересчитывает мировые матрицы, если что-то менялось
+    pub fn update(&mut self) {
+        if !self.dirty {
+            return;
+        }
+
+        for index in 0..self.nodes.len() {
+            let local = self.nodes[index].local.matrix();
+            self.nodes[index].world = match self.nodes[index].parent {
+                Some(parent) => self.nodes[parent.0].world * local,
+                None => local
+            };
+        }
+        self.dirty = false;
+    }
+
+    //Актуальна после update()
+    pub fn world(&self, node: NodeHandle) -> Mat4 {
+        self.nodes[node.0].world
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translation(x: f32) -> Transform {
+        Transform { translation: Vec3::new(x, 0.0, 0.0), ..Transform::IDENTITY }
+    }
+
+    #[test]
+    fn changed_parent_moves_children() {
+        let mut hierarchy = TransformHierarchy::new();
+        let root = hierarchy.add(translation(1.0), None);
+        let child = hierarchy.add(translation(2.0), Some(root));
+        hierarchy.update();
+        assert_eq!(hierarchy.world(child), Mat4::from_translation(Vec3::new(3.0, 0.0, 0.0)));
+
+        hierarchy.set_local(root, translation(-1.0));
+        hierarchy.update();
+        assert_eq!(hierarchy.world(child), Mat4::from_translation(Vec3::new(1.0, 0.0, 0.0)));
+
+        hierarchy.local_mut(child).scale = Vec3::splat(2.0);
+        hierarchy.update();
+        assert_eq!(hierarchy.world(root), Mat4::from_translation(Vec3::new(-1.0, 0.0, 0.0)));
+        assert_eq!(hierarchy.world(child).transform_point3(Vec3::X), Vec3::new(3.0, 0.0, 0.0));
+    }
+}
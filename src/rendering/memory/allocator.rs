@@ -0,0 +1,311 @@
+use std::ptr::NonNull;
+
+use log::{debug, warn};
+use vulkanalia::prelude::v1_0::*;
+
+use super::block::MemoryBlock;
+pub use super::block::ResourceKind;
+use crate::rendering::RqResult;
+use crate::rendering::RenderingError::{AllocateMemoryError, SupportError};
+
+//Размер блока по умолчанию, но не больше восьмой части кучи
+const BLOCK_SIZE: vk::DeviceSize = 64 * 1024 * 1024;
+
+//Назначение памяти, по которому выбирается ее тип
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MemoryUsage {
+    //Только для устройства: текстуры, вершины, индексы
+    GpuOnly,
+    //Запись с хоста и чтение устройством: промежуточные буферы, uniform
+    Upload,
+    //Запись устройством и чтение с хоста
+    Readback
+}
+
+impl MemoryUsage {
+    fn required_flags(self) -> vk::MemoryPropertyFlags {
+        match self {
+            MemoryUsage::GpuOnly => vk::MemoryPropertyFlags::DEVICE_LOCAL,
+            MemoryUsage::Upload | MemoryUsage::Readback =>
+                vk::MemoryPropertyFlags::HOST_VISIBLE | vk::MemoryPropertyFlags::HOST_COHERENT
+        }
+    }
+
+    fn preferred_flags(self) -> vk::MemoryPropertyFlags {
+        match self {
+            MemoryUsage::Readback => vk::MemoryPropertyFlags::HOST_CACHED,
+            _ => vk::MemoryPropertyFlags::empty()
+        }
+    }
+
+    //Память, видимая хосту, но локальная для устройства, обычно мала,
+    //ее стоит оставлять тем, кто явно о ней просит
+    fn unwanted_flags(self) -> vk::MemoryPropertyFlags {
+        match self {
+            MemoryUsage::GpuOnly => vk::MemoryPropertyFlags::HOST_VISIBLE,
+            MemoryUsage::Upload | MemoryUsage::Readback => vk::MemoryPropertyFlags::DEVICE_LOCAL
+        }
+    }
+}
+
+//Участок блока, занятый одним ресурсом
+#[derive(Debug)]
+pub struct Allocation {
+    pub memory: vk::DeviceMemory,
+    pub offset: vk::DeviceSize,
+    pub size: vk::DeviceSize,
+    mapped: Option<NonNull<u8>>,
+    block: usize
+}
+
+impl Allocation {
+    //Указатель на начало участка, если память отображена
+    pub fn mapped_ptr(&self) -> Option<*mut u8> {
+        self.mapped.map(|mapped| mapped.as_ptr())
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MemoryStatistics {
+    pub blocks: usize,
+    pub allocations: usize,
+    pub reserved_bytes: vk::DeviceSize,
+    pub used_bytes: vk::DeviceSize,
+    pub peak_used_bytes: vk::DeviceSize
+}
+
+//Ресурсы нарезаются из больших блоков, поэтому число вызовов allocate_memory
+//остается далеко от max_memory_allocation_count
+#[derive(Debug)]
+pub struct MemoryAllocator {
+    memory_types: Vec<vk::MemoryType>,
+    heap_sizes: Vec<vk::DeviceSize>,
+    granularity: vk::DeviceSize,
+    max_allocations: usize,
+    //Выделения вложений мимо распределителя, тоже входят в max_memory_allocation_count
+    external_allocations: usize,
+    //Индекс блока хранится в выделении, поэтому освобожденные блоки остаются None
+    blocks: Vec<Option<MemoryBlock>>,
+    peak_used_bytes: vk::DeviceSize
+}
+
+impl MemoryAllocator {
+    pub unsafe fn new(instance: &Instance, physical_device: vk::PhysicalDevice) -> Self {
+        let properties = instance.get_physical_device_memory_properties(physical_device);
+        let limits = instance.get_physical_device_properties(physical_device).limits;
+
+        Self {
+            memory_types: properties.memory_types[..properties.memory_type_count as usize].to_vec(),
+            heap_sizes: properties.memory_heaps[..properties.memory_heap_count as usize].iter()
+                .map(|heap| heap.size)
+                .collect(),
+            granularity: limits.buffer_image_granularity,
+            max_allocations: limits.max_memory_allocation_count as usize,
+            external_allocations: 0,
+            blocks: Vec::new(),
+            peak_used_bytes: 0
+        }
+    }
+
+    //Тип с обязательными свойствами, у которого больше желательных и меньше лишних
+    fn find_memory_type(&self, memory_type_bits: u32, usage: MemoryUsage) -> RqResult<u32> {
+        let required = usage.required_flags();
+
+        (0..self.memory_types.len() as u32)
+            .filter(|index| memory_type_bits & (1 << index) != 0)
+            .filter(|index| self.memory_types[*index as usize].property_flags.contains(required))
+            .min_by_key(|index| {
+                let flags = self.memory_types[*index as usize].property_flags;
+                let missing = (usage.preferred_flags() & !flags).bits().count_ones();
+                let unwanted = (usage.unwanted_flags() & flags).bits().count_ones();
+                missing + unwanted
+            })
+            .ok_or(SupportError("suitable memory type was not found"))
+    }
+
+    fn block_size(&self, memory_type: u32) -> vk::DeviceSize {
+        let heap = self.memory_types[memory_type as usize].heap_index as usize;
+        BLOCK_SIZE.min(self.heap_sizes[heap] / 8)
+    }
+
+    pub unsafe fn allocate(
+        &mut self,
+        device: &Device,
+        requirements: vk::MemoryRequirements,
+        usage: MemoryUsage,
+        kind: ResourceKind
+    ) -> RqResult<Allocation> {
+        let memory_type = self.find_memory_type(requirements.memory_type_bits, usage)?;
+        let block_size = self.block_size(memory_type);
+
+        //Крупные ресурсы получают собственный блок
+        let dedicated = requirements.size > block_size / 2;
+        if !dedicated {
+            for (index, block) in self.blocks.iter_mut().enumerate() {
+                let Some(block) = block.as_mut().filter(|block| block.memory_type == memory_type) else {
+                    continue;
+                };
+
+                if let Some(offset) = block.allocate(requirements.size, requirements.alignment, kind, self.granularity) {
+                    let allocation = allocation(block, index, offset, requirements.size);
+                    self.update_peak();
+                    return Result::Ok(allocation);
+                }
+            }
+        }
+
+        let size = if dedicated { requirements.size } else { block_size };
+        let index = self.create_block(device, memory_type, size)?;
+        let block = self.blocks[index].as_mut().expect("block was just created");
+        let offset = block.allocate(requirements.size, requirements.alignment, kind, self.granularity)
+            .expect("new block fits the allocation");
+
+        let allocation = allocation(block, index, offset, requirements.size);
+        self.update_peak();
+        Result::Ok(allocation)
+    }
+
+    unsafe fn create_block(&mut self, device: &Device, memory_type: u32, size: vk::DeviceSize) -> RqResult<usize> {
+        if self.statistics().blocks + self.external_allocations >= self.max_allocations {
+            return Err(AllocateMemoryError(vk::ErrorCode::TOO_MANY_OBJECTS));
+        }
+
+        let memory_info = vk::MemoryAllocateInfo::builder()
+            .allocation_size(size)
+            .memory_type_index(memory_type);
+
+        let memory = device.allocate_memory(&memory_info, None)
+            .map_err(AllocateMemoryError)?;
+
+        let host_visible = self.memory_types[memory_type as usize].property_flags
+            .contains(vk::MemoryPropertyFlags::HOST_VISIBLE);
+        let mapped = if host_visible {
+            match device.map_memory(memory, 0, size, vk::MemoryMapFlags::empty()) {
+                Ok(mapped) => NonNull::new(mapped as *mut u8),
+                Err(err) => {
+                    device.free_memory(memory, None);
+                    return Err(AllocateMemoryError(err));
+                }
+            }
+        } else {
+            None
+        };
+
+        debug!("memory block of {} bytes allocated in memory type {}", size, memory_type);
+
+        let block = MemoryBlock::new(memory, size, memory_type, mapped);
+        match self.blocks.iter().position(Option::is_none) {
+            Some(index) => {
+                self.blocks[index] = Some(block);
+                Result::Ok(index)
+            }
+            None => {
+                self.blocks.push(Some(block));
+                Result::Ok(self.blocks.len() - 1)
+            }
+        }
+    }
+
+    //Число выделений памяти, сделанных в обход распределителя (вложения swap chain и графа)
+    pub fn set_external_allocations(&mut self, count: usize) {
+        self.external_allocations = count;
+    }
+
+    //Выделяет память под буфер и привязывает ее
+    pub unsafe fn allocate_buffer(&mut self, device: &Device, buffer: vk::Buffer, usage: MemoryUsage) -> RqResult<Allocation> {
+        let requirements = device.get_buffer_memory_requirements(buffer);
+        let allocation = self.allocate(device, requirements, usage, ResourceKind::Linear)?;
+
+        if let Err(err) = device.bind_buffer_memory(buffer, allocation.memory, allocation.offset) {
+            self.free(device, &allocation);
+            return Err(AllocateMemoryError(err));
+        }
+
+        Result::Ok(allocation)
+    }
+
+    //Изображения с OPTIMAL раскладкой, LINEAR выделяются как буферы
+    pub unsafe fn allocate_image(&mut self, device: &Device, image: vk::Image, usage: MemoryUsage) -> RqResult<Allocation> {
+        let requirements = device.get_image_memory_requirements(image);
+        let allocation = self.allocate(device, requirements, usage, ResourceKind::Optimal)?;
+
+        if let Err(err) = device.bind_image_memory(image, allocation.memory, allocation.offset) {
+            self.free(device, &allocation);
+            return Err(AllocateMemoryError(err));
+        }
+
+        Result::Ok(allocation)
+    }
+
+    //Пустой блок сразу возвращается устройству
+    pub unsafe fn free(&mut self, device: &Device, allocation: &Allocation) {
+        let Some(block) = self.blocks.get_mut(allocation.block).and_then(Option::as_mut) else {
+            warn!("memory at offset {} was already freed", allocation.offset);
+            return;
+        };
+
+        if !block.free(allocation.offset) {
+            warn!("memory at offset {} was already freed", allocation.offset);
+            return;
+        }
+
+        if block.is_empty() {
+            free_block(device, block);
+            self.blocks[allocation.block] = None;
+        }
+    }
+
+    fn update_peak(&mut self) {
+        self.peak_used_bytes = self.peak_used_bytes.max(self.statistics().used_bytes);
+    }
+
+    pub fn statistics(&self) -> MemoryStatistics {
+        self.blocks.iter()
+            .flatten()
+            .fold(MemoryStatistics { peak_used_bytes: self.peak_used_bytes, ..Default::default() }, |statistics, block| {
+                MemoryStatistics {
+                    blocks: statistics.blocks + 1,
+                    allocations: statistics.allocations + block.allocation_count(),
+                    reserved_bytes: statistics.reserved_bytes + block.size,
+                    used_bytes: statistics.used_bytes + block.used(),
+                    ..statistics
+                }
+            })
+    }
+
+    //Все, что не было освобождено к этому моменту, считается утечкой
+    pub unsafe fn destroy(&mut self, device: &Device) {
+        let statistics = self.statistics();
+        debug!(
+            "memory allocator: peak {} bytes used, {} blocks ({} bytes) left",
+            statistics.peak_used_bytes, statistics.blocks, statistics.reserved_bytes
+        );
+
+        for block in self.blocks.drain(..).flatten() {
+            if !block.is_empty() {
+                warn!(
+                    "memory leak: {} allocations ({} bytes) in memory type {}",
+                    block.allocation_count(), block.used(), block.memory_type
+                );
+            }
+            free_block(device, &block);
+        }
+    }
+}
+
+fn allocation(block: &MemoryBlock, index: usize, offset: vk::DeviceSize, size: vk::DeviceSize) -> Allocation {
+    Allocation {
+        memory: block.memory,
+        offset,
+        size,
+        mapped: block.mapped.map(|mapped| unsafe { NonNull::new_unchecked(mapped.as_ptr().add(offset as usize)) }),
+        block: index
+    }
+}
+
+unsafe fn free_block(device: &Device, block: &MemoryBlock) {
+    if block.mapped.is_some() {
+        device.unmap_memory(block.memory);
+    }
+    device.free_memory(block.memory, None);
+}
@@ -0,0 +1,195 @@
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::ptr::NonNull;
+
+use vulkanalia::prelude::v1_0::*;
+
+//Линейные ресурсы (буферы) и изображения с OPTIMAL раскладкой не должны
+//делить страницу размером buffer_image_granularity
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResourceKind {
+    Linear,
+    Optimal
+}
+
+//Одно выделение памяти устройства, из которого нарезаются ресурсы.
+//Свободные участки хранятся списком, упорядоченным по смещению
+#[derive(Debug)]
+pub struct MemoryBlock {
+    pub memory: vk::DeviceMemory,
+    pub size: vk::DeviceSize,
+    pub memory_type: u32,
+    //Память HOST_VISIBLE отображается один раз на все время жизни блока
+    pub mapped: Option<NonNull<u8>>,
+    free: Vec<Range<vk::DeviceSize>>,
+    allocations: BTreeMap<vk::DeviceSize, (vk::DeviceSize, ResourceKind)>
+}
+
+impl MemoryBlock {
+    pub fn new(memory: vk::DeviceMemory, size: vk::DeviceSize, memory_type: u32, mapped: Option<NonNull<u8>>) -> Self {
+        Self {
+            memory,
+            size,
+            memory_type,
+            mapped,
+            free: vec![Range { start: 0, end: size }],
+            allocations: BTreeMap::new()
+        }
+    }
+
+    //Первый подходящий свободный участок с учетом выравнивания и соседей другого вида
+    pub fn allocate(
+        &mut self,
+        size: vk::DeviceSize,
+        alignment: vk::DeviceSize,
+        kind: ResourceKind,
+        granularity: vk::DeviceSize
+    ) -> Option<vk::DeviceSize> {
+        let (index, offset) = self.free.iter()
+            .enumerate()
+            .find_map(|(index, range)| self.fit(range, size, alignment, kind, granularity).map(|offset| (index, offset)))?;
+
+        let range = self.free.remove(index);
+        let end = offset + size;
+        if end < range.end {
+            self.free.insert(index, end..range.end);
+        }
+        if range.start < offset {
+            self.free.insert(index, range.start..offset);
+        }
+
+        self.allocations.insert(offset, (size, kind));
+        Some(offset)
+    }
+
+    fn fit(
+        &self,
+        range: &Range<vk::DeviceSize>,
+        size: vk::DeviceSize,
+        alignment: vk::DeviceSize,
+        kind: ResourceKind,
+        granularity: vk::DeviceSize
+    ) -> Option<vk::DeviceSize> {
+        let mut offset = align_up(range.start, alignment);
+
+        if let Some((previous, (previous_size, previous_kind))) = self.allocations.range(..range.start).next_back() {
+            if *previous_kind != kind && same_page(previous + previous_size - 1, offset, granularity) {
+                offset = align_up(offset, granularity);
+            }
+        }
+
+        let end = offset.checked_add(size)?;
+        if end > range.end {
+            return None;
+        }
+
+        if let Some((next, (_, next_kind))) = self.allocations.range(end..).next() {
+            if *next_kind != kind && same_page(end - 1, *next, granularity) {
+                return None;
+            }
+        }
+
+        Some(offset)
+    }
+
+    //Возвращает false, если по смещению ничего не выделено
+    pub fn free(&mut self, offset: vk::DeviceSize) -> bool {
+        let Some((size, _)) = self.allocations.remove(&offset) else {
+            return false;
+        };
+
+        let mut range = offset..offset + size;
+        let index = self.free.partition_point(|free| free.start < offset);
+
+        //Слияние с соседними свободными участками
+        if index < self.free.len() && self.free[index].start == range.end {
+            range.end = self.free.remove(index).end;
+        }
+        if index > 0 && self.free[index - 1].end == range.start {
+            range.start = self.free.remove(index - 1).start;
+            self.free.insert(index - 1, range);
+        } else {
+            self.free.insert(index, range);
+        }
+
+        true
+    }
+
+    pub fn allocation_count(&self) -> usize {
+        self.allocations.len()
+    }
+
+    pub fn used(&self) -> vk::DeviceSize {
+        self.allocations.values().map(|(size, _)| size).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.allocations.is_empty()
+    }
+}
+
+fn align_up(value: vk::DeviceSize, alignment: vk::DeviceSize) -> vk::DeviceSize {
+    value.div_ceil(alignment.max(1)) * alignment.max(1)
+}
+
+fn same_page(first: vk::DeviceSize, second: vk::DeviceSize, granularity: vk::DeviceSize) -> bool {
+    first / granularity.max(1) == second / granularity.max(1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(size: vk::DeviceSize) -> MemoryBlock {
+        MemoryBlock::new(vk::DeviceMemory::null(), size, 0, None)
+    }
+
+    #[test]
+    fn allocations_are_aligned() {
+        let mut block = block(1024);
+
+        assert_eq!(block.allocate(10, 4, ResourceKind::Linear, 1), Some(0));
+        assert_eq!(block.allocate(16, 64, ResourceKind::Linear, 1), Some(64));
+        //Промежуток перед выровненным участком остается свободным
+        assert_eq!(block.allocate(8, 8, ResourceKind::Linear, 1), Some(16));
+        assert_eq!(block.allocate(1024, 1, ResourceKind::Linear, 1), None);
+    }
+
+    #[test]
+    fn different_kinds_do_not_share_a_page() {
+        let mut block = block(1024);
+
+        assert_eq!(block.allocate(100, 4, ResourceKind::Optimal, 256), Some(0));
+        assert_eq!(block.allocate(100, 4, ResourceKind::Optimal, 256), Some(100));
+        //Буфер сдвигается на следующую страницу после изображения
+        assert_eq!(block.allocate(100, 4, ResourceKind::Linear, 256), Some(256));
+        assert_eq!(block.allocate(28, 4, ResourceKind::Linear, 256), Some(356));
+
+        //Освободившееся место перед изображением на той же странице буферу не подходит
+        assert!(block.free(0));
+        assert_eq!(block.allocate(50, 4, ResourceKind::Linear, 256), Some(384));
+        assert_eq!(block.allocate(50, 4, ResourceKind::Optimal, 256), Some(0));
+    }
+
+    #[test]
+    fn free_ranges_are_merged() {
+        let mut block = block(300);
+
+        let first = block.allocate(100, 1, ResourceKind::Linear, 1).unwrap();
+        let second = block.allocate(100, 1, ResourceKind::Linear, 1).unwrap();
+        let third = block.allocate(100, 1, ResourceKind::Linear, 1).unwrap();
+        assert_eq!(block.allocate(1, 1, ResourceKind::Linear, 1), None);
+
+        assert!(block.free(first));
+        assert!(block.free(third));
+        assert!(!block.free(third));
+        assert_eq!(block.allocate(200, 1, ResourceKind::Linear, 1), None);
+
+        //Средний участок сливается с обоими соседями
+        assert!(block.free(second));
+        assert!(block.is_empty());
+        assert_eq!(block.free, vec![0..300]);
+        assert_eq!(block.allocate(300, 1, ResourceKind::Linear, 1), Some(0));
+        assert_eq!(block.used(), 300);
+    }
+}
@@ -0,0 +1,29 @@
+mod block;
+mod allocator;
+
+pub use allocator::*;
+
+use vulkanalia::prelude::v1_0::*;
+
+use crate::rendering::RqResult;
+use crate::rendering::RenderingError::SupportError;
+
+//Ищем тип памяти, который разрешен ресурсу (memory_type_bits)
+//и обладает всеми требуемыми свойствами
+pub unsafe fn find_memory_type_index(
+    instance: &Instance,
+    physical_device: vk::PhysicalDevice,
+    requirements: vk::MemoryRequirements,
+    properties: vk::MemoryPropertyFlags
+) -> RqResult<u32>
+{
+    let memory = instance.get_physical_device_memory_properties(physical_device);
+
+    (0..memory.memory_type_count)
+        .find(|index| {
+            let suitable = (requirements.memory_type_bits & (1 << index)) != 0;
+            let memory_type = memory.memory_types[*index as usize];
+            suitable && memory_type.property_flags.contains(properties)
+        })
+        .ok_or(SupportError("suitable memory type was not found"))
+}
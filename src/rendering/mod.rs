@@ -2,8 +2,36 @@ mod exceptions;
 mod queue_builder;
 mod rendering_queue;
 mod shaders;
+mod reflection;
+mod memory;
+mod attachments;
+mod pipelines;
+mod descriptors;
+mod buffers;
+mod upload;
+mod textures;
+mod models;
+mod math;
+mod viewport;
+mod frame;
+mod shader_watcher;
+mod render_graph;
 
 pub use rendering_queue::*;
 pub use exceptions::*;
 pub use queue_builder::*;
-pub use shaders::*;
\ No newline at end of file
+pub use shaders::*;
+pub use reflection::*;
+pub use memory::*;
+pub use attachments::*;
+pub use pipelines::*;
+pub use descriptors::*;
+pub use buffers::*;
+pub use upload::*;
+pub use textures::*;
+pub use models::*;
+pub use math::*;
+pub use viewport::*;
+pub use frame::*;
+pub use shader_watcher::*;
+pub use render_graph::*;
\ No newline at end of file
@@ -0,0 +1,221 @@
+use std::path::Path;
+
+use log::warn;
+
+use super::{ModelData, PrimitiveData};
+use crate::rendering::{Mat4, ModelVertex, NodeHandle, Quat, RqResult, Transform, TransformHierarchy, Vec3};
+use crate::rendering::RenderingError::LoadModelError;
+
+//Примитивы сцены по умолчанию в мировых координатах: преобразования
+//узлов применяются к вершинам. Без сцен берутся все сетки как есть
+pub fn load(path: &Path) -> RqResult<ModelData> {
+    let (document, buffers, _) = gltf::import(path)
+        .map_err(|err| LoadModelError(format!("{}: {}", path.display(), err)))?;
+
+    load_document(path, &document, &buffers)
+}
+
+fn load_document(path: &Path, document: &gltf::Document, buffers: &[gltf::buffer::Data]) -> RqResult<ModelData> {
+    let mut model = ModelData::default();
+    match document.default_scene().or_else(|| document.scenes().next()) {
+        Some(scene) => {
+            let mut hierarchy = TransformHierarchy::new();
+            let mut instances = Vec::new();
+            for node in scene.nodes() {
+                add_node(&node, None, &mut hierarchy, &mut instances);
+            }
+            hierarchy.update();
+
+            for (node, mesh) in instances {
+                load_mesh(path, &mesh, hierarchy.world(node), buffers, &mut model)?;
+            }
+        }
+        None => {
+            for mesh in document.meshes() {
+                load_mesh(path, &mesh, Mat4::IDENTITY, buffers, &mut model)?;
+            }
+        }
+    }
+
+    Result::Ok(model)
+}
+
+//Матрица узла раскладывается на перенос, поворот и масштаб, сдвиг теряется
+fn add_node<'a>(
+    node: &gltf::Node<'a>,
+    parent: Option<NodeHandle>,
+    hierarchy: &mut TransformHierarchy,
+    instances: &mut Vec<(NodeHandle, gltf::Mesh<'a>)>
+) {
+    let (translation, rotation, scale) = node.transform().decomposed();
+    let handle = hierarchy.add(
+        Transform {
+            translation: Vec3::from(translation),
+            rotation: Quat::from_array(rotation),
+            scale: Vec3::from(scale)
+        },
+        parent
+    );
+
+    if let Some(mesh) = node.mesh() {
+        instances.push((handle, mesh));
+    }
+
+    for child in node.children() {
+        add_node(&child, Some(handle), hierarchy, instances);
+    }
+}
+
+fn load_mesh(
+    path: &Path,
+    mesh: &gltf::Mesh,
+    transform: Mat4,
+    buffers: &[gltf::buffer::Data],
+    model: &mut ModelData
+) -> RqResult<()> {
+    let mesh_name = mesh.name().unwrap_or("mesh");
+    //Нормали переносятся обратной транспонированной матрицей, иначе
+    //неравномерный масштаб отклоняет их от поверхности
+    let normal_matrix = transform.inverse().transpose();
+    //Отраженный узел меняет порядок обхода треугольников и направление битангенса
+    let mirrored = transform.determinant() < 0.0;
+    let handedness = if mirrored { -1.0 } else { 1.0 };
+
+    for primitive in mesh.primitives() {
+        if primitive.mode() != gltf::mesh::Mode::Triangles {
+            warn!("{}: primitive {} of \"{}\" is {:?}, only triangles are loaded",
+                path.display(), primitive.index(), mesh_name, primitive.mode());
+            continue;
+        }
+
+        let reader = primitive.reader(|buffer| Some(&buffers[buffer.index()]));
+
+        let positions: Vec<[f32; 3]> = reader.read_positions()
+            .ok_or_else(|| LoadModelError(format!(
+                "{}: primitive {} of \"{}\" has no positions",
+                path.display(), primitive.index(), mesh_name
+            )))?
+            .collect();
+
+        let normals: Option<Vec<[f32; 3]>> = reader.read_normals().map(Iterator::collect);
+        let uvs: Option<Vec<[f32; 2]>> = reader.read_tex_coords(0).map(|uvs| uvs.into_f32().collect());
+        let tangents: Option<Vec<[f32; 4]>> = reader.read_tangents().map(Iterator::collect);
+
+        //Атрибуты одного примитива обязаны иметь одинаковое число элементов
+        let counts = [
+            ("normals", normals.as_ref().map(Vec::len)),
+            ("texture coordinates", uvs.as_ref().map(Vec::len)),
+            ("tangents", tangents.as_ref().map(Vec::len))
+        ];
+        for (attribute, count) in counts {
+            if let Some(count) = count.filter(|count| *count != positions.len()) {
+                return Err(LoadModelError(format!(
+                    "{}: primitive {} of \"{}\" has {} positions and {} {}",
+                    path.display(), primitive.index(), mesh_name, positions.len(), count, attribute
+                )));
+            }
+        }
+
+        let indices: Vec<u32> = match reader.read_indices() {
+            Some(indices) => indices.into_u32().collect(),
+            None => (0..positions.len() as u32).collect()
+        };
+
+        let vertices = positions.iter()
+            .enumerate()
+            .map(|(index, position)| ModelVertex {
+                position: transform.transform_point3(Vec3::from(*position)).to_array(),
+                normal: normals.as_ref()
+                    .map(|normals| transform_direction(&normal_matrix, normals[index]))
+                    .unwrap_or([0.0; 3]),
+                uv: uvs.as_ref().map(|uvs| uvs[index]).unwrap_or([0.0; 2]),
+                tangent: tangents.as_ref()
+                    .map(|tangents| {
+                        let tangent = tangents[index];
+                        let [x, y, z] = transform_direction(&transform, [tangent[0], tangent[1], tangent[2]]);
+                        [x, y, z, tangent[3] * handedness]
+                    })
+                    .unwrap_or([0.0; 4])
+            })
+            .collect();
+
+        let mut data = PrimitiveData {
+            name: format!("{}[{}]", mesh_name, primitive.index()),
+            vertices,
+            indices
+        };
+        data.validate(path)?;
+
+        if mirrored {
+            data.indices.chunks_exact_mut(3).for_each(|triangle| triangle.swap(1, 2));
+        }
+
+        if normals.is_none() {
+            data.generate_normals();
+        }
+        if tangents.is_none() {
+            data.generate_tangents();
+        }
+        model.primitives.push(data);
+    }
+
+    Result::Ok(())
+}
+
+fn transform_direction(matrix: &Mat4, direction: [f32; 3]) -> [f32; 3] {
+    let direction = Vec3::from(direction);
+    matrix.transform_vector3(direction).normalize_or(direction).to_array()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    //Треугольник (0,0,0), (1,0,0), (0,1,0) с нормалью +Z и касательной +X
+    //в узле, отраженном по X
+    const MIRRORED: &str = r#"{
+        "asset": { "version": "2.0" },
+        "scene": 0,
+        "scenes": [{ "nodes": [0] }],
+        "nodes": [{ "mesh": 0, "scale": [-1.0, 1.0, 1.0] }],
+        "meshes": [{
+            "primitives": [{
+                "attributes": { "POSITION": 0, "NORMAL": 1, "TANGENT": 2 },
+                "indices": 3
+            }]
+        }],
+        "buffers": [{
+            "byteLength": 128,
+            "uri": "data:application/octet-stream;base64,AAAAAAAAAAAAAAAAAACAPwAAAAAAAAAAAAAAAAAAgD8AAAAAAAAAAAAAAAAAAIA/AAAAAAAAAAAAAIA/AAAAAAAAAAAAAIA/AACAPwAAAAAAAAAAAACAPwAAgD8AAAAAAAAAAAAAgD8AAIA/AAAAAAAAAAAAAIA/AAABAAIAAAA="
+        }],
+        "bufferViews": [
+            { "buffer": 0, "byteOffset": 0, "byteLength": 36 },
+            { "buffer": 0, "byteOffset": 36, "byteLength": 36 },
+            { "buffer": 0, "byteOffset": 72, "byteLength": 48 },
+            { "buffer": 0, "byteOffset": 120, "byteLength": 6 }
+        ],
+        "accessors": [
+            { "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3",
+              "min": [0.0, 0.0, 0.0], "max": [1.0, 1.0, 0.0] },
+            { "bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC3" },
+            { "bufferView": 2, "componentType": 5126, "count": 3, "type": "VEC4" },
+            { "bufferView": 3, "componentType": 5123, "count": 3, "type": "SCALAR" }
+        ]
+    }"#;
+
+    #[test]
+    fn mirrored_node_keeps_winding_and_handedness() {
+        let (document, buffers, _) = gltf::import_slice(MIRRORED.as_bytes()).unwrap();
+        let model = load_document(Path::new("mirrored.gltf"), &document, &buffers).unwrap();
+
+        let primitive = &model.primitives[0];
+        assert_eq!(primitive.vertices[1].position, [-1.0, 0.0, 0.0]);
+        assert_eq!(primitive.vertices[0].normal, [0.0, 0.0, 1.0]);
+        assert_eq!(primitive.vertices[0].tangent, [-1.0, 0.0, 0.0, -1.0]);
+
+        //Нормаль по порядку обхода совпадает с нормалью вершин
+        assert_eq!(primitive.indices, vec![0, 2, 1]);
+        let [a, b, c] = [0, 1, 2].map(|corner| Vec3::from(primitive.vertices[primitive.indices[corner] as usize].position));
+        assert!((b - a).cross(c - a).normalize().dot(Vec3::Z) > 0.99);
+    }
+}
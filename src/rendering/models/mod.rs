@@ -0,0 +1,7 @@
+mod model_vertex;
+mod model_data;
+mod obj;
+mod gltf;
+
+pub use model_vertex::*;
+pub use model_data::*;
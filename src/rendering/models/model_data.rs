@@ -0,0 +1,185 @@
+use std::path::Path;
+
+use crate::rendering::{ModelVertex, RqResult};
+use crate::rendering::RenderingError::LoadModelError;
+
+//Один примитив модели: треугольники с общим материалом
+#[derive(Debug, Clone, Default)]
+pub struct PrimitiveData {
+    pub name: String,
+    pub vertices: Vec<ModelVertex>,
+    pub indices: Vec<u32>
+}
+
+//Геометрия модели в памяти хоста, до загрузки на устройство
+#[derive(Debug, Clone, Default)]
+pub struct ModelData {
+    pub primitives: Vec<PrimitiveData>
+}
+
+impl ModelData {
+    //OBJ, glTF (.gltf с .bin рядом) и .glb, формат по расширению файла
+    pub fn load(path: &Path) -> RqResult<Self> {
+        let extension = path.extension()
+            .and_then(|extension| extension.to_str())
+            .map(str::to_lowercase)
+            .unwrap_or_default();
+
+        let model = match extension.as_str() {
+            "obj" => super::obj::load(path)?,
+            "gltf" | "glb" => super::gltf::load(path)?,
+            _ => return Err(LoadModelError(format!(
+                "{}: unknown model format \"{}\"",
+                path.display(), extension
+            )))
+        };
+
+        if model.primitives.is_empty() {
+            return Err(LoadModelError(format!("{}: model has no triangles", path.display())));
+        }
+
+        Result::Ok(model)
+    }
+}
+
+impl PrimitiveData {
+    //Загрузчики проверяют индексы до генерации нормалей и касательных
+    pub fn validate(&self, path: &Path) -> RqResult<()> {
+        if !self.indices.len().is_multiple_of(3) {
+            return Err(LoadModelError(format!(
+                "{}: primitive \"{}\" has {} indices, not whole triangles",
+                path.display(), self.name, self.indices.len()
+            )));
+        }
+
+        if let Some(index) = self.indices.iter().find(|index| **index as usize >= self.vertices.len()) {
+            return Err(LoadModelError(format!(
+                "{}: primitive \"{}\" refers to vertex {} of {}",
+                path.display(), self.name, index, self.vertices.len()
+            )));
+        }
+
+        Result::Ok(())
+    }
+
+    //Нормали по треугольникам, усредненные в общих вершинах
+    pub fn generate_normals(&mut self) {
+        let mut normals = vec![[0.0f32; 3]; self.vertices.len()];
+
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|corner| self.vertices[triangle[corner] as usize].position);
+            let normal = cross(sub(b, a), sub(c, a));
+            for index in triangle {
+                normals[*index as usize] = add(normals[*index as usize], normal);
+            }
+        }
+
+        for (vertex, normal) in self.vertices.iter_mut().zip(normals) {
+            vertex.normal = normalize(normal).unwrap_or([0.0, 0.0, 1.0]);
+        }
+    }
+
+    //Касательные по текстурным координатам (ортогонализация Грама-Шмидта)
+    pub fn generate_tangents(&mut self) {
+        let mut tangents = vec![[0.0f32; 3]; self.vertices.len()];
+        let mut bitangents = vec![[0.0f32; 3]; self.vertices.len()];
+
+        for triangle in self.indices.chunks_exact(3) {
+            let [a, b, c] = [0, 1, 2].map(|corner| self.vertices[triangle[corner] as usize]);
+            let edge1 = sub(b.position, a.position);
+            let edge2 = sub(c.position, a.position);
+            let (du1, dv1) = (b.uv[0] - a.uv[0], b.uv[1] - a.uv[1]);
+            let (du2, dv2) = (c.uv[0] - a.uv[0], c.uv[1] - a.uv[1]);
+
+            let determinant = du1 * dv2 - du2 * dv1;
+            if determinant.abs() < f32::EPSILON {
+                continue;
+            }
+            let r = 1.0 / determinant;
+            let tangent = scale(sub(scale(edge1, dv2), scale(edge2, dv1)), r);
+            let bitangent = scale(sub(scale(edge2, du1), scale(edge1, du2)), r);
+
+            for index in triangle {
+                tangents[*index as usize] = add(tangents[*index as usize], tangent);
+                bitangents[*index as usize] = add(bitangents[*index as usize], bitangent);
+            }
+        }
+
+        for (index, vertex) in self.vertices.iter_mut().enumerate() {
+            let normal = vertex.normal;
+            let tangent = sub(tangents[index], scale(normal, dot(normal, tangents[index])));
+            let tangent = normalize(tangent).unwrap_or_else(|| any_perpendicular(normal));
+            let handedness = if dot(cross(normal, tangent), bitangents[index]) < 0.0 { -1.0 } else { 1.0 };
+
+            vertex.tangent = [tangent[0], tangent[1], tangent[2], handedness];
+        }
+    }
+}
+
+fn add(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] + b[0], a[1] + b[1], a[2] + b[2]]
+}
+
+fn sub(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [a[0] - b[0], a[1] - b[1], a[2] - b[2]]
+}
+
+fn scale(a: [f32; 3], s: f32) -> [f32; 3] {
+    [a[0] * s, a[1] * s, a[2] * s]
+}
+
+fn dot(a: [f32; 3], b: [f32; 3]) -> f32 {
+    a[0] * b[0] + a[1] * b[1] + a[2] * b[2]
+}
+
+fn cross(a: [f32; 3], b: [f32; 3]) -> [f32; 3] {
+    [
+        a[1] * b[2] - a[2] * b[1],
+        a[2] * b[0] - a[0] * b[2],
+        a[0] * b[1] - a[1] * b[0]
+    ]
+}
+
+fn normalize(a: [f32; 3]) -> Option<[f32; 3]> {
+    let length = dot(a, a).sqrt();
+    (length > f32::EPSILON).then(|| scale(a, 1.0 / length))
+}
+
+fn any_perpendicular(normal: [f32; 3]) -> [f32; 3] {
+    let axis = if normal[0].abs() < 0.9 { [1.0, 0.0, 0.0] } else { [0.0, 1.0, 0.0] };
+    normalize(cross(normal, axis)).unwrap_or([1.0, 0.0, 0.0])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triangle(indices: Vec<u32>) -> PrimitiveData {
+        let vertices = [[0.0, 0.0, 0.0], [1.0, 0.0, 0.0], [0.0, 1.0, 0.0]]
+            .map(|position| ModelVertex { position, uv: [position[0], position[1]], ..ModelVertex::default() })
+            .to_vec();
+
+        PrimitiveData { name: String::from("triangle"), vertices, indices }
+    }
+
+    #[test]
+    fn broken_indices_are_rejected() {
+        let path = Path::new("model.obj");
+
+        assert!(triangle(vec![0, 1, 2]).validate(path).is_ok());
+        assert!(triangle(vec![0, 1, 3]).validate(path).is_err());
+        assert!(triangle(vec![0, 1]).validate(path).is_err());
+    }
+
+    #[test]
+    fn generated_normals_and_tangents() {
+        let mut primitive = triangle(vec![0, 1, 2]);
+        primitive.generate_normals();
+        primitive.generate_tangents();
+
+        for vertex in &primitive.vertices {
+            assert_eq!(vertex.normal, [0.0, 0.0, 1.0]);
+            assert_eq!(vertex.tangent, [1.0, 0.0, 0.0, 1.0]);
+        }
+    }
+}
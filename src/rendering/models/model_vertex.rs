@@ -0,0 +1,32 @@
+use std::mem::offset_of;
+
+use bytemuck::{Pod, Zeroable};
+
+use crate::rendering::{Vertex, VertexAttributeDescription, VertexFormat};
+
+//Вершина загруженных моделей. Конвейер для моделей описывает вход через
+//VertexInputDescription::from_vertex::<ModelVertex>() или теми же location
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ModelVertex {
+    pub position: [f32; 3],
+    pub normal: [f32; 3],
+    pub uv: [f32; 2],
+    //w - знак битангенса
+    pub tangent: [f32; 4]
+}
+
+//Только f32 без промежутков, размер 48 байт
+unsafe impl Zeroable for ModelVertex {}
+unsafe impl Pod for ModelVertex {}
+
+impl Vertex for ModelVertex {
+    fn attribute_descriptions(binding: u32) -> Vec<VertexAttributeDescription> {
+        vec![
+            VertexAttributeDescription { location: 0, binding, format: VertexFormat::Vec3, offset: offset_of!(ModelVertex, position) as u32 },
+            VertexAttributeDescription { location: 1, binding, format: VertexFormat::Vec3, offset: offset_of!(ModelVertex, normal) as u32 },
+            VertexAttributeDescription { location: 2, binding, format: VertexFormat::Vec2, offset: offset_of!(ModelVertex, uv) as u32 },
+            VertexAttributeDescription { location: 3, binding, format: VertexFormat::Vec4, offset: offset_of!(ModelVertex, tangent) as u32 }
+        ]
+    }
+}
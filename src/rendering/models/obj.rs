@@ -0,0 +1,76 @@
+use std::path::Path;
+
+use log::warn;
+
+use super::{ModelData, PrimitiveData};
+use crate::rendering::{ModelVertex, RqResult};
+use crate::rendering::RenderingError::LoadModelError;
+
+//Каждый объект/группа OBJ становится отдельным примитивом. Материалы
+//не читаются, их отсутствие не мешает загрузке геометрии
+pub fn load(path: &Path) -> RqResult<ModelData> {
+    let options = tobj::LoadOptions {
+        single_index: true,
+        triangulate: true,
+        ignore_points: true,
+        ignore_lines: true
+    };
+
+    let (models, _) = tobj::load_obj(path, &options)
+        .map_err(|err| LoadModelError(format!("{}: {}", path.display(), err)))?;
+
+    let mut primitives = Vec::with_capacity(models.len());
+    for model in models {
+        let mesh = model.mesh;
+        if mesh.indices.is_empty() {
+            warn!("{}: object \"{}\" has no faces", path.display(), model.name);
+            continue;
+        }
+
+        let has_normals = !mesh.normals.is_empty();
+        let has_uvs = !mesh.texcoords.is_empty();
+
+        //С single_index у всех атрибутов одна нумерация
+        let vertices_count = mesh.positions.len() / 3;
+        if (has_normals && mesh.normals.len() != vertices_count * 3)
+            || (has_uvs && mesh.texcoords.len() != vertices_count * 2) {
+            return Err(LoadModelError(format!(
+                "{}: object \"{}\" has {} positions, {} normals and {} texture coordinates",
+                path.display(), model.name, vertices_count, mesh.normals.len() / 3, mesh.texcoords.len() / 2
+            )));
+        }
+
+        let vertices = (0..vertices_count)
+            .map(|index| ModelVertex {
+                position: [mesh.positions[index * 3], mesh.positions[index * 3 + 1], mesh.positions[index * 3 + 2]],
+                normal: if has_normals {
+                    [mesh.normals[index * 3], mesh.normals[index * 3 + 1], mesh.normals[index * 3 + 2]]
+                } else {
+                    [0.0; 3]
+                },
+                //В OBJ начало координат текстуры внизу, в Vulkan - вверху
+                uv: if has_uvs {
+                    [mesh.texcoords[index * 2], 1.0 - mesh.texcoords[index * 2 + 1]]
+                } else {
+                    [0.0; 2]
+                },
+                tangent: [0.0; 4]
+            })
+            .collect();
+
+        let mut primitive = PrimitiveData {
+            name: model.name,
+            vertices,
+            indices: mesh.indices
+        };
+        primitive.validate(path)?;
+
+        if !has_normals {
+            primitive.generate_normals();
+        }
+        primitive.generate_tangents();
+        primitives.push(primitive);
+    }
+
+    Result::Ok(ModelData { primitives })
+}
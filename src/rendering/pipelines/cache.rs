@@ -0,0 +1,195 @@
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::time::Instant;
+
+use log::{info, warn};
+use vulkanalia::prelude::v1_0::*;
+
+use crate::rendering::RqResult;
+use crate::rendering::RenderingError::{CreatePipelineCacheError, SavePipelineCacheError};
+
+//Заголовок VK_PIPELINE_CACHE_HEADER_VERSION_ONE:
+//длина заголовка, версия, vendorID, deviceID (u32, little-endian)
+//и pipelineCacheUUID (16 байт)
+const HEADER_SIZE: usize = 16 + vk::UUID_SIZE;
+const HEADER_VERSION_ONE: u32 = 1;
+
+//Кэш конвейеров, который переживает перезапуск приложения
+#[derive(Debug)]
+pub struct PipelineCache {
+    pub cache: vk::PipelineCache,
+    path: Option<PathBuf>
+}
+
+impl PipelineCache {
+    //Каталог кэша пользователя, например ~/.cache/VulkanLearning
+    pub fn default_path() -> Option<PathBuf> {
+        dirs::cache_dir()
+            .map(|directory| directory.join("VulkanLearning").join("pipeline_cache.bin"))
+    }
+
+    pub unsafe fn load(
+        instance: &Instance,
+        device: &Device,
+        physical_device: vk::PhysicalDevice,
+        path: Option<PathBuf>
+    ) -> RqResult<Self> {
+        let started = Instant::now();
+        let properties = instance.get_physical_device_properties(physical_device);
+
+        let initial_data = path.as_deref()
+            .and_then(read_cache_file)
+            .filter(|data| {
+                let valid = is_header_valid(data, &properties);
+                if !valid {
+                    warn!("pipeline cache was created by another device or driver and will be ignored");
+                }
+                valid
+            })
+            .unwrap_or_default();
+
+        let cache_info = vk::PipelineCacheCreateInfo::builder()
+            .initial_data(&initial_data);
+
+        let cache = device.create_pipeline_cache(&cache_info, None)
+            .map_err(CreatePipelineCacheError)?;
+
+        info!(
+            "pipeline cache loaded ({} bytes) in {:?}",
+            initial_data.len(),
+            started.elapsed()
+        );
+
+        Result::Ok(Self {
+            cache,
+            path
+        })
+    }
+
+    //Записывает данные кэша во временный файл и переименовывает его,
+    //чтобы падение во время записи не испортило старый кэш
+    pub unsafe fn save(&self, device: &Device) -> RqResult<()> {
+        let Some(path) = &self.path else {
+            return Result::Ok(());
+        };
+
+        let started = Instant::now();
+        let data = device.get_pipeline_cache_data(self.cache)
+            .map_err(|err| SavePipelineCacheError(format!("{}", err)))?;
+
+        write_atomically(path, &data)
+            .map_err(|err| SavePipelineCacheError(format!("{}: {}", path.display(), err)))?;
+
+        info!(
+            "pipeline cache saved ({} bytes) in {:?}",
+            data.len(),
+            started.elapsed()
+        );
+
+        Result::Ok(())
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        device.destroy_pipeline_cache(self.cache, None);
+    }
+}
+
+fn read_cache_file(path: &Path) -> Option<Vec<u8>> {
+    match fs::read(path) {
+        Ok(data) => Some(data),
+        Err(err) => {
+            info!("pipeline cache {} was not loaded: {}", path.display(), err);
+            None
+        }
+    }
+}
+
+fn is_header_valid(data: &[u8], properties: &vk::PhysicalDeviceProperties) -> bool {
+    if data.len() < HEADER_SIZE {
+        return false;
+    }
+
+    let read_u32 = |offset: usize| u32::from_le_bytes([
+        data[offset], data[offset + 1], data[offset + 2], data[offset + 3]
+    ]);
+
+    read_u32(0) as usize >= HEADER_SIZE
+        && read_u32(4) == HEADER_VERSION_ONE
+        && read_u32(8) == properties.vendor_id
+        && read_u32(12) == properties.device_id
+        && data[16..HEADER_SIZE] == properties.pipeline_cache_uuid[..]
+}
+
+fn write_atomically(path: &Path, data: &[u8]) -> std::io::Result<()> {
+    if let Some(directory) = path.parent() {
+        fs::create_dir_all(directory)?;
+    }
+
+    //Номер процесса в имени, чтобы два запущенных приложения не писали в один файл
+    let temporary_path = path.with_extension(format!("{}.tmp", std::process::id()));
+    let mut file = fs::File::create(&temporary_path)?;
+    file.write_all(data)?;
+    file.sync_all()?;
+    drop(file);
+
+    fs::rename(&temporary_path, path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const UUID: [u8; vk::UUID_SIZE] = [7; vk::UUID_SIZE];
+
+    fn properties() -> vk::PhysicalDeviceProperties {
+        vk::PhysicalDeviceProperties {
+            vendor_id: 0x10DE,
+            device_id: 0x2204,
+            pipeline_cache_uuid: UUID.into(),
+            ..Default::default()
+        }
+    }
+
+    fn header(version: u32, vendor_id: u32, device_id: u32, uuid: [u8; vk::UUID_SIZE]) -> Vec<u8> {
+        let mut data = Vec::new();
+        data.extend_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+        data.extend_from_slice(&version.to_le_bytes());
+        data.extend_from_slice(&vendor_id.to_le_bytes());
+        data.extend_from_slice(&device_id.to_le_bytes());
+        data.extend_from_slice(&uuid);
+        //Данные драйвера после заголовка
+        data.extend_from_slice(&[1, 2, 3, 4]);
+        data
+    }
+
+    #[test]
+    fn valid_header_is_accepted() {
+        assert!(is_header_valid(&header(HEADER_VERSION_ONE, 0x10DE, 0x2204, UUID), &properties()));
+    }
+
+    #[test]
+    fn short_buffer_is_rejected() {
+        let data = header(HEADER_VERSION_ONE, 0x10DE, 0x2204, UUID);
+        assert!(!is_header_valid(&data[..HEADER_SIZE - 1], &properties()));
+        assert!(!is_header_valid(&[], &properties()));
+    }
+
+    #[test]
+    fn wrong_version_is_rejected() {
+        assert!(!is_header_valid(&header(2, 0x10DE, 0x2204, UUID), &properties()));
+    }
+
+    #[test]
+    fn other_device_is_rejected() {
+        assert!(!is_header_valid(&header(HEADER_VERSION_ONE, 0x1002, 0x2204, UUID), &properties()));
+        assert!(!is_header_valid(&header(HEADER_VERSION_ONE, 0x10DE, 0x2205, UUID), &properties()));
+    }
+
+    #[test]
+    fn other_driver_uuid_is_rejected() {
+        let mut uuid = UUID;
+        uuid[vk::UUID_SIZE - 1] = 0;
+        assert!(!is_header_valid(&header(HEADER_VERSION_ONE, 0x10DE, 0x2204, uuid), &properties()));
+    }
+}
@@ -0,0 +1,126 @@
+use log::warn;
+use vulkanalia::prelude::v1_0::*;
+
+use super::{CullMode, PolygonMode, RasterizationDescription};
+use crate::rendering::get_enabled_features;
+
+//Отладочный режим отрисовки, применяется ко всем конвейерам поверх описаний.
+//Незаданные поля берутся из описания конвейера
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct DebugRasterization {
+    pub polygon_mode: Option<PolygonMode>,
+    pub cull_mode: Option<CullMode>,
+    pub line_width: Option<f32>,
+    //Каркас поверх закрашенной геометрии, изображение под линиями
+    //умножается на этот цвет
+    pub wireframe_overlay: Option<[f32; 4]>
+}
+
+impl DebugRasterization {
+    //Черные линии
+    pub const OVERLAY_COLOR: [f32; 4] = [0.0, 0.0, 0.0, 1.0];
+}
+
+//Возможности устройства, от которых зависят режимы растеризации
+#[derive(Debug, Clone, Copy)]
+pub struct RasterizationFeatures {
+    pub fill_mode_non_solid: bool,
+    pub wide_lines: bool,
+    pub line_width_range: [f32; 2],
+    pub depth_clamp: bool,
+    pub depth_bias_clamp: bool
+}
+
+impl RasterizationFeatures {
+    pub unsafe fn query(instance: &Instance, physical_device: vk::PhysicalDevice) -> Self {
+        let features = get_enabled_features(instance, physical_device);
+        let limits = instance.get_physical_device_properties(physical_device).limits;
+
+        Self {
+            fill_mode_non_solid: features.fill_mode_non_solid == vk::TRUE,
+            wide_lines: features.wide_lines == vk::TRUE,
+            line_width_range: limits.line_width_range,
+            depth_clamp: features.depth_clamp == vk::TRUE,
+            depth_bias_clamp: features.depth_bias_clamp == vk::TRUE
+        }
+    }
+
+    //Режимы, которые устройство не поддерживает, заменяются допустимыми
+    pub fn resolve(
+        &self,
+        rasterization: &RasterizationDescription,
+        debug: &DebugRasterization
+    ) -> RasterizationDescription {
+        let mut resolved = *rasterization;
+        resolved.polygon_mode = debug.polygon_mode.unwrap_or(rasterization.polygon_mode);
+        resolved.cull_mode = debug.cull_mode.unwrap_or(rasterization.cull_mode);
+        resolved.line_width = debug.line_width.unwrap_or(rasterization.line_width);
+
+        if resolved.polygon_mode != PolygonMode::Fill && !self.fill_mode_non_solid {
+            warn!("fill mode non solid is not supported, polygons will be filled");
+            resolved.polygon_mode = PolygonMode::Fill;
+        }
+
+        if self.wide_lines {
+            let [min, max] = self.line_width_range;
+            resolved.line_width = resolved.line_width.clamp(min, max);
+        } else if resolved.line_width != 1.0 {
+            warn!("wide lines are not supported, line width will be 1.0");
+            resolved.line_width = 1.0;
+        }
+
+        if resolved.depth_clamp && !self.depth_clamp {
+            warn!("depth clamp is not supported, fragments outside the depth range will be clipped");
+            resolved.depth_clamp = false;
+        }
+
+        if let Some(bias) = resolved.depth_bias.as_mut().filter(|bias| bias.clamp != 0.0 && !self.depth_bias_clamp) {
+            warn!("depth bias clamp is not supported, depth bias will not be clamped");
+            bias.clamp = 0.0;
+        }
+
+        resolved
+    }
+
+    pub fn overlay_color(&self, debug: &DebugRasterization) -> Option<[f32; 4]> {
+        debug.wireframe_overlay.filter(|_| {
+            if !self.fill_mode_non_solid {
+                warn!("fill mode non solid is not supported, wireframe overlay will be disabled");
+            }
+            self.fill_mode_non_solid
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rendering::DepthBias;
+
+    #[test]
+    fn unsupported_depth_clamps_are_disabled() {
+        let rasterization = RasterizationDescription {
+            depth_clamp: true,
+            depth_bias: Some(DepthBias { constant_factor: 1.0, clamp: 0.5, slope_factor: 1.0 }),
+            ..RasterizationDescription::default()
+        };
+        let mut features = RasterizationFeatures {
+            fill_mode_non_solid: false,
+            wide_lines: false,
+            line_width_range: [1.0, 1.0],
+            depth_clamp: false,
+            depth_bias_clamp: false
+        };
+
+        let resolved = features.resolve(&rasterization, &DebugRasterization::default());
+        assert!(!resolved.depth_clamp);
+        assert_eq!(resolved.depth_bias.unwrap().clamp, 0.0);
+        assert_eq!(resolved.depth_bias.unwrap().constant_factor, 1.0);
+
+        features.depth_clamp = true;
+        features.depth_bias_clamp = true;
+        let resolved = features.resolve(&rasterization, &DebugRasterization::default());
+        assert!(resolved.depth_clamp);
+        assert_eq!(resolved.depth_bias.unwrap().clamp, 0.5);
+    }
+}
@@ -0,0 +1,750 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use vulkanalia::prelude::v1_0::*;
+
+use crate::rendering::{RqResult, Shader};
+use super::SpecializationMap;
+use crate::rendering::RenderingError::LoadPipelineDescriptionError;
+
+//Описание конвейера, которое читается из файла рядом с шейдерами.
+//Поддерживаются форматы RON, TOML и JSON (по расширению файла)
+//Неизвестные ключи в любом разделе дают ошибку загрузки
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct PipelineDescription {
+    pub name: String,
+    pub shaders: ShaderStagesDescription,
+    pub vertex_input: VertexInputDescription,
+    pub input_assembly: InputAssemblyDescription,
+    pub rasterization: RasterizationDescription,
+    pub depth: DepthState,
+    pub blend: BlendDescription,
+    //Наборы дескрипторов. Если не заданы, берутся из шейдеров
+    pub descriptor_sets: Vec<DescriptorSetDescription>,
+    //Диапазоны push-констант. Если не заданы, берутся из шейдеров
+    pub push_constants: Vec<PushConstantRangeDescription>,
+    //Область вывода и ножницы динамические всегда,
+    //здесь перечисляются дополнительные состояния
+    pub dynamic_states: Vec<DynamicState>,
+    //Каталог файла описания, пути к шейдерам считаются от него
+    #[serde(skip)]
+    pub base_directory: PathBuf
+}
+
+impl PipelineDescription {
+    pub fn load(path: &Path) -> RqResult<Self> {
+        let text = fs::read_to_string(path)
+            .map_err(|err| LoadPipelineDescriptionError(format!("{}: {}", path.display(), err)))?;
+
+        let extension = path.extension()
+            .and_then(|extension| extension.to_str())
+            .unwrap_or("");
+
+        let description: Result<Self, String> = match extension {
+            "ron" => ron::from_str(&text).map_err(|err| err.to_string()),
+            "toml" => toml::from_str(&text).map_err(|err| err.to_string()),
+            "json" => serde_json::from_str(&text).map_err(|err| err.to_string()),
+            _ => Err(format!("unknown description format \"{}\"", extension))
+        };
+
+        let mut description = description
+            .map_err(|err| LoadPipelineDescriptionError(format!("{}: {}", path.display(), err)))?;
+
+        if description.name.is_empty() {
+            description.name = path.file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+        }
+
+        description.base_directory = path.parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+
+        Result::Ok(description)
+    }
+
+    pub fn shader_path(&self, shader: &str) -> PathBuf {
+        self.base_directory.join(shader)
+    }
+
+    //Читает шейдеры, перечисленные в описании
+    pub fn read_shaders(&self, buffer: &mut Vec<u8>) -> RqResult<(Shader, Shader)> {
+        let vertex_shader = Shader::read_file(&self.shader_path(&self.shaders.vertex), buffer)?
+            .with_stage(vk::ShaderStageFlags::VERTEX);
+        let fragment_shader = Shader::read_file(&self.shader_path(&self.shaders.fragment), buffer)?
+            .with_stage(vk::ShaderStageFlags::FRAGMENT);
+
+        Result::Ok((vertex_shader, fragment_shader))
+    }
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ShaderStagesDescription {
+    pub vertex: String,
+    pub fragment: String,
+    //Точки входа, если в модуле их несколько. По умолчанию main
+    pub vertex_entry_point: Option<String>,
+    pub fragment_entry_point: Option<String>,
+    //Перестановки одного модуля без перекомпиляции GLSL
+    pub vertex_specialization: SpecializationMap,
+    pub fragment_specialization: SpecializationMap
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct VertexInputDescription {
+    pub bindings: Vec<VertexBindingDescription>,
+    pub attributes: Vec<VertexAttributeDescription>
+}
+
+impl VertexInputDescription {
+    pub fn binding_descriptions(&self) -> Vec<vk::VertexInputBindingDescription> {
+        self.bindings.iter()
+            .map(|binding| vk::VertexInputBindingDescription::builder()
+                .binding(binding.binding)
+                .stride(binding.stride)
+                .input_rate(binding.input_rate.into())
+                .build())
+            .collect()
+    }
+
+    pub fn attribute_descriptions(&self) -> Vec<vk::VertexInputAttributeDescription> {
+        self.attributes.iter()
+            .map(|attribute| vk::VertexInputAttributeDescription::builder()
+                .location(attribute.location)
+                .binding(attribute.binding)
+                .format(attribute.format.into())
+                .offset(attribute.offset)
+                .build())
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VertexBindingDescription {
+    pub binding: u32,
+    pub stride: u32,
+    #[serde(default)]
+    pub input_rate: VertexInputRate
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct VertexAttributeDescription {
+    pub location: u32,
+    pub binding: u32,
+    pub format: VertexFormat,
+    pub offset: u32
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VertexInputRate {
+    #[default]
+    Vertex,
+    Instance
+}
+
+impl From<VertexInputRate> for vk::VertexInputRate {
+    fn from(rate: VertexInputRate) -> Self {
+        match rate {
+            VertexInputRate::Vertex => vk::VertexInputRate::VERTEX,
+            VertexInputRate::Instance => vk::VertexInputRate::INSTANCE
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum VertexFormat {
+    Float,
+    Vec2,
+    Vec3,
+    Vec4,
+    Int,
+    IVec2,
+    IVec3,
+    IVec4,
+    UInt,
+    UVec2,
+    UVec3,
+    UVec4,
+    Rgba8Unorm
+}
+
+impl From<VertexFormat> for vk::Format {
+    fn from(format: VertexFormat) -> Self {
+        match format {
+            VertexFormat::Float => vk::Format::R32_SFLOAT,
+            VertexFormat::Vec2 => vk::Format::R32G32_SFLOAT,
+            VertexFormat::Vec3 => vk::Format::R32G32B32_SFLOAT,
+            VertexFormat::Vec4 => vk::Format::R32G32B32A32_SFLOAT,
+            VertexFormat::Int => vk::Format::R32_SINT,
+            VertexFormat::IVec2 => vk::Format::R32G32_SINT,
+            VertexFormat::IVec3 => vk::Format::R32G32B32_SINT,
+            VertexFormat::IVec4 => vk::Format::R32G32B32A32_SINT,
+            VertexFormat::UInt => vk::Format::R32_UINT,
+            VertexFormat::UVec2 => vk::Format::R32G32_UINT,
+            VertexFormat::UVec3 => vk::Format::R32G32B32_UINT,
+            VertexFormat::UVec4 => vk::Format::R32G32B32A32_UINT,
+            VertexFormat::Rgba8Unorm => vk::Format::R8G8B8A8_UNORM
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct InputAssemblyDescription {
+    pub topology: PrimitiveTopology,
+    pub primitive_restart: bool
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PrimitiveTopology {
+    PointList,
+    LineList,
+    LineStrip,
+    #[default]
+    TriangleList,
+    TriangleStrip,
+    TriangleFan
+}
+
+impl From<PrimitiveTopology> for vk::PrimitiveTopology {
+    fn from(topology: PrimitiveTopology) -> Self {
+        match topology {
+            PrimitiveTopology::PointList => vk::PrimitiveTopology::POINT_LIST,
+            PrimitiveTopology::LineList => vk::PrimitiveTopology::LINE_LIST,
+            PrimitiveTopology::LineStrip => vk::PrimitiveTopology::LINE_STRIP,
+            PrimitiveTopology::TriangleList => vk::PrimitiveTopology::TRIANGLE_LIST,
+            PrimitiveTopology::TriangleStrip => vk::PrimitiveTopology::TRIANGLE_STRIP,
+            PrimitiveTopology::TriangleFan => vk::PrimitiveTopology::TRIANGLE_FAN
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RasterizationDescription {
+    pub polygon_mode: PolygonMode,
+    pub cull_mode: CullMode,
+    pub front_face: FrontFace,
+    pub line_width: f32,
+    pub depth_clamp: bool,
+    pub depth_bias: Option<DepthBias>
+}
+
+impl Default for RasterizationDescription {
+    fn default() -> Self {
+        Self {
+            polygon_mode: PolygonMode::Fill,
+            cull_mode: CullMode::Back,
+            front_face: FrontFace::Clockwise,
+            line_width: 1.0,
+            depth_clamp: false,
+            depth_bias: None
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DepthBias {
+    pub constant_factor: f32,
+    pub clamp: f32,
+    pub slope_factor: f32
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum PolygonMode {
+    Fill,
+    Line,
+    Point
+}
+
+impl From<PolygonMode> for vk::PolygonMode {
+    fn from(mode: PolygonMode) -> Self {
+        match mode {
+            PolygonMode::Fill => vk::PolygonMode::FILL,
+            PolygonMode::Line => vk::PolygonMode::LINE,
+            PolygonMode::Point => vk::PolygonMode::POINT
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CullMode {
+    None,
+    Front,
+    Back,
+    FrontAndBack
+}
+
+impl From<CullMode> for vk::CullModeFlags {
+    fn from(mode: CullMode) -> Self {
+        match mode {
+            CullMode::None => vk::CullModeFlags::NONE,
+            CullMode::Front => vk::CullModeFlags::FRONT,
+            CullMode::Back => vk::CullModeFlags::BACK,
+            CullMode::FrontAndBack => vk::CullModeFlags::FRONT_AND_BACK
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FrontFace {
+    Clockwise,
+    CounterClockwise
+}
+
+impl From<FrontFace> for vk::FrontFace {
+    fn from(face: FrontFace) -> Self {
+        match face {
+            FrontFace::Clockwise => vk::FrontFace::CLOCKWISE,
+            FrontFace::CounterClockwise => vk::FrontFace::COUNTER_CLOCKWISE
+        }
+    }
+}
+
+//Настройки теста глубины для конкретного конвейера
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DepthState {
+    pub test_enable: bool,
+    pub write_enable: bool,
+    pub compare_op: CompareOp
+}
+
+impl Default for DepthState {
+    fn default() -> Self {
+        Self {
+            test_enable: true,
+            write_enable: true,
+            compare_op: CompareOp::Less
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CompareOp {
+    Never,
+    Less,
+    Equal,
+    LessOrEqual,
+    Greater,
+    NotEqual,
+    GreaterOrEqual,
+    Always
+}
+
+impl From<CompareOp> for vk::CompareOp {
+    fn from(op: CompareOp) -> Self {
+        match op {
+            CompareOp::Never => vk::CompareOp::NEVER,
+            CompareOp::Less => vk::CompareOp::LESS,
+            CompareOp::Equal => vk::CompareOp::EQUAL,
+            CompareOp::LessOrEqual => vk::CompareOp::LESS_OR_EQUAL,
+            CompareOp::Greater => vk::CompareOp::GREATER,
+            CompareOp::NotEqual => vk::CompareOp::NOT_EQUAL,
+            CompareOp::GreaterOrEqual => vk::CompareOp::GREATER_OR_EQUAL,
+            CompareOp::Always => vk::CompareOp::ALWAYS
+        }
+    }
+}
+
+//Смешивание по цветовым вложениям прохода в порядке их объявления.
+//Если вложений больше, чем записей, для остальных повторяется последняя
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BlendDescription {
+    pub attachments: Vec<AttachmentBlend>,
+    pub constants: [f32; 4]
+}
+
+impl BlendDescription {
+    pub fn attachment_states(&self, color_attachments: u32) -> RqResult<Vec<vk::PipelineColorBlendAttachmentState>> {
+        if self.attachments.len() > color_attachments as usize {
+            return Err(LoadPipelineDescriptionError(format!(
+                "blend is described for {} attachments, but pass has {}",
+                self.attachments.len(), color_attachments
+            )));
+        }
+
+        let last = self.attachments.last().copied().unwrap_or_default();
+        let states = (0..color_attachments as usize)
+            .map(|index| self.attachments.get(index).copied().unwrap_or(last))
+            .map(vk::PipelineColorBlendAttachmentState::from)
+            .collect();
+
+        Result::Ok(states)
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AttachmentBlend {
+    pub mode: BlendMode,
+    pub color_write_mask: ColorWriteMask
+}
+
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize)]
+pub enum BlendMode {
+    //Без смешивания
+    #[default]
+    Opaque,
+    //Обычная прозрачность: src * a + dst * (1 - a)
+    Alpha,
+    //Цвет уже умножен на альфу: src + dst * (1 - a)
+    PremultipliedAlpha,
+    //Свечение, частицы: src + dst
+    Additive,
+    //Затемнение, тени: src * dst
+    Multiply,
+    Custom(CustomBlend)
+}
+
+impl BlendMode {
+    pub fn custom_blend(self) -> Option<CustomBlend> {
+        let (src_color_factor, dst_color_factor, src_alpha_factor, dst_alpha_factor) = match self {
+            BlendMode::Opaque => return None,
+            BlendMode::Custom(custom) => return Some(custom),
+            BlendMode::Alpha => (BlendFactor::SrcAlpha, BlendFactor::OneMinusSrcAlpha, BlendFactor::One, BlendFactor::OneMinusSrcAlpha),
+            BlendMode::PremultipliedAlpha => (BlendFactor::One, BlendFactor::OneMinusSrcAlpha, BlendFactor::One, BlendFactor::OneMinusSrcAlpha),
+            BlendMode::Additive => (BlendFactor::One, BlendFactor::One, BlendFactor::One, BlendFactor::One),
+            BlendMode::Multiply => (BlendFactor::DstColor, BlendFactor::Zero, BlendFactor::DstAlpha, BlendFactor::Zero)
+        };
+
+        Some(CustomBlend {
+            src_color_factor,
+            dst_color_factor,
+            color_op: BlendOp::Add,
+            src_alpha_factor,
+            dst_alpha_factor,
+            alpha_op: BlendOp::Add
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CustomBlend {
+    pub src_color_factor: BlendFactor,
+    pub dst_color_factor: BlendFactor,
+    pub color_op: BlendOp,
+    pub src_alpha_factor: BlendFactor,
+    pub dst_alpha_factor: BlendFactor,
+    pub alpha_op: BlendOp
+}
+
+impl Default for CustomBlend {
+    fn default() -> Self {
+        Self {
+            src_color_factor: BlendFactor::One,
+            dst_color_factor: BlendFactor::Zero,
+            color_op: BlendOp::Add,
+            src_alpha_factor: BlendFactor::One,
+            dst_alpha_factor: BlendFactor::Zero,
+            alpha_op: BlendOp::Add
+        }
+    }
+}
+
+impl From<AttachmentBlend> for vk::PipelineColorBlendAttachmentState {
+    fn from(blend: AttachmentBlend) -> Self {
+        let custom = blend.mode.custom_blend();
+        let factors = custom.unwrap_or_default();
+
+        vk::PipelineColorBlendAttachmentState::builder()
+            .color_write_mask(blend.color_write_mask.into())
+            .blend_enable(custom.is_some())
+            .src_color_blend_factor(factors.src_color_factor.into())
+            .dst_color_blend_factor(factors.dst_color_factor.into())
+            .color_blend_op(factors.color_op.into())
+            .src_alpha_blend_factor(factors.src_alpha_factor.into())
+            .dst_alpha_blend_factor(factors.dst_alpha_factor.into())
+            .alpha_blend_op(factors.alpha_op.into())
+            .build()
+    }
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ColorWriteMask {
+    pub r: bool,
+    pub g: bool,
+    pub b: bool,
+    pub a: bool
+}
+
+impl Default for ColorWriteMask {
+    fn default() -> Self {
+        Self { r: true, g: true, b: true, a: true }
+    }
+}
+
+impl From<ColorWriteMask> for vk::ColorComponentFlags {
+    fn from(mask: ColorWriteMask) -> Self {
+        let mut flags = vk::ColorComponentFlags::empty();
+        if mask.r { flags |= vk::ColorComponentFlags::R; }
+        if mask.g { flags |= vk::ColorComponentFlags::G; }
+        if mask.b { flags |= vk::ColorComponentFlags::B; }
+        if mask.a { flags |= vk::ColorComponentFlags::A; }
+        flags
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlendFactor {
+    Zero,
+    One,
+    SrcColor,
+    OneMinusSrcColor,
+    DstColor,
+    OneMinusDstColor,
+    SrcAlpha,
+    OneMinusSrcAlpha,
+    DstAlpha,
+    OneMinusDstAlpha,
+    ConstantColor,
+    OneMinusConstantColor,
+    ConstantAlpha,
+    OneMinusConstantAlpha
+}
+
+impl From<BlendFactor> for vk::BlendFactor {
+    fn from(factor: BlendFactor) -> Self {
+        match factor {
+            BlendFactor::Zero => vk::BlendFactor::ZERO,
+            BlendFactor::One => vk::BlendFactor::ONE,
+            BlendFactor::SrcColor => vk::BlendFactor::SRC_COLOR,
+            BlendFactor::OneMinusSrcColor => vk::BlendFactor::ONE_MINUS_SRC_COLOR,
+            BlendFactor::DstColor => vk::BlendFactor::DST_COLOR,
+            BlendFactor::OneMinusDstColor => vk::BlendFactor::ONE_MINUS_DST_COLOR,
+            BlendFactor::SrcAlpha => vk::BlendFactor::SRC_ALPHA,
+            BlendFactor::OneMinusSrcAlpha => vk::BlendFactor::ONE_MINUS_SRC_ALPHA,
+            BlendFactor::DstAlpha => vk::BlendFactor::DST_ALPHA,
+            BlendFactor::OneMinusDstAlpha => vk::BlendFactor::ONE_MINUS_DST_ALPHA,
+            BlendFactor::ConstantColor => vk::BlendFactor::CONSTANT_COLOR,
+            BlendFactor::OneMinusConstantColor => vk::BlendFactor::ONE_MINUS_CONSTANT_COLOR,
+            BlendFactor::ConstantAlpha => vk::BlendFactor::CONSTANT_ALPHA,
+            BlendFactor::OneMinusConstantAlpha => vk::BlendFactor::ONE_MINUS_CONSTANT_ALPHA
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BlendOp {
+    Add,
+    Subtract,
+    ReverseSubtract,
+    Min,
+    Max
+}
+
+impl From<BlendOp> for vk::BlendOp {
+    fn from(op: BlendOp) -> Self {
+        match op {
+            BlendOp::Add => vk::BlendOp::ADD,
+            BlendOp::Subtract => vk::BlendOp::SUBTRACT,
+            BlendOp::ReverseSubtract => vk::BlendOp::REVERSE_SUBTRACT,
+            BlendOp::Min => vk::BlendOp::MIN,
+            BlendOp::Max => vk::BlendOp::MAX
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DynamicState {
+    Viewport,
+    Scissor,
+    LineWidth,
+    DepthBias,
+    BlendConstants,
+    StencilReference
+}
+
+impl From<DynamicState> for vk::DynamicState {
+    fn from(state: DynamicState) -> Self {
+        match state {
+            DynamicState::Viewport => vk::DynamicState::VIEWPORT,
+            DynamicState::Scissor => vk::DynamicState::SCISSOR,
+            DynamicState::LineWidth => vk::DynamicState::LINE_WIDTH,
+            DynamicState::DepthBias => vk::DynamicState::DEPTH_BIAS,
+            DynamicState::BlendConstants => vk::DynamicState::BLEND_CONSTANTS,
+            DynamicState::StencilReference => vk::DynamicState::STENCIL_REFERENCE
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DescriptorSetDescription {
+    pub set: u32,
+    pub bindings: Vec<DescriptorBindingDescription>
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct DescriptorBindingDescription {
+    pub binding: u32,
+    pub descriptor_type: DescriptorType,
+    #[serde(default = "default_descriptor_count")]
+    pub count: u32,
+    pub stages: Vec<ShaderStage>
+}
+
+fn default_descriptor_count() -> u32 {
+    1
+}
+
+impl From<&DescriptorBindingDescription> for vk::DescriptorSetLayoutBinding {
+    fn from(binding: &DescriptorBindingDescription) -> Self {
+        let stages = binding.stages.iter()
+            .fold(vk::ShaderStageFlags::empty(), |stages, stage| stages | (*stage).into());
+
+        vk::DescriptorSetLayoutBinding::builder()
+            .binding(binding.binding)
+            .descriptor_type(binding.descriptor_type.into())
+            .descriptor_count(binding.count)
+            .stage_flags(stages)
+            .build()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DescriptorType {
+    Sampler,
+    CombinedImageSampler,
+    SampledImage,
+    StorageImage,
+    UniformBuffer,
+    StorageBuffer,
+    UniformBufferDynamic,
+    StorageBufferDynamic,
+    InputAttachment
+}
+
+impl From<DescriptorType> for vk::DescriptorType {
+    fn from(descriptor_type: DescriptorType) -> Self {
+        match descriptor_type {
+            DescriptorType::Sampler => vk::DescriptorType::SAMPLER,
+            DescriptorType::CombinedImageSampler => vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            DescriptorType::SampledImage => vk::DescriptorType::SAMPLED_IMAGE,
+            DescriptorType::StorageImage => vk::DescriptorType::STORAGE_IMAGE,
+            DescriptorType::UniformBuffer => vk::DescriptorType::UNIFORM_BUFFER,
+            DescriptorType::StorageBuffer => vk::DescriptorType::STORAGE_BUFFER,
+            DescriptorType::UniformBufferDynamic => vk::DescriptorType::UNIFORM_BUFFER_DYNAMIC,
+            DescriptorType::StorageBufferDynamic => vk::DescriptorType::STORAGE_BUFFER_DYNAMIC,
+            DescriptorType::InputAttachment => vk::DescriptorType::INPUT_ATTACHMENT
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PushConstantRangeDescription {
+    pub stages: Vec<ShaderStage>,
+    #[serde(default)]
+    pub offset: u32,
+    pub size: u32
+}
+
+impl From<&PushConstantRangeDescription> for vk::PushConstantRange {
+    fn from(range: &PushConstantRangeDescription) -> Self {
+        let stages = range.stages.iter()
+            .fold(vk::ShaderStageFlags::empty(), |stages, stage| stages | (*stage).into());
+
+        vk::PushConstantRange::builder()
+            .stage_flags(stages)
+            .offset(range.offset)
+            .size(range.size)
+            .build()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ShaderStage {
+    Vertex,
+    Fragment
+}
+
+impl From<ShaderStage> for vk::ShaderStageFlags {
+    fn from(stage: ShaderStage) -> Self {
+        match stage {
+            ShaderStage::Vertex => vk::ShaderStageFlags::VERTEX,
+            ShaderStage::Fragment => vk::ShaderStageFlags::FRAGMENT
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rendering::{InstanceTransform, ModelVertex};
+
+    #[test]
+    fn unknown_blend_keys_are_rejected() {
+        let error = ron::from_str::<BlendDescription>("(enable: true, src_color_factor: SrcAlpha)")
+            .unwrap_err()
+            .to_string();
+        assert!(error.contains("enable"), "{}", error);
+
+        assert!(ron::from_str::<AttachmentBlend>("(mode: Alpha, enable: true)").is_err());
+        assert!(ron::from_str::<CustomBlend>("(src_color_factor: One, blend_op: Add)").is_err());
+    }
+
+    #[test]
+    fn unknown_keys_are_rejected() {
+        assert!(ron::from_str::<RasterizationDescription>("(cull_mode: None, depth_clmap: true)").is_err());
+        assert!(ron::from_str::<DepthState>("(test_enable: true, compare: Less)").is_err());
+        assert!(ron::from_str::<VertexBindingDescription>("(binding: 0, stride: 12, rate: Instance)").is_err());
+        assert!(ron::from_str::<PipelineDescription>("(name: \"a\", shader: ())").is_err());
+    }
+
+    #[test]
+    fn shipped_descriptions_load() {
+        let directory = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/assets/shaders");
+        for name in ["Example.pipeline.ron", "Model.pipeline.ron", "Model.instanced.pipeline.ron"] {
+            PipelineDescription::load(&directory.join(name)).unwrap();
+        }
+    }
+
+    #[test]
+    fn blend_modes_load() {
+        let blend = ron::from_str::<BlendDescription>(
+            "(attachments: [(mode: Alpha), (mode: Custom((src_color_factor: One, dst_color_factor: One)))])"
+        ).unwrap();
+
+        assert_eq!(blend.attachment_states(2).unwrap().len(), 2);
+        assert!(blend.attachments[0].mode.custom_blend().is_some());
+    }
+
+    #[test]
+    fn model_pipeline_matches_model_vertex() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/assets/shaders/Model.pipeline.ron");
+        let description = PipelineDescription::load(&path).unwrap();
+
+        let expected = VertexInputDescription::from_vertex::<ModelVertex>();
+        assert_eq!(description.vertex_input.binding_descriptions(), expected.binding_descriptions());
+        assert_eq!(description.vertex_input.attribute_descriptions(), expected.attribute_descriptions());
+
+        //Set 0 должен совпадать с макетом uniform данных очереди
+        assert_eq!(description.descriptor_sets.len(), 1);
+        let camera: vk::DescriptorSetLayoutBinding = (&description.descriptor_sets[0].bindings[0]).into();
+        assert_eq!((description.descriptor_sets[0].set, camera.binding, camera.descriptor_count), (0, 0, 1));
+        assert_eq!(camera.descriptor_type, vk::DescriptorType::UNIFORM_BUFFER);
+        assert_eq!(camera.stage_flags, vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT);
+    }
+
+    #[test]
+    fn instanced_model_pipeline_matches_instance_transform() {
+        let path = Path::new(env!("CARGO_MANIFEST_DIR")).join("src/assets/shaders/Model.instanced.pipeline.ron");
+        let description = PipelineDescription::load(&path).unwrap();
+
+        let expected = VertexInputDescription::from_vertex_and_instance::<ModelVertex, InstanceTransform>();
+        assert_eq!(description.vertex_input.binding_descriptions(), expected.binding_descriptions());
+        assert_eq!(description.vertex_input.attribute_descriptions(), expected.attribute_descriptions());
+    }
+}
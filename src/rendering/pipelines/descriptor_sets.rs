@@ -0,0 +1,64 @@
+use std::collections::BTreeMap;
+
+use vulkanalia::prelude::v1_0::*;
+
+use super::PipelineDescription;
+use crate::rendering::{merge_descriptor_bindings, RqResult, ShaderReflection};
+use crate::rendering::RenderingError::DescribeDescriptorSetsError;
+
+//Наборы из описания имеют приоритет над отраженными из шейдеров,
+//но должны содержать все привязки, которые шейдеры используют
+pub fn descriptor_set_bindings(
+    description: &PipelineDescription,
+    reflections: &[&ShaderReflection]
+) -> RqResult<BTreeMap<u32, Vec<vk::DescriptorSetLayoutBinding>>> {
+    let reflected = merge_descriptor_bindings(reflections)?;
+    if description.descriptor_sets.is_empty() {
+        return Result::Ok(reflected);
+    }
+
+    let mut sets: BTreeMap<u32, Vec<vk::DescriptorSetLayoutBinding>> = BTreeMap::new();
+    for set in &description.descriptor_sets {
+        if sets.contains_key(&set.set) {
+            return Err(DescribeDescriptorSetsError(format!("set {} is described twice", set.set)));
+        }
+
+        let mut bindings: Vec<vk::DescriptorSetLayoutBinding> = Vec::with_capacity(set.bindings.len());
+        for binding in &set.bindings {
+            if bindings.iter().any(|existing| existing.binding == binding.binding) {
+                return Err(DescribeDescriptorSetsError(format!(
+                    "set {} binding {} is described twice",
+                    set.set, binding.binding
+                )));
+            }
+            bindings.push(binding.into());
+        }
+
+        sets.insert(set.set, bindings);
+    }
+
+    for (set, bindings) in &reflected {
+        for binding in bindings {
+            let described = sets.get(set)
+                .and_then(|described| described.iter().find(|described| described.binding == binding.binding));
+
+            match described {
+                Some(described) if described.descriptor_type == binding.descriptor_type
+                    && described.descriptor_count >= binding.descriptor_count
+                    && described.stage_flags.contains(binding.stage_flags) => {}
+                Some(described) => return Err(DescribeDescriptorSetsError(format!(
+                    "set {} binding {}: shaders use {:?} x{} in {:?}, described {:?} x{} in {:?}",
+                    set, binding.binding,
+                    binding.descriptor_type, binding.descriptor_count, binding.stage_flags,
+                    described.descriptor_type, described.descriptor_count, described.stage_flags
+                ))),
+                None => return Err(DescribeDescriptorSetsError(format!(
+                    "set {} binding {} is used by shaders but not described",
+                    set, binding.binding
+                )))
+            }
+        }
+    }
+
+    Result::Ok(sets)
+}
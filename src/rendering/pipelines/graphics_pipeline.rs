@@ -0,0 +1,361 @@
+use std::ffi::CString;
+
+use vulkanalia::prelude::v1_0::*;
+
+use super::{descriptor_set_bindings, push_constant_ranges, CompareOp, DebugRasterization, GraphicsPipeline, PipelineDescription, RasterizationFeatures, SpecializationData};
+use crate::rendering::{check_interface, DescriptorLayoutCache, EntryPoint, RqResult, ShaderReflection};
+use crate::rendering::RenderingError::{BuildPipelinesError, CreatePipelineError, CreatePipelineLayoutError};
+use crate::rendering::shaders::Shader;
+
+//Параметры прохода рендеринга, под который собирается конвейер
+#[derive(Debug, Clone, Copy)]
+pub struct PipelineTarget {
+    pub render_pass: vk::RenderPass,
+    pub cache: vk::PipelineCache,
+    pub samples: vk::SampleCountFlags,
+    pub min_sample_shading: Option<f32>,
+    pub color_attachments: u32,
+    pub features: RasterizationFeatures,
+    pub debug: DebugRasterization,
+    pub max_push_constants_size: u32
+}
+
+pub unsafe fn create_graphics_pipeline(
+    device: &Device,
+    target: &PipelineTarget,
+    layouts: &mut DescriptorLayoutCache,
+    description: &PipelineDescription,
+    vertex_shader: &Shader,
+    fragment_shader: &Shader
+) -> RqResult<GraphicsPipeline> {
+    let shaders = &description.shaders;
+
+    let vertex_entry_point = vertex_shader.entry_point(
+        vk::ShaderStageFlags::VERTEX,
+        shaders.vertex_entry_point.as_deref()
+    )?;
+    let fragment_entry_point = fragment_shader.entry_point(
+        vk::ShaderStageFlags::FRAGMENT,
+        shaders.fragment_entry_point.as_deref()
+    )?;
+
+    let vertex_reflection = vertex_shader.reflection.for_entry_point(vertex_entry_point);
+    let fragment_reflection = fragment_shader.reflection.for_entry_point(fragment_entry_point);
+    check_interface(&vertex_reflection, &fragment_reflection)?;
+
+    let vertex_specialization = SpecializationData::new(&vertex_reflection, &shaders.vertex_specialization)?;
+    let fragment_specialization = SpecializationData::new(&fragment_reflection, &shaders.fragment_specialization)?;
+
+    let vertex_shader_module = create_shader_module(device, vertex_shader)?;
+    let fragment_shader_module = match create_shader_module(device, fragment_shader) {
+        Ok(module) => module,
+        Err(err) => {
+            device.destroy_shader_module(vertex_shader_module, None);
+            return Err(err);
+        }
+    };
+
+    let stages = [
+        StageInfo {
+            stage: vk::ShaderStageFlags::VERTEX,
+            module: vertex_shader_module,
+            entry_point: entry_point_name(vertex_entry_point),
+            specialization: vertex_specialization
+        },
+        StageInfo {
+            stage: vk::ShaderStageFlags::FRAGMENT,
+            module: fragment_shader_module,
+            entry_point: entry_point_name(fragment_entry_point),
+            specialization: fragment_specialization
+        }
+    ];
+
+    let pipeline = build_graphics_pipeline(
+        device,
+        target,
+        layouts,
+        description,
+        [vertex_shader, fragment_shader],
+        &[&vertex_reflection, &fragment_reflection],
+        &stages
+    );
+
+    //Модули нужны только на время создания конвейера
+    device.destroy_shader_module(vertex_shader_module, None);
+    device.destroy_shader_module(fragment_shader_module, None);
+
+    pipeline
+}
+
+//Модуль шейдера с выбранной точкой входа и значениями констант
+struct StageInfo {
+    stage: vk::ShaderStageFlags,
+    module: vk::ShaderModule,
+    entry_point: CString,
+    specialization: SpecializationData
+}
+
+unsafe fn build_graphics_pipeline(
+    device: &Device,
+    target: &PipelineTarget,
+    layouts: &mut DescriptorLayoutCache,
+    description: &PipelineDescription,
+    [vertex_shader, fragment_shader]: [&Shader; 2],
+    reflections: &[&ShaderReflection; 2],
+    stages: &[StageInfo]
+) -> RqResult<GraphicsPipeline> {
+    let blend_attachments = description.blend.attachment_states(target.color_attachments)?;
+
+    let specialization_infos: Vec<vk::SpecializationInfo> = stages.iter()
+        .map(|stage| vk::SpecializationInfo::builder()
+            .map_entries(&stage.specialization.entries)
+            .data(&stage.specialization.data)
+            .build())
+        .collect();
+
+    let pipeline_stages: Vec<vk::PipelineShaderStageCreateInfo> = stages.iter()
+        .zip(&specialization_infos)
+        .map(|(stage, specialization_info)| {
+            let mut info = vk::PipelineShaderStageCreateInfo::builder()
+                .stage(stage.stage)
+                .module(stage.module)
+                .name(stage.entry_point.as_bytes_with_nul());
+
+            if !stage.specialization.is_empty() {
+                info = info.specialization_info(specialization_info);
+            }
+
+            info.build()
+        })
+        .collect();
+
+    //Без явного описания вершинный ввод берется из входов шейдера
+    let (binding_descriptions, attribute_descriptions) = if description.vertex_input.bindings.is_empty() {
+        reflections[0].vertex_input()
+    } else {
+        (
+            description.vertex_input.binding_descriptions(),
+            description.vertex_input.attribute_descriptions()
+        )
+    };
+    let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::builder()
+        .vertex_binding_descriptions(&binding_descriptions)
+        .vertex_attribute_descriptions(&attribute_descriptions)
+        .build();
+
+    let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::builder()
+        .topology(description.input_assembly.topology.into())
+        .primitive_restart_enable(description.input_assembly.primitive_restart)
+        .build();
+
+    //Область вывода и ножницы задаются при записи команд,
+    //поэтому при изменении размера окна конвейер не пересоздается
+    let viewport_state = vk::PipelineViewportStateCreateInfo::builder()
+        .viewport_count(1)
+        .scissor_count(1)
+        .build();
+
+    let rasterization = &target.features.resolve(&description.rasterization, &target.debug);
+    let depth_bias = rasterization.depth_bias;
+    let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
+        //| if true Фрагменты за ближней и дальней областью не отбрасываются
+        .depth_clamp_enable(rasterization.depth_clamp)
+        //| if true скипаем этап растрирования
+        .rasterizer_discard_enable(false)
+        .polygon_mode(rasterization.polygon_mode.into())
+        .line_width(rasterization.line_width)
+        //| Обрабокта граней
+        .cull_mode(rasterization.cull_mode.into())
+        //| Порядок вершин по/против часовой
+        .front_face(rasterization.front_face.into())
+        //  Разрешение изменение значений глубины
+        .depth_bias_enable(depth_bias.is_some())
+        .depth_bias_constant_factor(depth_bias.map_or(0.0, |bias| bias.constant_factor))
+        .depth_bias_clamp(depth_bias.map_or(0.0, |bias| bias.clamp))
+        .depth_bias_slope_factor(depth_bias.map_or(0.0, |bias| bias.slope_factor))
+        .build();
+
+    //Сглаживание
+    let min_sample_shading = target.min_sample_shading;
+    let multisample_state = vk::PipelineMultisampleStateCreateInfo::builder()
+        .sample_shading_enable(min_sample_shading.is_some())
+        .min_sample_shading(min_sample_shading.unwrap_or(0.0))
+        .rasterization_samples(target.samples)
+        .build();
+
+
+    //Буфер глубины
+    let depth_state = &description.depth;
+    let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::builder()
+        .depth_test_enable(depth_state.test_enable)
+        .depth_write_enable(depth_state.write_enable)
+        .depth_compare_op(depth_state.compare_op.into())
+        .depth_bounds_test_enable(false)
+        .min_depth_bounds(0.0)
+        .max_depth_bounds(1.0)
+        .stencil_test_enable(false)
+        .build();
+
+    let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
+        .logic_op_enable(false)
+        .logic_op(vk::LogicOp::COPY)
+        .attachments(&blend_attachments)
+        .blend_constants(description.blend.constants)
+        .build();
+
+    //Каркас рисуется вторым конвейером поверх уже нарисованной геометрии:
+    //глубина не пишется, линии смещаются к камере, цвет под ними умножается на цвет каркаса
+    let overlay_color = target.features.overlay_color(&target.debug);
+    let (overlay_compare_op, overlay_bias) = match depth_state.compare_op {
+        CompareOp::Greater | CompareOp::GreaterOrEqual => (vk::CompareOp::GREATER_OR_EQUAL, 1.0),
+        CompareOp::Less | CompareOp::LessOrEqual => (vk::CompareOp::LESS_OR_EQUAL, -1.0),
+        compare_op => (compare_op.into(), 0.0)
+    };
+
+    let overlay_rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
+        .depth_clamp_enable(rasterization.depth_clamp)
+        .rasterizer_discard_enable(false)
+        .polygon_mode(vk::PolygonMode::LINE)
+        .line_width(rasterization.line_width)
+        .cull_mode(vk::CullModeFlags::NONE)
+        .front_face(rasterization.front_face.into())
+        .depth_bias_enable(overlay_bias != 0.0)
+        .depth_bias_constant_factor(overlay_bias)
+        .depth_bias_slope_factor(overlay_bias)
+        .build();
+
+    let overlay_depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::builder()
+        .depth_test_enable(depth_state.test_enable)
+        .depth_write_enable(false)
+        .depth_compare_op(overlay_compare_op)
+        .depth_bounds_test_enable(false)
+        .min_depth_bounds(0.0)
+        .max_depth_bounds(1.0)
+        .stencil_test_enable(false)
+        .build();
+
+    let overlay_blend_attachments: Vec<vk::PipelineColorBlendAttachmentState> = blend_attachments.iter()
+        .map(|attachment| vk::PipelineColorBlendAttachmentState::builder()
+            .color_write_mask(attachment.color_write_mask)
+            .blend_enable(true)
+            .src_color_blend_factor(vk::BlendFactor::ZERO)
+            .dst_color_blend_factor(vk::BlendFactor::CONSTANT_COLOR)
+            .color_blend_op(vk::BlendOp::ADD)
+            .src_alpha_blend_factor(vk::BlendFactor::ZERO)
+            .dst_alpha_blend_factor(vk::BlendFactor::ONE)
+            .alpha_blend_op(vk::BlendOp::ADD)
+            .build())
+        .collect();
+
+    let overlay_color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
+        .logic_op_enable(false)
+        .logic_op(vk::LogicOp::COPY)
+        .attachments(&overlay_blend_attachments)
+        .blend_constants(overlay_color.unwrap_or_default())
+        .build();
+
+    let mut dynamic_states = vec![vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+    for state in &description.dynamic_states {
+        let state = vk::DynamicState::from(*state);
+        if !dynamic_states.contains(&state) {
+            dynamic_states.push(state);
+        }
+    }
+
+    let dynamic_state = vk::PipelineDynamicStateCreateInfo::builder()
+        .dynamic_states(&dynamic_states)
+        .build();
+
+    //Смещение глубины и константы смешивания каркаса задаются самим конвейером
+    let overlay_dynamic_states: Vec<vk::DynamicState> = dynamic_states.iter()
+        .copied()
+        .filter(|state| *state != vk::DynamicState::DEPTH_BIAS && *state != vk::DynamicState::BLEND_CONSTANTS)
+        .collect();
+
+    let overlay_dynamic_state = vk::PipelineDynamicStateCreateInfo::builder()
+        .dynamic_states(&overlay_dynamic_states)
+        .build();
+
+    let push_constant_ranges = push_constant_ranges(description, reflections, target.max_push_constants_size)?;
+    let set_layouts = layouts.set_layouts(device, &descriptor_set_bindings(description, reflections)?)?;
+
+    let layout_info = vk::PipelineLayoutCreateInfo::builder()
+        .set_layouts(&set_layouts)
+        .push_constant_ranges(&push_constant_ranges)
+        .build();
+
+    let pipeline_layout = device.create_pipeline_layout(&layout_info, None)
+        .map_err(CreatePipelineLayoutError)?;
+
+    let pipeline_info = vk::GraphicsPipelineCreateInfo::builder()
+        .stages(&pipeline_stages)
+        .vertex_input_state(&vertex_input_state)
+        .input_assembly_state(&input_assembly_state)
+        .viewport_state(&viewport_state)
+        .rasterization_state(&rasterization_state)
+        .multisample_state(&multisample_state)
+        .depth_stencil_state(&depth_stencil_state)
+        .color_blend_state(&color_blend_state)
+        .layout(pipeline_layout)
+        .render_pass(target.render_pass)
+        .subpass(0)
+        .dynamic_state(&dynamic_state)
+        // .base_pipeline_handle(vk::Pipeline::null())
+        // .base_pipeline_index(-1)
+        .build();
+
+    let mut pipeline_infos = vec![pipeline_info];
+    if overlay_color.is_some() {
+        pipeline_infos.push(vk::GraphicsPipelineCreateInfo {
+            rasterization_state: &overlay_rasterization_state,
+            depth_stencil_state: &overlay_depth_stencil_state,
+            color_blend_state: &overlay_color_blend_state,
+            dynamic_state: &overlay_dynamic_state,
+            ..pipeline_info
+        });
+    }
+
+    let pipelines = device.create_graphics_pipelines(
+        target.cache,
+        &pipeline_infos,
+        None
+    );
+
+    let pipelines = match pipelines {
+        Ok(pipelines) => pipelines,
+        Err(err) => {
+            device.destroy_pipeline_layout(pipeline_layout, None);
+            return Err(BuildPipelinesError(err));
+        }
+    };
+
+    Result::Ok(GraphicsPipeline {
+        name: description.name.clone(),
+        pipeline: pipelines.0[0],
+        overlay: pipelines.0.get(1).copied(),
+        layout: pipeline_layout,
+        set_layouts,
+        push_constant_ranges,
+        vertex_bindings: binding_descriptions,
+        vertex_attributes: attribute_descriptions,
+        description: description.clone(),
+        vertex_shader: vertex_shader.clone(),
+        fragment_shader: fragment_shader.clone(),
+        pass: None
+    })
+}
+
+
+unsafe fn create_shader_module(device: &Device, shader: &Shader) -> RqResult<vk::ShaderModule> {
+    let info = vk::ShaderModuleCreateInfo::builder()
+        .code_size(shader.bytecode.code_size())
+        .code(shader.bytecode.code());
+
+    device.create_shader_module(&info, None)
+        .map_err(CreatePipelineError)
+}
+
+//Имя из SPIR-V не содержит нулей, кроме завершающего
+fn entry_point_name(entry_point: &EntryPoint) -> CString {
+    CString::new(entry_point.name.as_str()).unwrap_or_default()
+}
@@ -0,0 +1,17 @@
+mod cache;
+mod debug_rasterization;
+mod descriptor_sets;
+mod description;
+mod graphics_pipeline;
+mod push_constants;
+mod registry;
+mod specialization;
+
+pub use cache::*;
+pub use debug_rasterization::*;
+pub use descriptor_sets::*;
+pub use description::*;
+pub use graphics_pipeline::*;
+pub use push_constants::*;
+pub use registry::*;
+pub use specialization::*;
@@ -0,0 +1,216 @@
+use std::mem::size_of;
+
+use bytemuck::{bytes_of, Pod};
+use vulkanalia::prelude::v1_0::*;
+
+use super::{GraphicsPipeline, PipelineDescription};
+use crate::rendering::{merge_push_constant_ranges, RqResult, ShaderReflection};
+use crate::rendering::RenderingError::PushConstantsError;
+
+//Диапазоны из описания имеют приоритет над отраженными из шейдеров,
+//но должны покрывать блоки push-констант, которые шейдеры объявляют
+pub fn push_constant_ranges(
+    description: &PipelineDescription,
+    reflections: &[&ShaderReflection],
+    max_push_constants_size: u32
+) -> RqResult<Vec<vk::PushConstantRange>> {
+    if description.push_constants.is_empty() {
+        let ranges = merge_push_constant_ranges(reflections);
+        validate_ranges(&ranges, max_push_constants_size)?;
+        return Result::Ok(ranges);
+    }
+
+    let ranges: Vec<vk::PushConstantRange> = description.push_constants.iter()
+        .map(vk::PushConstantRange::from)
+        .collect();
+    validate_ranges(&ranges, max_push_constants_size)?;
+
+    for reflection in reflections {
+        let Some(block) = &reflection.push_constants else {
+            continue;
+        };
+
+        //Диапазоны уже проверены на переполнение, а у стадии он может быть только один
+        let stages = reflection.stages();
+        let block_end = block.offset + block.size;
+        let covered = ranges.iter()
+            .filter(|range| range.stage_flags.contains(stages))
+            .any(|range| range.offset <= block.offset && block_end <= range.offset + range.size);
+
+        if !covered {
+            return Err(PushConstantsError(format!(
+                "block \"{}\" of {:?} stage takes [{}, {}), but no described range of the stage contains it",
+                block.name, stages, block.offset, block_end
+            )));
+        }
+    }
+
+    Result::Ok(ranges)
+}
+
+fn validate_ranges(ranges: &[vk::PushConstantRange], max_push_constants_size: u32) -> RqResult<()> {
+    for (index, range) in ranges.iter().enumerate() {
+        if range.stage_flags.is_empty() {
+            return Err(PushConstantsError(format!("range {} has no stages", index)));
+        }
+
+        if range.size == 0 || !range.size.is_multiple_of(4) || !range.offset.is_multiple_of(4) {
+            return Err(PushConstantsError(format!(
+                "range {} (offset {}, size {}) must be non-empty and aligned to 4 bytes",
+                index, range.offset, range.size
+            )));
+        }
+
+        let end = range_end(range)?;
+        if end > max_push_constants_size {
+            return Err(PushConstantsError(format!(
+                "range {} ends at {} bytes, device allows {}",
+                index, end, max_push_constants_size
+            )));
+        }
+
+        //Одна стадия может входить только в один диапазон макета
+        if let Some(other) = ranges[..index].iter().find(|other| other.stage_flags.intersects(range.stage_flags)) {
+            return Err(PushConstantsError(format!(
+                "stages {:?} are declared in several ranges",
+                other.stage_flags & range.stage_flags
+            )));
+        }
+    }
+
+    Result::Ok(())
+}
+
+fn range_end(range: &vk::PushConstantRange) -> RqResult<u32> {
+    range.offset.checked_add(range.size)
+        .ok_or_else(|| PushConstantsError(format!(
+            "range (offset {}, size {}) overflows",
+            range.offset, range.size
+        )))
+}
+
+//Стадии для записи [offset, offset + size): все диапазоны, которые задевает
+//значение, должны целиком его содержать
+fn push_constant_stages(ranges: &[vk::PushConstantRange], offset: u32, size: u32) -> RqResult<vk::ShaderStageFlags> {
+    if size == 0 || !size.is_multiple_of(4) || !offset.is_multiple_of(4) {
+        return Err(PushConstantsError(format!(
+            "value (offset {}, size {}) must be non-empty and aligned to 4 bytes",
+            offset, size
+        )));
+    }
+
+    let end = offset.checked_add(size)
+        .ok_or_else(|| PushConstantsError(format!("value (offset {}, size {}) overflows", offset, size)))?;
+    let mut stages = vk::ShaderStageFlags::empty();
+    for range in ranges {
+        let range_end = range_end(range)?;
+        if range_end <= offset || end <= range.offset {
+            continue;
+        }
+
+        if range.offset > offset || range_end < end {
+            return Err(PushConstantsError(format!(
+                "value [{}, {}) crosses range [{}, {}) of {:?}",
+                offset, end, range.offset, range_end, range.stage_flags
+            )));
+        }
+        stages |= range.stage_flags;
+    }
+
+    if stages.is_empty() {
+        return Err(PushConstantsError(format!("no range contains value [{}, {})", offset, end)));
+    }
+
+    Result::Ok(stages)
+}
+
+impl GraphicsPipeline {
+    //Значение копируется в командный буфер как есть,
+    //раскладка T должна совпадать с блоком в шейдере (std430).
+    //Pod гарантирует, что в T нет неинициализированных байт выравнивания
+    pub unsafe fn push_constants<T: Pod>(
+        &self,
+        device: &Device,
+        command_buffer: vk::CommandBuffer,
+        offset: u32,
+        value: &T
+    ) -> RqResult<()> {
+        let size = size_of::<T>() as u32;
+        let stages = push_constant_stages(&self.push_constant_ranges, offset, size)?;
+
+        device.cmd_push_constants(command_buffer, self.layout, stages, offset, bytes_of(value));
+
+        Result::Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rendering::{EntryPoint, PushConstantBlock, PushConstantRangeDescription, ShaderStage};
+
+    fn range(stages: vk::ShaderStageFlags, offset: u32, size: u32) -> vk::PushConstantRange {
+        vk::PushConstantRange::builder()
+            .stage_flags(stages)
+            .offset(offset)
+            .size(size)
+            .build()
+    }
+
+    fn reflection(stage: vk::ShaderStageFlags, offset: u32, size: u32) -> ShaderReflection {
+        ShaderReflection {
+            entry_points: vec![EntryPoint { name: String::from("main"), stage, interface: Vec::new() }],
+            push_constants: Some(PushConstantBlock { name: String::from("Constants"), offset, size }),
+            ..ShaderReflection::default()
+        }
+    }
+
+    fn description(ranges: &[(ShaderStage, u32, u32)]) -> PipelineDescription {
+        PipelineDescription {
+            push_constants: ranges.iter()
+                .map(|&(stage, offset, size)| PushConstantRangeDescription { stages: vec![stage], offset, size })
+                .collect(),
+            ..PipelineDescription::default()
+        }
+    }
+
+    #[test]
+    fn described_range_must_contain_block() {
+        let vertex = reflection(vk::ShaderStageFlags::VERTEX, 0, 64);
+        let fragment = reflection(vk::ShaderStageFlags::FRAGMENT, 64, 16);
+        let described = description(&[(ShaderStage::Vertex, 0, 64), (ShaderStage::Fragment, 64, 16)]);
+        assert!(push_constant_ranges(&described, &[&vertex, &fragment], 128).is_ok());
+
+        //Диапазон фрагментной стадии заканчивается там же, но начинается после блока
+        let fragment = reflection(vk::ShaderStageFlags::FRAGMENT, 48, 32);
+        assert!(push_constant_ranges(&described, &[&vertex, &fragment], 128).is_err());
+
+        //Конец диапазона вершинной стадии дальше конца блока, но блок туда не помещается
+        let described = description(&[(ShaderStage::Vertex, 64, 64), (ShaderStage::Fragment, 64, 16)]);
+        assert!(push_constant_ranges(&described, &[&vertex], 128).is_err());
+    }
+
+    #[test]
+    fn overflowing_ranges_are_rejected() {
+        let ranges = [range(vk::ShaderStageFlags::VERTEX, u32::MAX - 3, 8)];
+        assert!(validate_ranges(&ranges, 128).is_err());
+        assert!(push_constant_stages(&ranges, 0, 4).is_err());
+
+        let ranges = [range(vk::ShaderStageFlags::VERTEX, 0, 16)];
+        assert!(push_constant_stages(&ranges, u32::MAX - 3, 8).is_err());
+    }
+
+    #[test]
+    fn value_takes_stages_of_containing_ranges() {
+        let ranges = [
+            range(vk::ShaderStageFlags::VERTEX, 0, 64),
+            range(vk::ShaderStageFlags::FRAGMENT, 64, 16)
+        ];
+
+        assert_eq!(push_constant_stages(&ranges, 0, 64).unwrap(), vk::ShaderStageFlags::VERTEX);
+        assert_eq!(push_constant_stages(&ranges, 64, 16).unwrap(), vk::ShaderStageFlags::FRAGMENT);
+        assert!(push_constant_stages(&ranges, 48, 32).is_err());
+        assert!(push_constant_stages(&ranges, 80, 4).is_err());
+        assert!(push_constant_stages(&ranges, 2, 4).is_err());
+    }
+}
@@ -0,0 +1,121 @@
+use std::collections::HashMap;
+
+use vulkanalia::prelude::v1_0::*;
+
+use super::PipelineDescription;
+use crate::rendering::{FrameDescriptorSets, Shader};
+
+//Индекс конвейера в реестре, действителен все время жизни очереди
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct PipelineHandle(usize);
+
+#[derive(Debug)]
+pub struct GraphicsPipeline {
+    pub name: String,
+    pub pipeline: vk::Pipeline,
+    //Каркас поверх геометрии, если включен отладочный режим
+    pub overlay: Option<vk::Pipeline>,
+    pub layout: vk::PipelineLayout,
+    //Макеты принадлежат DescriptorLayoutCache и общие для конвейеров
+    pub set_layouts: Vec<vk::DescriptorSetLayout>,
+    pub push_constant_ranges: Vec<vk::PushConstantRange>,
+    //Пустой, если вершины строятся в шейдере без буферов
+    pub vertex_bindings: Vec<vk::VertexInputBindingDescription>,
+    pub vertex_attributes: Vec<vk::VertexInputAttributeDescription>,
+    //Описание, из которого собран конвейер, нужно для пересборки
+    pub description: PipelineDescription,
+    //Шейдеры хранятся для пересборки без чтения файлов
+    pub vertex_shader: Shader,
+    pub fragment_shader: Shader,
+    //Проход графа, под который собран конвейер. None - основной проход
+    pub pass: Option<String>
+}
+
+impl GraphicsPipeline {
+    //Конвейер и каркас поверх него, оба рисуются одними и теми же командами
+    pub fn variants(&self) -> impl Iterator<Item = vk::Pipeline> {
+        std::iter::once(self.pipeline).chain(self.overlay)
+    }
+
+    //Uniform данные кадра привязываются в set 0, если макет набора совпадает
+    //с макетом set 0 конвейера. Общий кэш макетов дает одинаковым привязкам один макет
+    pub unsafe fn bind_uniform_set(
+        &self,
+        device: &Device,
+        command_buffer: vk::CommandBuffer,
+        uniform_sets: Option<&FrameDescriptorSets>,
+        frame_index: usize
+    ) {
+        if let Some(sets) = uniform_sets.filter(|sets| self.set_layouts.first() == Some(&sets.layout)) {
+            device.cmd_bind_descriptor_sets(
+                command_buffer,
+                vk::PipelineBindPoint::GRAPHICS,
+                self.layout,
+                0,
+                &[sets.get(frame_index)],
+                &[]
+            );
+        }
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        self.variants().for_each(|pipeline| device.destroy_pipeline(pipeline, None));
+        device.destroy_pipeline_layout(self.layout, None);
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct PipelineRegistry {
+    pipelines: Vec<GraphicsPipeline>,
+    names: HashMap<String, PipelineHandle>
+}
+
+impl PipelineRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    //Конвейер с уже занятым именем заменяется, старый возвращается
+    //вызывающему для уничтожения
+    pub fn insert(&mut self, pipeline: GraphicsPipeline) -> (PipelineHandle, Option<GraphicsPipeline>) {
+        if let Some(handle) = self.names.get(&pipeline.name) {
+            let old = std::mem::replace(&mut self.pipelines[handle.0], pipeline);
+            return (*handle, Some(old));
+        }
+
+        let handle = PipelineHandle(self.pipelines.len());
+        self.names.insert(pipeline.name.clone(), handle);
+        self.pipelines.push(pipeline);
+
+        (handle, None)
+    }
+
+    pub fn get(&self, handle: PipelineHandle) -> Option<&GraphicsPipeline> {
+        self.pipelines.get(handle.0)
+    }
+
+    pub fn find(&self, name: &str) -> Option<PipelineHandle> {
+        self.names.get(name).copied()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = (PipelineHandle, &GraphicsPipeline)> {
+        self.pipelines.iter()
+            .enumerate()
+            .map(|(index, pipeline)| (PipelineHandle(index), pipeline))
+    }
+
+    pub fn len(&self) -> usize {
+        self.pipelines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.pipelines.is_empty()
+    }
+
+    pub unsafe fn destroy(&mut self, device: &Device) {
+        for pipeline in self.pipelines.drain(..) {
+            pipeline.destroy(device);
+        }
+        self.names.clear();
+    }
+}
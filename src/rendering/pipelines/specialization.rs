@@ -0,0 +1,166 @@
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+use vulkanalia::prelude::v1_0::*;
+
+use crate::rendering::{ConstantType, RenderingError, RqResult, ShaderReflection, SpecializationConstant};
+use crate::rendering::RenderingError::SpecializationError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum SpecializationValue {
+    Bool(bool),
+    Int(i64),
+    Float(f64)
+}
+
+//Ключ - SpecId числом ("3") или имя константы в шейдере ("LIGHTS_COUNT")
+pub type SpecializationMap = BTreeMap<String, SpecializationValue>;
+
+//Значения, упакованные по типам констант из шейдера
+#[derive(Debug, Clone, Default)]
+pub struct SpecializationData {
+    pub entries: Vec<vk::SpecializationMapEntry>,
+    pub data: Vec<u8>
+}
+
+impl SpecializationData {
+    pub fn new(reflection: &ShaderReflection, values: &SpecializationMap) -> RqResult<Self> {
+        let mut specialization = Self::default();
+        //Одна константа может быть задана и по SpecId, и по имени
+        let mut ids = BTreeSet::new();
+
+        for (key, value) in values {
+            let constant = find_constant(reflection, key)
+                .ok_or_else(|| SpecializationError(format!("unknown specialization constant \"{}\"", key)))?;
+            if !ids.insert(constant.id) {
+                return Err(SpecializationError(format!(
+                    "\"{}\" (id {}) is specialized twice", constant.name, constant.id
+                )));
+            }
+
+            let bytes = pack_value(constant, *value)?;
+
+            specialization.entries.push(vk::SpecializationMapEntry::builder()
+                .constant_id(constant.id)
+                .offset(specialization.data.len() as u32)
+                .size(bytes.len())
+                .build());
+            specialization.data.extend_from_slice(&bytes);
+        }
+
+        Result::Ok(specialization)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+}
+
+fn find_constant<'a>(reflection: &'a ShaderReflection, key: &str) -> Option<&'a SpecializationConstant> {
+    match key.parse::<u32>() {
+        Ok(id) => reflection.specialization_constants.iter()
+            .find(|constant| constant.id == id),
+        Err(_) => reflection.find_specialization_constant(key)
+    }
+}
+
+fn pack_value(constant: &SpecializationConstant, value: SpecializationValue) -> RqResult<Vec<u8>> {
+    let bytes = match (constant.constant_type, value) {
+        (ConstantType::Bool, SpecializationValue::Bool(value)) => (value as u32).to_le_bytes().to_vec(),
+        (ConstantType::Int { width: 32, signed: true }, SpecializationValue::Int(value)) => i32::try_from(value)
+            .map(|value| value.to_le_bytes().to_vec())
+            .map_err(|_| out_of_range(constant, value))?,
+        (ConstantType::Int { width: 32, signed: false }, SpecializationValue::Int(value)) => u32::try_from(value)
+            .map(|value| value.to_le_bytes().to_vec())
+            .map_err(|_| out_of_range(constant, value))?,
+        (ConstantType::Int { width: 64, signed: true }, SpecializationValue::Int(value)) => value.to_le_bytes().to_vec(),
+        (ConstantType::Int { width: 64, signed: false }, SpecializationValue::Int(value)) => u64::try_from(value)
+            .map(|value| value.to_le_bytes().to_vec())
+            .map_err(|_| out_of_range(constant, value))?,
+        (ConstantType::Float { width: 32 }, SpecializationValue::Float(value)) => (value as f32).to_le_bytes().to_vec(),
+        (ConstantType::Float { width: 32 }, SpecializationValue::Int(value)) => (value as f32).to_le_bytes().to_vec(),
+        (ConstantType::Float { width: 64 }, SpecializationValue::Float(value)) => value.to_le_bytes().to_vec(),
+        (ConstantType::Float { width: 64 }, SpecializationValue::Int(value)) => (value as f64).to_le_bytes().to_vec(),
+        (constant_type, value) => return Err(SpecializationError(format!(
+            "\"{}\" (id {}) is {:?}, got {:?}",
+            constant.name, constant.id, constant_type, value
+        )))
+    };
+
+    Result::Ok(bytes)
+}
+
+fn out_of_range(constant: &SpecializationConstant, value: i64) -> RenderingError {
+    SpecializationError(format!(
+        "\"{}\" (id {}): {} does not fit {:?}",
+        constant.name, constant.id, value, constant.constant_type
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn constant(name: &str, id: u32, constant_type: ConstantType) -> SpecializationConstant {
+        SpecializationConstant { name: String::from(name), id, constant_type }
+    }
+
+    fn reflection() -> ShaderReflection {
+        ShaderReflection {
+            specialization_constants: vec![
+                constant("LIGHTS_COUNT", 3, ConstantType::Int { width: 32, signed: false }),
+                constant("USE_FOG", 5, ConstantType::Bool)
+            ],
+            ..ShaderReflection::default()
+        }
+    }
+
+    fn map(entries: &[(&str, SpecializationValue)]) -> SpecializationMap {
+        entries.iter().map(|(key, value)| (String::from(*key), *value)).collect()
+    }
+
+    #[test]
+    fn constants_are_found_by_id_and_name() {
+        let data = SpecializationData::new(&reflection(), &map(&[
+            ("LIGHTS_COUNT", SpecializationValue::Int(4)),
+            ("5", SpecializationValue::Bool(true))
+        ])).unwrap();
+
+        let ids: Vec<u32> = data.entries.iter().map(|entry| entry.constant_id).collect();
+        assert_eq!(ids, vec![5, 3]);
+        assert_eq!(data.data, [1, 0, 0, 0, 4, 0, 0, 0]);
+
+        assert!(SpecializationData::new(&reflection(), &map(&[("7", SpecializationValue::Int(1))])).is_err());
+        assert!(SpecializationData::new(&reflection(), &map(&[("FOG", SpecializationValue::Bool(true))])).is_err());
+    }
+
+    #[test]
+    fn same_constant_by_id_and_name_is_rejected() {
+        let values = map(&[
+            ("3", SpecializationValue::Int(1)),
+            ("LIGHTS_COUNT", SpecializationValue::Int(2))
+        ]);
+        assert!(SpecializationData::new(&reflection(), &values).is_err());
+    }
+
+    #[test]
+    fn values_are_packed_by_constant_type() {
+        let int = constant("I", 0, ConstantType::Int { width: 32, signed: true });
+        let uint = constant("U", 1, ConstantType::Int { width: 32, signed: false });
+        let float = constant("F", 2, ConstantType::Float { width: 32 });
+
+        assert_eq!(pack_value(&int, SpecializationValue::Int(-1)).unwrap(), (-1i32).to_le_bytes());
+        assert!(pack_value(&int, SpecializationValue::Int(i32::MAX as i64 + 1)).is_err());
+        assert!(pack_value(&uint, SpecializationValue::Int(-1)).is_err());
+        assert!(pack_value(&uint, SpecializationValue::Int(u32::MAX as i64 + 1)).is_err());
+        assert_eq!(pack_value(&uint, SpecializationValue::Int(u32::MAX as i64)).unwrap(), u32::MAX.to_le_bytes());
+
+        //Целое значение подходит для float константы
+        assert_eq!(pack_value(&float, SpecializationValue::Int(2)).unwrap(), 2.0f32.to_le_bytes());
+
+        assert!(pack_value(&int, SpecializationValue::Float(1.0)).is_err());
+        assert!(pack_value(&int, SpecializationValue::Bool(true)).is_err());
+        assert!(pack_value(&float, SpecializationValue::Bool(false)).is_err());
+    }
+}
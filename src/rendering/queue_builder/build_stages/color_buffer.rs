@@ -0,0 +1,54 @@
+use vulkanalia::prelude::v1_0::*;
+
+use super::DepthBufferBuildStage;
+use crate::rendering::{choose_sample_count, ColorBuffer, QueueFamilyIndices, RqResult, SwapChainData};
+
+pub struct ColorBufferBuildStage {
+    pub entry: Box<Entry>,
+    pub instance: Box<Instance>,
+    pub messenger: Option<vk::DebugUtilsMessengerEXT>,
+    pub physical_device: vk::PhysicalDevice,
+    pub logical_device: Box<Device>,
+    pub queue_families: QueueFamilyIndices,
+    pub surface: vk::SurfaceKHR,
+    pub swap_chain: Box<SwapChainData>
+}
+
+impl ColorBufferBuildStage {
+    //samples - желаемый уровень MSAA, будет ограничен возможностями устройства
+    //min_sample_shading - доля выборок, для которых выполняется фрагментный шейдер
+    pub fn create_color_buffer(
+        self,
+        samples: vk::SampleCountFlags,
+        min_sample_shading: Option<f32>
+    ) -> RqResult<DepthBufferBuildStage> {
+        let color_buffer = unsafe {
+            let samples = choose_sample_count(
+                &self.instance,
+                self.physical_device,
+                samples
+            );
+
+            ColorBuffer::create(
+                &self.instance,
+                &self.logical_device,
+                self.physical_device,
+                &self.swap_chain,
+                samples,
+                min_sample_shading
+            )?
+        };
+
+        Result::Ok(DepthBufferBuildStage {
+            entry: self.entry,
+            instance: self.instance,
+            messenger: self.messenger,
+            physical_device: self.physical_device,
+            logical_device: self.logical_device,
+            queue_families: self.queue_families,
+            surface: self.surface,
+            swap_chain: self.swap_chain,
+            color_buffer: Box::new(color_buffer)
+        })
+    }
+}
@@ -1,5 +1,5 @@
 use vulkanalia::prelude::v1_0::*;
-use vulkanalia::vk::PipelineLayout;
+use crate::rendering::{ColorBuffer, CompiledGraph, DepthBuffer, DescriptorLayoutCache, FrameDescriptorSets, Mesh, PipelineCache, PipelineRegistry, ViewportRect};
 use crate::rendering::RenderingError::CreateCommandBufferError;
 use crate::rendering::RqResult;
 use super::SyncObjectsBuildStage;
@@ -13,68 +13,33 @@ pub struct CommandBufferBuildStage {
     pub queue_families: super::QueueFamilyIndices,
     pub surface: vk::SurfaceKHR,
     pub swap_chain: Box<super::SwapChainData>,
+    pub color_buffer: Box<ColorBuffer>,
+    pub depth_buffer: Box<DepthBuffer>,
     pub render_pass: vk::RenderPass,
-    pub pipeline: vk::Pipeline,
-    pub pipeline_layout: PipelineLayout,
+    pub pipeline_cache: Box<PipelineCache>,
+    pub descriptor_layouts: Box<DescriptorLayoutCache>,
+    pub pipelines: Box<PipelineRegistry>,
     pub framebuffers: Vec<vk::Framebuffer>,
     pub command_pool: vk::CommandPool
 }
 
 impl CommandBufferBuildStage{
-    pub fn create_command_buffer(self) -> RqResult<SyncObjectsBuildStage>{
+    pub fn create_command_buffer(
+        self,
+        clear_color: [f32; 4]
+    ) -> RqResult<SyncObjectsBuildStage>{
         let allocate_info = vk::CommandBufferAllocateInfo::builder()
             .command_pool(self.command_pool)
             .level(vk::CommandBufferLevel::PRIMARY)
             .command_buffer_count(self.framebuffers.len() as u32)
             .build();
 
+        //Записываются вместе с буферами остальных кадров в полете при создании объектов синхронизации
         let command_buffers = unsafe {
             self.logical_device.allocate_command_buffers(&allocate_info)
                 .map_err(|err| CreateCommandBufferError(err))?
         };
 
-
-        for (i, command_buffer) in command_buffers.iter().enumerate() {
-            let inheritance = vk::CommandBufferInheritanceInfo::builder();
-
-            let info = vk::CommandBufferBeginInfo::builder()
-                .flags(vk::CommandBufferUsageFlags::empty()) // Optional.
-                .inheritance_info(&inheritance);             // Optional.
-            unsafe {
-                //Запуск записи командного буфера
-                self.logical_device.begin_command_buffer(*command_buffer, &info)
-                    .map_err(|err| CreateCommandBufferError(err))?;
-            }
-
-
-            let render_area = vk::Rect2D::builder()
-                .offset(vk::Offset2D::default())
-                .extent(self.swap_chain.extent);
-
-            let color_clear_value = vk::ClearValue {
-                color: vk::ClearColorValue {
-                    float32: [0.0, 0.0, 0.0, 1.0],
-                },
-            };
-
-            let clear_values = &[color_clear_value];
-            let info = vk::RenderPassBeginInfo::builder()
-                .render_pass(self.render_pass)
-                .framebuffer(self.framebuffers[i])
-                .render_area(render_area)
-                .clear_values(clear_values);
-
-            unsafe {
-                self.logical_device.cmd_begin_render_pass(*command_buffer, &info, vk::SubpassContents::INLINE);
-                self.logical_device.cmd_bind_pipeline(*command_buffer, vk::PipelineBindPoint::GRAPHICS, self.pipeline);
-                self.logical_device.cmd_draw(*command_buffer, 3, 1, 0, 0);
-                self.logical_device.cmd_end_render_pass(*command_buffer);
-
-                self.logical_device.end_command_buffer(*command_buffer)
-                    .map_err(|err|CreateCommandBufferError(err))?;
-            };
-        }
-
         Result::Ok(SyncObjectsBuildStage{
             entry: self.entry,
             instance: self.instance,
@@ -84,12 +49,108 @@ impl CommandBufferBuildStage{
             queue_families: self.queue_families,
             surface: self.surface,
             swap_chain: self.swap_chain,
+            color_buffer: self.color_buffer,
+            depth_buffer: self.depth_buffer,
             render_pass: self.render_pass,
-            pipeline: self.pipeline,
-            pipeline_layout: self.pipeline_layout,
+            pipeline_cache: self.pipeline_cache,
+            descriptor_layouts: self.descriptor_layouts,
+            pipelines: self.pipelines,
             framebuffers: self.framebuffers,
             command_pool: self.command_pool,
-            command_buffers
+            command_buffers,
+            clear_color
         })
     }
-}
\ No newline at end of file
+}
+
+//Все, что нужно для записи командных буферов прохода рендеринга
+pub struct RecordingInfo<'a> {
+    pub render_pass: vk::RenderPass,
+    pub framebuffers: &'a [vk::Framebuffer],
+    pub extent: vk::Extent2D,
+    pub clear_color: [f32; 4],
+    //1.0 для обычной глубины, 0.0 для reverse-Z
+    pub clear_depth: f32,
+    pub viewports: &'a [ViewportRect],
+    pub pipelines: &'a PipelineRegistry,
+    pub meshes: &'a [Mesh],
+    //Наборы uniform данных по кадрам в полете, см. GraphicsPipeline::bind_uniform_set
+    pub uniform_sets: Option<&'a FrameDescriptorSets>,
+    //Если задан, кадр записывает граф вместо основного прохода
+    pub graph: Option<&'a CompiledGraph>
+}
+
+//Буферы идут по кадрам в полете, внутри кадра - по изображениям swap chain:
+//кадр может получить любое изображение, а наборы дескрипторов у кадров свои
+pub unsafe fn record_command_buffers(
+    device: &Device,
+    command_buffers: &[vk::CommandBuffer],
+    info: &RecordingInfo
+) -> RqResult<()> {
+    let images_count = info.framebuffers.len();
+    for (i, command_buffer) in command_buffers.iter().enumerate() {
+        let (frame_index, image_index) = (i / images_count, i % images_count);
+        let inheritance = vk::CommandBufferInheritanceInfo::builder();
+
+        let command_buffer_info = vk::CommandBufferBeginInfo::builder()
+            .flags(vk::CommandBufferUsageFlags::empty()) // Optional.
+            .inheritance_info(&inheritance);             // Optional.
+        //Запуск записи командного буфера
+        device.begin_command_buffer(*command_buffer, &command_buffer_info)
+            .map_err(|err| CreateCommandBufferError(err))?;
+
+        if let Some(graph) = info.graph {
+            graph.execute(device, *command_buffer, image_index, frame_index, info);
+
+            device.end_command_buffer(*command_buffer)
+                .map_err(CreateCommandBufferError)?;
+            continue;
+        }
+
+        let render_area = vk::Rect2D::builder()
+            .offset(vk::Offset2D::default())
+            .extent(info.extent);
+
+        let color_clear_value = vk::ClearValue {
+            color: vk::ClearColorValue {
+                float32: info.clear_color,
+            },
+        };
+
+        let depth_clear_value = vk::ClearValue {
+            depth_stencil: vk::ClearDepthStencilValue {
+                depth: info.clear_depth,
+                stencil: 0
+            }
+        };
+
+        let clear_values = &[color_clear_value, depth_clear_value];
+        let begin_info = vk::RenderPassBeginInfo::builder()
+            .render_pass(info.render_pass)
+            .framebuffer(info.framebuffers[image_index])
+            .render_area(render_area)
+            .clear_values(clear_values);
+
+        device.cmd_begin_render_pass(*command_buffer, &begin_info, vk::SubpassContents::INLINE);
+        //Сцена рисуется в каждую область вывода (разделенный экран, картинка в картинке)
+        for viewport in info.viewports {
+            device.cmd_set_viewport(*command_buffer, 0, &[viewport.to_viewport(info.extent)]);
+            device.cmd_set_scissor(*command_buffer, 0, &[viewport.to_scissor(info.extent)]);
+
+            //Каждый зарегистрированный конвейер рисует по очереди
+            for (_, pipeline) in info.pipelines.iter() {
+                pipeline.bind_uniform_set(device, *command_buffer, info.uniform_sets, frame_index);
+                for variant in pipeline.variants() {
+                    device.cmd_bind_pipeline(*command_buffer, vk::PipelineBindPoint::GRAPHICS, variant);
+                    pipeline.draw_meshes(device, *command_buffer, info.meshes);
+                }
+            }
+        }
+        device.cmd_end_render_pass(*command_buffer);
+
+        device.end_command_buffer(*command_buffer)
+            .map_err(|err|CreateCommandBufferError(err))?;
+    }
+
+    Result::Ok(())
+}
@@ -1,5 +1,5 @@
 use vulkanalia::prelude::v1_0::*;
-use vulkanalia::vk::PipelineLayout;
+use crate::rendering::{ColorBuffer, DepthBuffer, DescriptorLayoutCache, PipelineCache, PipelineRegistry};
 use crate::rendering::queue_builder::CommandBufferBuildStage;
 use crate::rendering::RenderingError::CreateCommandPoolError;
 use crate::rendering::RqResult;
@@ -13,9 +13,12 @@ pub struct CommandPoolBuildStage {
     pub queue_families: super::QueueFamilyIndices,
     pub surface: vk::SurfaceKHR,
     pub swap_chain: Box<super::SwapChainData>,
+    pub color_buffer: Box<ColorBuffer>,
+    pub depth_buffer: Box<DepthBuffer>,
     pub render_pass: vk::RenderPass,
-    pub pipeline: vk::Pipeline,
-    pub pipeline_layout: PipelineLayout,
+    pub pipeline_cache: Box<PipelineCache>,
+    pub descriptor_layouts: Box<DescriptorLayoutCache>,
+    pub pipelines: Box<PipelineRegistry>,
     pub framebuffers: Vec<vk::Framebuffer>
 }
 
@@ -39,9 +42,12 @@ impl CommandPoolBuildStage {
             queue_families: self.queue_families,
             surface: self.surface,
             swap_chain: self.swap_chain,
+            color_buffer: self.color_buffer,
+            depth_buffer: self.depth_buffer,
             render_pass: self.render_pass,
-            pipeline: self.pipeline,
-            pipeline_layout: self.pipeline_layout,
+            pipeline_cache: self.pipeline_cache,
+            descriptor_layouts: self.descriptor_layouts,
+            pipelines: self.pipelines,
             framebuffers: self.framebuffers,
             command_pool
         })
@@ -0,0 +1,44 @@
+use vulkanalia::prelude::v1_0::*;
+
+use super::RenderPassBuildStage;
+use crate::rendering::{ColorBuffer, DepthBuffer, QueueFamilyIndices, RqResult, SwapChainData};
+
+pub struct DepthBufferBuildStage {
+    pub entry: Box<Entry>,
+    pub instance: Box<Instance>,
+    pub messenger: Option<vk::DebugUtilsMessengerEXT>,
+    pub physical_device: vk::PhysicalDevice,
+    pub logical_device: Box<Device>,
+    pub queue_families: QueueFamilyIndices,
+    pub surface: vk::SurfaceKHR,
+    pub swap_chain: Box<SwapChainData>,
+    pub color_buffer: Box<ColorBuffer>
+}
+
+impl DepthBufferBuildStage {
+    pub fn create_depth_buffer(self, use_stencil: bool) -> RqResult<RenderPassBuildStage> {
+        let depth_buffer = unsafe {
+            DepthBuffer::create(
+                &self.instance,
+                &self.logical_device,
+                self.physical_device,
+                &self.swap_chain,
+                self.color_buffer.samples,
+                use_stencil
+            )?
+        };
+
+        Result::Ok(RenderPassBuildStage {
+            entry: self.entry,
+            instance: self.instance,
+            messenger: self.messenger,
+            physical_device: self.physical_device,
+            logical_device: self.logical_device,
+            queue_families: self.queue_families,
+            surface: self.surface,
+            swap_chain: self.swap_chain,
+            color_buffer: self.color_buffer,
+            depth_buffer: Box::new(depth_buffer)
+        })
+    }
+}
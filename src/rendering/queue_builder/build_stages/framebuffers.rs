@@ -1,44 +1,19 @@
 use vulkanalia::prelude::v1_0::*;
-use crate::rendering::queue_builder::CommandPoolBuildStage;
+use crate::rendering::queue_builder::{CommandPoolBuildStage, PipelineAddingStage};
 use crate::rendering::RenderingError::CreateFrameBufferError;
-use crate::rendering::RqResult;
-
-pub struct FramebuffersBuildStage{
-    pub entry: Box<Entry>,
-    pub instance: Box<Instance>,
-    pub messenger: Option<vk::DebugUtilsMessengerEXT>,
-    pub physical_device: vk::PhysicalDevice,
-    pub logical_device: Box<Device>,
-    pub queue_families: super::QueueFamilyIndices,
-    pub surface: vk::SurfaceKHR,
-    pub swap_chain: Box<super::SwapChainData>,
-    pub render_pass: vk::RenderPass,
-    pub pipeline: vk::Pipeline,
-    pub pipeline_layout: vk::PipelineLayout
-}
+use crate::rendering::{ColorBuffer, DepthBuffer, RqResult, SwapChainData};
 
-impl FramebuffersBuildStage {
+impl PipelineAddingStage {
     pub fn create_framebuffers(self) -> RqResult<CommandPoolBuildStage>{
-        let mut framebuffers = Vec::with_capacity(self.swap_chain.image_views.len());
-
-        for imageView in &self.swap_chain.image_views {
-            let attachments = &[imageView.clone()];
-            let framebuffer_info = vk::FramebufferCreateInfo::builder()
-                .render_pass(self.render_pass)
-                .attachments(attachments)
-                .width(self.swap_chain.extent.width)
-                .height(self.swap_chain.extent.height)
-                .layers(1);
-
-            let framebuffer = unsafe {
-                self.logical_device.create_framebuffer(
-                    &framebuffer_info,
-                    None
-                ).map_err(|err| CreateFrameBufferError(err))?
-            };
-
-            framebuffers.push(framebuffer);
-        }
+        let framebuffers = unsafe {
+            create_framebuffers(
+                &self.logical_device,
+                self.render_pass,
+                &self.swap_chain,
+                &self.color_buffer,
+                &self.depth_buffer
+            )?
+        };
 
         Result::Ok(CommandPoolBuildStage{
             entry: self.entry,
@@ -49,10 +24,56 @@ impl FramebuffersBuildStage {
             queue_families: self.queue_families,
             surface: self.surface,
             swap_chain: self.swap_chain,
+            color_buffer: self.color_buffer,
+            depth_buffer: self.depth_buffer,
             render_pass: self.render_pass,
-            pipeline: self.pipeline,
-            pipeline_layout: self.pipeline_layout,
+            pipeline_cache: self.pipeline_cache,
+            descriptor_layouts: self.descriptor_layouts,
+            pipelines: self.pipelines,
             framebuffers
         })
     }
-}
\ No newline at end of file
+}
+
+pub unsafe fn create_framebuffers(
+    device: &Device,
+    render_pass: vk::RenderPass,
+    swap_chain: &SwapChainData,
+    color_buffer: &ColorBuffer,
+    depth_buffer: &DepthBuffer
+) -> RqResult<Vec<vk::Framebuffer>> {
+    let mut framebuffers = Vec::with_capacity(swap_chain.image_views.len());
+
+    for (index, image_view) in swap_chain.image_views.iter().enumerate() {
+        let depth_image_view = depth_buffer.attachments.image_views[index];
+
+        //При MSAA рисуем в многовыборочное изображение, а swap chain
+        //получает результат разрешения
+        let attachments = if color_buffer.is_multisampled() {
+            let color_image_view = color_buffer.attachments.image_views[index];
+            vec![color_image_view, depth_image_view, *image_view]
+        } else {
+            vec![*image_view, depth_image_view]
+        };
+
+        let framebuffer_info = vk::FramebufferCreateInfo::builder()
+            .render_pass(render_pass)
+            .attachments(&attachments)
+            .width(swap_chain.extent.width)
+            .height(swap_chain.extent.height)
+            .layers(1);
+
+        let framebuffer = device.create_framebuffer(&framebuffer_info, None);
+        let framebuffer = match framebuffer {
+            Ok(framebuffer) => framebuffer,
+            Err(err) => {
+                framebuffers.iter().for_each(|framebuffer| device.destroy_framebuffer(*framebuffer, None));
+                return Result::Err(CreateFrameBufferError(err));
+            }
+        };
+
+        framebuffers.push(framebuffer);
+    }
+
+    Result::Ok(framebuffers)
+}
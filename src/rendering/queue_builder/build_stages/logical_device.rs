@@ -47,7 +47,9 @@ impl LogicalDeviceBuildStage {
 
         let layers = get_layers(use_validation_layer);
         let extensions = get_extensions();
-        let features = vk::PhysicalDeviceFeatures::builder();
+        let features = unsafe {
+            get_enabled_features(&self.instance, self.physical_device)
+        };
 
         let device_info = vk::DeviceCreateInfo::builder()
             .queue_create_infos(&queue_infos)
@@ -74,6 +76,28 @@ impl LogicalDeviceBuildStage {
     }
 }
 
+//Необязательные возможности устройства включаются, если поддерживаются
+pub unsafe fn get_enabled_features(
+    instance: &Instance,
+    physical_device: vk::PhysicalDevice
+) -> vk::PhysicalDeviceFeatures {
+    let supported = instance.get_physical_device_features(physical_device);
+
+    vk::PhysicalDeviceFeatures::builder()
+        .sample_rate_shading(supported.sample_rate_shading == vk::TRUE)
+        //Каркасный и точечный режимы, толстые линии для отладки геометрии
+        .fill_mode_non_solid(supported.fill_mode_non_solid == vk::TRUE)
+        .wide_lines(supported.wide_lines == vk::TRUE)
+        //Ограничение глубины и смещения глубины из описаний конвейеров
+        .depth_clamp(supported.depth_clamp == vk::TRUE)
+        .depth_bias_clamp(supported.depth_bias_clamp == vk::TRUE)
+        .sampler_anisotropy(supported.sampler_anisotropy == vk::TRUE)
+        //Косвенная отрисовка с аргументами, которые готовит устройство
+        .multi_draw_indirect(supported.multi_draw_indirect == vk::TRUE)
+        .draw_indirect_first_instance(supported.draw_indirect_first_instance == vk::TRUE)
+        .build()
+}
+
 fn get_extensions() -> Vec<*const c_char> {
     REQUIRED_EXTENSIONS
         .iter()
@@ -2,6 +2,9 @@ mod instance;
 mod physical_device;
 mod logical_device;
 mod swap_chain;
+mod color_buffer;
+mod depth_buffer;
+mod pipeline_cache;
 mod adding_pipelines;
 mod framebuffers;
 mod render_pass;
@@ -13,6 +16,9 @@ pub use instance::*;
 pub use physical_device::*;
 pub use logical_device::*;
 pub use swap_chain::*;
+pub use color_buffer::*;
+pub use depth_buffer::*;
+pub use pipeline_cache::*;
 pub use adding_pipelines::*;
 pub use framebuffers::*;
 pub use render_pass::*;
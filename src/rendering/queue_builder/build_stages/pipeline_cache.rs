@@ -0,0 +1,50 @@
+use std::path::PathBuf;
+
+use vulkanalia::prelude::v1_0::*;
+use super::PipelineAddingStage;
+use crate::rendering::{ColorBuffer, DepthBuffer, DescriptorLayoutCache, PipelineCache, PipelineRegistry, QueueFamilyIndices, RqResult, SwapChainData};
+
+pub struct PipelineCacheBuildStage {
+    pub entry: Box<Entry>,
+    pub instance: Box<Instance>,
+    pub messenger: Option<vk::DebugUtilsMessengerEXT>,
+    pub physical_device: vk::PhysicalDevice,
+    pub logical_device: Box<Device>,
+    pub queue_families: QueueFamilyIndices,
+    pub surface: vk::SurfaceKHR,
+    pub swap_chain: Box<SwapChainData>,
+    pub color_buffer: Box<ColorBuffer>,
+    pub depth_buffer: Box<DepthBuffer>,
+    pub render_pass: vk::RenderPass
+}
+
+impl PipelineCacheBuildStage {
+    //path - файл кэша, None - кэш живет только до закрытия приложения
+    pub fn create_pipeline_cache(self, path: Option<PathBuf>) -> RqResult<PipelineAddingStage> {
+        let pipeline_cache = unsafe {
+            PipelineCache::load(
+                &self.instance,
+                &self.logical_device,
+                self.physical_device,
+                path
+            )?
+        };
+
+        Result::Ok(PipelineAddingStage {
+            entry: self.entry,
+            instance: self.instance,
+            messenger: self.messenger,
+            physical_device: self.physical_device,
+            logical_device: self.logical_device,
+            queue_families: self.queue_families,
+            surface: self.surface,
+            swap_chain: self.swap_chain,
+            color_buffer: self.color_buffer,
+            depth_buffer: self.depth_buffer,
+            render_pass: self.render_pass,
+            pipeline_cache: Box::new(pipeline_cache),
+            descriptor_layouts: Box::new(DescriptorLayoutCache::new()),
+            pipelines: Box::new(PipelineRegistry::new())
+        })
+    }
+}
@@ -1,6 +1,6 @@
 use vulkanalia::prelude::v1_0::*;
-use super::PipelineAddingStage;
-use crate::rendering::{QueueFamilyIndices, RqResult, SwapChainData};
+use super::PipelineCacheBuildStage;
+use crate::rendering::{ColorBuffer, DepthBuffer, QueueFamilyIndices, RqResult, SwapChainData};
 use crate::rendering::RenderingError::CreateRenderPassError;
 
 pub struct RenderPassBuildStage {
@@ -11,29 +11,73 @@ pub struct RenderPassBuildStage {
     pub logical_device: Box<Device>,
     pub queue_families: QueueFamilyIndices,
     pub surface: vk::SurfaceKHR,
-    pub swap_chain: Box<SwapChainData>
+    pub swap_chain: Box<SwapChainData>,
+    pub color_buffer: Box<ColorBuffer>,
+    pub depth_buffer: Box<DepthBuffer>
 }
 
 impl RenderPassBuildStage{
-    pub fn create_render_pass(self) -> RqResult<PipelineAddingStage> {
+    pub fn create_render_pass(self) -> RqResult<PipelineCacheBuildStage> {
+        let multisampled = self.color_buffer.is_multisampled();
+
+        //Многовыборочное изображение не показывается, его хранить не нужно
+        let (color_store_op, color_final_layout) = if multisampled {
+            (vk::AttachmentStoreOp::DONT_CARE, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+        } else {
+            (vk::AttachmentStoreOp::STORE, vk::ImageLayout::PRESENT_SRC_KHR)
+        };
+
         //Далее идет создание проходов рендеринга
         let color_attachment = vk::AttachmentDescription::builder()
             .format(self.swap_chain.format)
-            .samples(vk::SampleCountFlags::_1)
+            .samples(self.color_buffer.samples)
             //Определяем что делать с данными до рендеринга и после
             //Применяется к данным о цвете и глубине
             .load_op(vk::AttachmentLoadOp::CLEAR) //Отчистка фрейм буфера
-            .store_op(vk::AttachmentStoreOp::STORE) // Сохраняем в памяти
+            .store_op(color_store_op) // Сохраняем в памяти
             //Применяются к данным трафарета
             .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
             .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
             //Макет изоражения до начала этапа рендеринга
             .initial_layout(vk::ImageLayout::UNDEFINED)
             //После
-            .final_layout(vk::ImageLayout::PRESENT_SRC_KHR) // Изображения для SwapChain
+            .final_layout(color_final_layout) // Изображения для SwapChain
+            .build();
+
+        let depth_stencil_load_op = if self.depth_buffer.use_stencil {
+            vk::AttachmentLoadOp::CLEAR
+        } else {
+            vk::AttachmentLoadOp::DONT_CARE
+        };
+
+        //Содержимое глубины после отрисовки не нужно
+        let depth_stencil_attachment = vk::AttachmentDescription::builder()
+            .format(self.depth_buffer.format)
+            .samples(self.depth_buffer.samples)
+            .load_op(vk::AttachmentLoadOp::CLEAR)
+            .store_op(vk::AttachmentStoreOp::DONT_CARE)
+            .stencil_load_op(depth_stencil_load_op)
+            .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .final_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
+            .build();
+
+        //Изображение swap chain, в которое разрешается многовыборочный цвет
+        let resolve_attachment = vk::AttachmentDescription::builder()
+            .format(self.swap_chain.format)
+            .samples(vk::SampleCountFlags::_1)
+            .load_op(vk::AttachmentLoadOp::DONT_CARE)
+            .store_op(vk::AttachmentStoreOp::STORE)
+            .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+            .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .final_layout(vk::ImageLayout::PRESENT_SRC_KHR)
             .build();
 
-        let color_attachments = &[color_attachment];
+        let mut attachments = vec![color_attachment, depth_stencil_attachment];
+        if multisampled {
+            attachments.push(resolve_attachment);
+        }
 
         let color_attachment_ref = vk::AttachmentReference::builder()
             .attachment(0) //Индекс в массиве attachments (у нас 1 элемент тот что выше)
@@ -42,27 +86,52 @@ impl RenderPassBuildStage{
 
         let color_attachment_refs = &[color_attachment_ref];
 
+        let depth_stencil_attachment_ref = vk::AttachmentReference::builder()
+            .attachment(1)
+            .layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL)
+            .build();
+
+        let resolve_attachment_ref = vk::AttachmentReference::builder()
+            .attachment(2)
+            .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL)
+            .build();
+
+        let resolve_attachment_refs = &[resolve_attachment_ref];
+
+        //Глубину предыдущий кадр пишет и на поздних тестах фрагментов,
+        //очистка должна дождаться этих записей
         let dependency = vk::SubpassDependency::builder()
             .src_subpass(vk::SUBPASS_EXTERNAL) //  |неявный подпас
             .dst_subpass(0) //индекс подпаса       |до ренедринга
-            .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
-            .src_access_mask(vk::AccessFlags::empty())
-            .dst_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT)
-            .dst_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+            .src_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS)
+            .src_access_mask(vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
+            .dst_stage_mask(vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT
+                | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS
+                | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS)
+            .dst_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE)
             .build();
 
         let dependencies = &[dependency];
 
         //Подпас графического типа
-        let subpass = vk::SubpassDescription::builder()
+        let mut subpass = vk::SubpassDescription::builder()
             .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
             .color_attachments(color_attachment_refs)
-            .build();
+            .depth_stencil_attachment(&depth_stencil_attachment_ref);
+
+        if multisampled {
+            subpass = subpass.resolve_attachments(resolve_attachment_refs);
+        }
+
+        let subpass = subpass.build();
 
         let subpasses = &[subpass];
 
         let render_pass = vk::RenderPassCreateInfo::builder()
-            .attachments(color_attachments)
+            .attachments(&attachments)
             .subpasses(subpasses)
             .dependencies(dependencies)
             .build();
@@ -72,7 +141,7 @@ impl RenderPassBuildStage{
                 .map_err(|err|CreateRenderPassError(err))?
         };
 
-        Result::Ok(PipelineAddingStage{
+        Result::Ok(PipelineCacheBuildStage{
             entry: self.entry,
             instance: self.instance,
             messenger: self.messenger,
@@ -81,6 +150,8 @@ impl RenderPassBuildStage{
             queue_families: self.queue_families,
             surface: self.surface,
             swap_chain: self.swap_chain,
+            color_buffer: self.color_buffer,
+            depth_buffer: self.depth_buffer,
             render_pass: render_pass
         })
     }
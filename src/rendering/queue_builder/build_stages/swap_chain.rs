@@ -9,7 +9,7 @@ use vulkanalia::vk::{
 use winit::dpi::PhysicalSize;
 
 use super::QueueFamilyIndices;
-use crate::rendering::{ RenderPassBuildStage, RqResult};
+use crate::rendering::{ColorBufferBuildStage, RqResult};
 use crate::rendering::RenderingError::{CreateSwapChainError, SupportError};
 
 #[derive(Debug)]
@@ -18,9 +18,11 @@ pub struct SwapChainData{
     pub extent: vk::Extent2D,
     pub images: Vec<vk::Image>,
     pub image_views: Vec<vk::ImageView>,
-    pub format: vk::Format
+    pub format: vk::Format,
+    pub composite_alpha: vk::CompositeAlphaFlagsKHR
 }
 
+#[derive(Debug, Clone, Copy)]
 pub struct RenderingResolution {
     pub width: u32,
     pub height: u32
@@ -50,21 +52,65 @@ impl SwapChainBuildStage  {
     pub fn create_swap_chain(
         self,
         rendering_resolution: RenderingResolution,
-        old_swapchain: vk::SwapchainKHR
-    ) -> RqResult<RenderPassBuildStage>
+        old_swapchain: vk::SwapchainKHR,
+        transparent: bool
+    ) -> RqResult<ColorBufferBuildStage>
+    {
+        let swap_chain_data = unsafe {
+            SwapChainData::create(
+                &self.logical_device,
+                self.surface,
+                &self.queue_families,
+                &self.swap_chain_support,
+                SwapChainParameters {
+                    rendering_resolution,
+                    old_swapchain,
+                    transparent
+                }
+            )?
+        };
+
+        return Result::Ok(ColorBufferBuildStage {
+            entry: self.entry,
+            instance: self.instance,
+            messenger: self.messenger,
+            physical_device: self.physical_device,
+            logical_device: self.logical_device,
+            queue_families: self.queue_families,
+            surface: self.surface,
+            swap_chain: Box::new(swap_chain_data),
+        });
+    }
+}
+
+pub struct SwapChainParameters {
+    pub rendering_resolution: RenderingResolution,
+    pub old_swapchain: vk::SwapchainKHR,
+    pub transparent: bool
+}
+
+impl SwapChainData {
+    pub unsafe fn create(
+        device: &Device,
+        surface: vk::SurfaceKHR,
+        queue_families: &QueueFamilyIndices,
+        support: &SwapСhainSupport,
+        parameters: SwapChainParameters
+    ) -> RqResult<Self>
     {
-        let support = &self.swap_chain_support;
         let format = choose_swap_chain_surface_format(&support.formats)
             .ok_or(SupportError("suitable format was not found"))?;
         let present_mode = choose_present_mode(&support.present_modes);
-        let extent = choose_swap_chain_extent(rendering_resolution, &support.capabilities);
+        let extent = choose_swap_chain_extent(parameters.rendering_resolution, &support.capabilities);
+        let composite_alpha = choose_composite_alpha(parameters.transparent, &support.capabilities)
+            .ok_or(SupportError("transparent composite alpha is not supported"))?;
 
         let image_count = (support.capabilities.min_image_count + 1).clamp(
             support.capabilities.min_image_count,
             support.capabilities.max_image_count
         );
 
-        let queue_family_indices = self.queue_families.get_unique_indices();
+        let queue_family_indices = queue_families.get_unique_indices();
         let sharing_mode = if queue_family_indices.len() > 1 {
             vk::SharingMode::CONCURRENT
         } else {
@@ -72,7 +118,7 @@ impl SwapChainBuildStage  {
         };
 
         let swap_chain_info = vk::SwapchainCreateInfoKHR::builder()
-            .surface(self.surface)
+            .surface(surface)
             .min_image_count(image_count)
             .image_format(format.format)
             .image_color_space(format.color_space)
@@ -82,46 +128,50 @@ impl SwapChainBuildStage  {
             .image_sharing_mode(sharing_mode)
             .queue_family_indices(&queue_family_indices)
             .pre_transform(support.capabilities.current_transform)
-            .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
+            .composite_alpha(composite_alpha)
             .present_mode(present_mode)
             .clipped(true)
-            .old_swapchain(old_swapchain)
+            .old_swapchain(parameters.old_swapchain)
             .build();
 
-        let swap_chain = unsafe{
-            self.logical_device.create_swapchain_khr(&swap_chain_info, None)
-                .map_err(|err| CreateSwapChainError(err))?
-        };
-
-        let swap_chain_images = unsafe {
-            self.logical_device.get_swapchain_images_khr(swap_chain)
-                .map_err(|err| CreateSwapChainError(err))?
+        let swap_chain = device.create_swapchain_khr(&swap_chain_info, None)
+            .map_err(|err| CreateSwapChainError(err))?;
+
+        //При ошибке новая цепочка уничтожается, виды уничтожает create_swap_chain_image_views
+        let swap_chain_images = device.get_swapchain_images_khr(swap_chain)
+            .map_err(|err| CreateSwapChainError(err))
+            .and_then(|images| {
+                let views = create_swap_chain_image_views(device, &images, &format.format)?;
+                Result::Ok((images, views))
+            });
+        let (swap_chain_images, swap_chain_image_views) = match swap_chain_images {
+            Ok(created) => created,
+            Err(err) => {
+                device.destroy_swapchain_khr(swap_chain, None);
+                return Err(err);
+            }
         };
 
-        let swap_chain_image_views = create_swap_chain_image_views(
-            &self.logical_device,
-            &swap_chain_images,
-            &format.format
-        )?;
-
-        let swap_chain_data  = SwapChainData{
+        Result::Ok(SwapChainData{
             swap_chain,
             extent,
             images: swap_chain_images,
             image_views: swap_chain_image_views,
-            format: format.format
-        };
+            format: format.format,
+            composite_alpha
+        })
+    }
 
-        return Result::Ok(RenderPassBuildStage {
-            entry: self.entry,
-            instance: self.instance,
-            messenger: self.messenger,
-            physical_device: self.physical_device,
-            logical_device: self.logical_device,
-            queue_families: self.queue_families,
-            surface: self.surface,
-            swap_chain: Box::new(swap_chain_data),
-        });
+    pub fn is_transparent(&self) -> bool {
+        self.composite_alpha != vk::CompositeAlphaFlagsKHR::OPAQUE
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        for image_view in &self.image_views{
+            device.destroy_image_view(*image_view, None);
+        }
+
+        device.destroy_swapchain_khr(self.swap_chain, None);
     }
 }
 
@@ -204,6 +254,26 @@ fn choose_present_mode(
     return vk::PresentModeKHR::FIFO;
 }
 
+// PRE_MULTIPLIED - цвет в изображении уже умножен на альфа канал
+// POST_MULTIPLIED - композитор сам умножит цвет на альфа канал
+fn choose_composite_alpha(
+    transparent: bool,
+    capabilities: &vk::SurfaceCapabilitiesKHR
+) -> Option<vk::CompositeAlphaFlagsKHR>
+{
+    if !transparent {
+        return Some(vk::CompositeAlphaFlagsKHR::OPAQUE);
+    }
+
+    let supported = capabilities.supported_composite_alpha;
+    [
+        vk::CompositeAlphaFlagsKHR::PRE_MULTIPLIED,
+        vk::CompositeAlphaFlagsKHR::POST_MULTIPLIED
+    ]
+        .into_iter()
+        .find(|composite_alpha| supported.contains(*composite_alpha))
+}
+
 fn choose_swap_chain_surface_format(
     formats: &Vec<vk::SurfaceFormatKHR>
 ) -> Option<vk::SurfaceFormatKHR>
@@ -248,10 +318,13 @@ fn create_swap_chain_image_views(
             .build();
 
         unsafe {
-            let image_view = device.create_image_view(&view_info, None)
-                .map_err(|err| CreateSwapChainError(err))?;
-
-            image_views.push(image_view);
+            match device.create_image_view(&view_info, None) {
+                Ok(image_view) => image_views.push(image_view),
+                Err(err) => {
+                    image_views.iter().for_each(|view| device.destroy_image_view(*view, None));
+                    return Err(CreateSwapChainError(err));
+                }
+            }
         }
     }
 
@@ -1,8 +1,10 @@
 use vulkanalia::prelude::v1_0::*;
-use vulkanalia::vk::{HasBuilder, KhrSurfaceExtension, KhrSwapchainExtension, PipelineLayout};
+use crate::rendering::{ColorBuffer, DepthBuffer, DescriptorLayoutCache, FrameCommands, PipelineCache, PipelineRegistry, ViewportRect};
+use vulkanalia::vk::{HasBuilder, KhrSurfaceExtension, KhrSwapchainExtension};
 
 use crate::rendering::queue_builder::builder_extension::EndBuildStage;
-use crate::rendering::RenderingError::CreateSyncObjectsError;
+use super::{record_command_buffers, RecordingInfo};
+use crate::rendering::RenderingError::{CreateCommandBufferError, CreateSyncObjectsError};
 use crate::rendering::RqResult;
 
 
@@ -15,12 +17,16 @@ pub struct SyncObjectsBuildStage{
     pub queue_families: super::QueueFamilyIndices,
     pub surface: vk::SurfaceKHR,
     pub swap_chain: Box<super::SwapChainData>,
+    pub color_buffer: Box<ColorBuffer>,
+    pub depth_buffer: Box<DepthBuffer>,
     pub render_pass: vk::RenderPass,
-    pub pipeline: vk::Pipeline,
-    pub pipeline_layout: PipelineLayout,
+    pub pipeline_cache: Box<PipelineCache>,
+    pub descriptor_layouts: Box<DescriptorLayoutCache>,
+    pub pipelines: Box<PipelineRegistry>,
     pub framebuffers: Vec<vk::Framebuffer>,
     pub command_pool: vk::CommandPool,
-    pub command_buffers: Vec<vk::CommandBuffer>
+    pub command_buffers: Vec<vk::CommandBuffer>,
+    pub clear_color: [f32; 4]
 }
 
 
@@ -55,6 +61,44 @@ impl SyncObjectsBuildStage{
             render_finished_semaphores.push(render);
         }
 
+        let frame_commands = unsafe {
+            FrameCommands::create(&self.logical_device, self.queue_families.graphics, flight_frames_count)?
+        };
+
+        //Заранее записанные буферы остальных кадров в полете
+        let mut command_buffers = self.command_buffers;
+        let allocate_info = vk::CommandBufferAllocateInfo::builder()
+            .command_pool(self.command_pool)
+            .level(vk::CommandBufferLevel::PRIMARY)
+            .command_buffer_count(command_buffers.len() as u32 * (flight_frames_count as u32 - 1))
+            .build();
+
+        unsafe {
+            if allocate_info.command_buffer_count > 0 {
+                command_buffers.extend(
+                    self.logical_device.allocate_command_buffers(&allocate_info)
+                        .map_err(CreateCommandBufferError)?
+                );
+            }
+
+            record_command_buffers(
+                &self.logical_device,
+                &command_buffers,
+                &RecordingInfo {
+                    render_pass: self.render_pass,
+                    framebuffers: &self.framebuffers,
+                    extent: self.swap_chain.extent,
+                    clear_color: self.clear_color,
+                    clear_depth: 1.0,
+                    viewports: &[ViewportRect::FULL],
+                    pipelines: &self.pipelines,
+                    meshes: &[],
+                    uniform_sets: None,
+                    graph: None
+                }
+            )?;
+        }
+
         Result::Ok(EndBuildStage{
             entry: self.entry,
             instance: self.instance,
@@ -64,15 +108,20 @@ impl SyncObjectsBuildStage{
             queue_families: self.queue_families,
             surface: self.surface,
             swap_chain: self.swap_chain,
+            color_buffer: self.color_buffer,
+            depth_buffer: self.depth_buffer,
             render_pass: self.render_pass,
-            pipeline: self.pipeline,
-            pipeline_layout: self.pipeline_layout,
+            pipeline_cache: self.pipeline_cache,
+            descriptor_layouts: self.descriptor_layouts,
+            pipelines: self.pipelines,
             framebuffers: self.framebuffers,
             command_pool: self.command_pool,
-            command_buffers: self.command_buffers,
+            command_buffers,
+            clear_color: self.clear_color,
             image_available_semaphores,
             render_finished_semaphores,
             frame_in_flight_fences,
+            frame_commands,
             flight_frames_count
         })
     }
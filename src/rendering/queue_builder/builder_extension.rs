@@ -3,9 +3,9 @@ use vulkanalia::loader::{
     LibloadingLoader,
     LIBRARY
 };
-use vulkanalia::vk::{PipelineLayout, Semaphore};
+use vulkanalia::vk::Semaphore;
 
-use crate::rendering::{DeviceQueues, RenderingQueue, RqResult};
+use crate::rendering::{ColorBuffer, DepthBuffer, DescriptorLayoutCache, FrameCommands, PipelineCache, PipelineRegistry, DeviceQueues, RenderingQueue, RqResult};
 use crate::rendering::RenderingError::{
     CreateEntryError,
     LoadLibraryError
@@ -44,15 +44,20 @@ pub struct EndBuildStage {
     pub queue_families: super::QueueFamilyIndices,
     pub surface: vk::SurfaceKHR,
     pub swap_chain: Box<super::SwapChainData>,
+    pub color_buffer: Box<ColorBuffer>,
+    pub depth_buffer: Box<DepthBuffer>,
     pub render_pass: vk::RenderPass,
-    pub pipeline: vk::Pipeline,
-    pub pipeline_layout: PipelineLayout,
+    pub pipeline_cache: Box<PipelineCache>,
+    pub descriptor_layouts: Box<DescriptorLayoutCache>,
+    pub pipelines: Box<PipelineRegistry>,
     pub framebuffers: Vec<vk::Framebuffer>,
     pub command_pool: vk::CommandPool,
     pub command_buffers: Vec<vk::CommandBuffer>,
+    pub clear_color: [f32; 4],
     pub image_available_semaphores: Vec<Semaphore>,
     pub render_finished_semaphores: Vec<Semaphore>,
     pub frame_in_flight_fences: Vec<vk::Fence>,
+    pub frame_commands: FrameCommands,
     pub flight_frames_count: u8
 }
 
@@ -81,15 +86,20 @@ impl EndBuildStage {
             queues,
             self.surface,
             self.swap_chain,
+            self.color_buffer,
+            self.depth_buffer,
             self.render_pass,
-            self.pipeline,
-            self.pipeline_layout,
+            self.pipeline_cache,
+            self.descriptor_layouts,
+            self.pipelines,
             self.framebuffers,
             self.command_pool,
             self.command_buffers,
+            self.clear_color,
             self.image_available_semaphores,
             self.render_finished_semaphores,
             self.frame_in_flight_fences,
+            self.frame_commands,
             self.flight_frames_count
         )
     }
@@ -0,0 +1,1054 @@
+use std::collections::{BTreeMap, HashMap};
+
+use vulkanalia::prelude::v1_0::*;
+
+use crate::rendering::RenderingError::ReflectShaderError;
+use crate::rendering::RqResult;
+
+//Разбор SPIR-V по спецификации: заголовок из 5 слов, дальше инструкции,
+//первое слово инструкции - (количество слов << 16) | код операции
+const MAGIC_NUMBER: u32 = 0x0723_0203;
+const HEADER_SIZE: usize = 5;
+
+const OP_NAME: u32 = 5;
+const OP_MEMBER_NAME: u32 = 6;
+const OP_ENTRY_POINT: u32 = 15;
+const OP_TYPE_BOOL: u32 = 20;
+const OP_TYPE_INT: u32 = 21;
+const OP_TYPE_FLOAT: u32 = 22;
+const OP_TYPE_VECTOR: u32 = 23;
+const OP_TYPE_MATRIX: u32 = 24;
+const OP_TYPE_IMAGE: u32 = 25;
+const OP_TYPE_SAMPLER: u32 = 26;
+const OP_TYPE_SAMPLED_IMAGE: u32 = 27;
+const OP_TYPE_ARRAY: u32 = 28;
+const OP_TYPE_RUNTIME_ARRAY: u32 = 29;
+const OP_TYPE_STRUCT: u32 = 30;
+const OP_TYPE_POINTER: u32 = 32;
+const OP_CONSTANT: u32 = 43;
+const OP_SPEC_CONSTANT_TRUE: u32 = 48;
+const OP_SPEC_CONSTANT_FALSE: u32 = 49;
+const OP_SPEC_CONSTANT: u32 = 50;
+const OP_VARIABLE: u32 = 59;
+const OP_DECORATE: u32 = 71;
+const OP_MEMBER_DECORATE: u32 = 72;
+
+const DECORATION_SPEC_ID: u32 = 1;
+const DECORATION_BLOCK: u32 = 2;
+const DECORATION_BUFFER_BLOCK: u32 = 3;
+const DECORATION_MATRIX_STRIDE: u32 = 7;
+const DECORATION_ARRAY_STRIDE: u32 = 6;
+const DECORATION_BUILT_IN: u32 = 11;
+const DECORATION_LOCATION: u32 = 30;
+const DECORATION_BINDING: u32 = 33;
+const DECORATION_DESCRIPTOR_SET: u32 = 34;
+const DECORATION_OFFSET: u32 = 35;
+
+const STORAGE_UNIFORM_CONSTANT: u32 = 0;
+const STORAGE_INPUT: u32 = 1;
+const STORAGE_UNIFORM: u32 = 2;
+const STORAGE_OUTPUT: u32 = 3;
+const STORAGE_PUSH_CONSTANT: u32 = 9;
+const STORAGE_STORAGE_BUFFER: u32 = 12;
+
+const DIM_BUFFER: u32 = 5;
+const DIM_SUBPASS_DATA: u32 = 6;
+
+#[derive(Debug, Clone)]
+pub struct EntryPoint {
+    pub name: String,
+    pub stage: vk::ShaderStageFlags,
+    //id входных и выходных переменных, которые использует точка входа
+    pub interface: Vec<u32>
+}
+
+//Входная или выходная переменная, матрицы, массивы и члены блоков
+//разложены по отдельным location
+#[derive(Debug, Clone)]
+pub struct InterfaceVariable {
+    pub id: u32,
+    pub name: String,
+    pub location: u32,
+    pub format: vk::Format
+}
+
+#[derive(Debug, Clone)]
+pub struct DescriptorBinding {
+    pub name: String,
+    pub set: u32,
+    pub binding: u32,
+    pub descriptor_type: vk::DescriptorType,
+    pub count: u32
+}
+
+#[derive(Debug, Clone)]
+pub struct PushConstantBlock {
+    pub name: String,
+    //Блок занимает [offset, offset + size): члены могут начинаться не с нуля,
+    //если другая стадия использует начало диапазона
+    pub offset: u32,
+    pub size: u32
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConstantType {
+    Bool,
+    Int { width: u32, signed: bool },
+    Float { width: u32 }
+}
+
+impl ConstantType {
+    //VkBool32 занимает 4 байта
+    pub fn size(self) -> u32 {
+        match self {
+            ConstantType::Bool => 4,
+            ConstantType::Int { width, .. } | ConstantType::Float { width } => width / 8
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SpecializationConstant {
+    pub name: String,
+    pub id: u32,
+    pub constant_type: ConstantType
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ShaderReflection {
+    pub entry_points: Vec<EntryPoint>,
+    pub inputs: Vec<InterfaceVariable>,
+    pub outputs: Vec<InterfaceVariable>,
+    pub descriptor_bindings: Vec<DescriptorBinding>,
+    pub push_constants: Option<PushConstantBlock>,
+    pub specialization_constants: Vec<SpecializationConstant>
+}
+
+#[derive(Debug, Clone)]
+enum SpirvType {
+    Bool,
+    Int { width: u32, signed: bool },
+    Float { width: u32 },
+    Vector { component: u32, count: u32 },
+    Matrix { column: u32, count: u32 },
+    Image { dim: u32, sampled: u32 },
+    Sampler,
+    SampledImage,
+    Array { element: u32, length: u32 },
+    RuntimeArray { element: u32 },
+    Struct { members: Vec<u32> },
+    Pointer { pointee: u32 }
+}
+
+#[derive(Default)]
+struct Decorations {
+    location: Option<u32>,
+    binding: Option<u32>,
+    set: Option<u32>,
+    built_in: bool,
+    block: bool,
+    buffer_block: bool,
+    array_stride: Option<u32>,
+    spec_id: Option<u32>
+}
+
+#[derive(Default)]
+struct MemberDecorations {
+    location: Option<u32>,
+    offset: Option<u32>,
+    matrix_stride: Option<u32>,
+    built_in: bool
+}
+
+#[derive(Default)]
+struct Module {
+    names: HashMap<u32, String>,
+    member_names: HashMap<(u32, u32), String>,
+    types: HashMap<u32, SpirvType>,
+    constants: HashMap<u32, u32>,
+    decorations: HashMap<u32, Decorations>,
+    member_decorations: HashMap<(u32, u32), MemberDecorations>,
+    //(тип указателя, id, класс хранения)
+    variables: Vec<(u32, u32, u32)>,
+    //(тип, id)
+    spec_constants: Vec<(u32, u32)>,
+    entry_points: Vec<EntryPoint>
+}
+
+impl ShaderReflection {
+    pub fn reflect(code: &[u32]) -> RqResult<Self> {
+        let module = Module::parse(code)?;
+
+        let mut reflection = ShaderReflection {
+            entry_points: module.entry_points.clone(),
+            ..Default::default()
+        };
+
+        for &(pointer_type, id, storage_class) in &module.variables {
+            let pointee = match module.types.get(&pointer_type) {
+                Some(SpirvType::Pointer { pointee }) => *pointee,
+                _ => return Err(ReflectShaderError(format!("variable {} is not a pointer", id)))
+            };
+
+            let decorations = module.decorations.get(&id);
+            let name = module.name(id);
+
+            match storage_class {
+                STORAGE_INPUT | STORAGE_OUTPUT => {
+                    if module.is_built_in(id, pointee) {
+                        continue;
+                    }
+
+                    let location = decorations.and_then(|decorations| decorations.location);
+
+                    let variables = if storage_class == STORAGE_INPUT {
+                        &mut reflection.inputs
+                    } else {
+                        &mut reflection.outputs
+                    };
+
+                    variables.extend(module.interface_variables(id, &name, pointee, location)?);
+                }
+                STORAGE_UNIFORM_CONSTANT | STORAGE_UNIFORM | STORAGE_STORAGE_BUFFER => {
+                    let (descriptor_type, count) = module.descriptor_type(pointee, storage_class)?;
+
+                    reflection.descriptor_bindings.push(DescriptorBinding {
+                        name,
+                        set: decorations.and_then(|decorations| decorations.set).unwrap_or(0),
+                        binding: decorations.and_then(|decorations| decorations.binding).unwrap_or(0),
+                        descriptor_type,
+                        count
+                    });
+                }
+                STORAGE_PUSH_CONSTANT => {
+                    let offset = module.first_member_offset(pointee)?;
+                    reflection.push_constants = Some(PushConstantBlock {
+                        name,
+                        offset,
+                        size: module.size_of(pointee)? - offset
+                    });
+                }
+                _ => {}
+            }
+        }
+
+        for &(type_id, id) in &module.spec_constants {
+            let spec_id = module.decorations.get(&id)
+                .and_then(|decorations| decorations.spec_id);
+
+            //Без SpecId константа не специализируется
+            if let Some(spec_id) = spec_id {
+                reflection.specialization_constants.push(SpecializationConstant {
+                    name: module.name(id),
+                    id: spec_id,
+                    constant_type: module.constant_type(type_id)?
+                });
+            }
+        }
+
+        reflection.inputs.sort_by_key(|variable| variable.location);
+        reflection.outputs.sort_by_key(|variable| variable.location);
+        reflection.descriptor_bindings.sort_by_key(|binding| (binding.set, binding.binding));
+
+        Result::Ok(reflection)
+    }
+
+    //Интерфейс одной точки входа, если в модуле их несколько
+    pub fn for_entry_point(&self, entry_point: &EntryPoint) -> ShaderReflection {
+        let filter = |variables: &[InterfaceVariable]| variables.iter()
+            .filter(|variable| entry_point.interface.contains(&variable.id))
+            .cloned()
+            .collect();
+
+        ShaderReflection {
+            entry_points: vec![entry_point.clone()],
+            inputs: filter(&self.inputs),
+            outputs: filter(&self.outputs),
+            ..self.clone()
+        }
+    }
+
+    pub fn find_specialization_constant(&self, name: &str) -> Option<&SpecializationConstant> {
+        self.specialization_constants.iter()
+            .find(|constant| constant.name == name)
+    }
+
+    //Объединение стадий всех точек входа модуля
+    pub fn stages(&self) -> vk::ShaderStageFlags {
+        self.entry_points.iter()
+            .fold(vk::ShaderStageFlags::empty(), |stages, entry_point| stages | entry_point.stage)
+    }
+
+    //Все входы вершинного шейдера в одном чередующемся буфере (binding 0)
+    pub fn vertex_input(&self) -> (Vec<vk::VertexInputBindingDescription>, Vec<vk::VertexInputAttributeDescription>) {
+        if self.inputs.is_empty() {
+            return (Vec::new(), Vec::new());
+        }
+
+        let mut offset = 0;
+        let attributes = self.inputs.iter()
+            .map(|input| {
+                let attribute = vk::VertexInputAttributeDescription::builder()
+                    .binding(0)
+                    .location(input.location)
+                    .format(input.format)
+                    .offset(offset)
+                    .build();
+                offset += format_size(input.format);
+                attribute
+            })
+            .collect();
+
+        let binding = vk::VertexInputBindingDescription::builder()
+            .binding(0)
+            .stride(offset)
+            .input_rate(vk::VertexInputRate::VERTEX)
+            .build();
+
+        (vec![binding], attributes)
+    }
+}
+
+//Каждый вход следующей стадии должен быть выходом предыдущей того же формата
+pub fn check_interface(previous: &ShaderReflection, next: &ShaderReflection) -> RqResult<()> {
+    for input in &next.inputs {
+        let output = previous.outputs.iter()
+            .find(|output| output.location == input.location);
+
+        match output {
+            Some(output) if output.format == input.format => {}
+            Some(output) => return Err(ReflectShaderError(format!(
+                "location {}: \"{}\" is {:?}, but \"{}\" is {:?}",
+                input.location, output.name, output.format, input.name, input.format
+            ))),
+            None => return Err(ReflectShaderError(format!(
+                "location {}: input \"{}\" is not written by previous stage",
+                input.location, input.name
+            )))
+        }
+    }
+
+    Result::Ok(())
+}
+
+//Привязки всех стадий по наборам, одна привязка в нескольких стадиях
+//объединяется по флагам стадий
+pub fn merge_descriptor_bindings(
+    reflections: &[&ShaderReflection]
+) -> RqResult<BTreeMap<u32, Vec<vk::DescriptorSetLayoutBinding>>> {
+    let mut sets: BTreeMap<u32, Vec<vk::DescriptorSetLayoutBinding>> = BTreeMap::new();
+
+    for reflection in reflections {
+        let stages = reflection.stages();
+
+        for binding in &reflection.descriptor_bindings {
+            let bindings = sets.entry(binding.set).or_default();
+
+            match bindings.iter_mut().find(|existing| existing.binding == binding.binding) {
+                Some(existing) if existing.descriptor_type == binding.descriptor_type
+                    && existing.descriptor_count == binding.count => {
+                    existing.stage_flags |= stages;
+                }
+                Some(existing) => return Err(ReflectShaderError(format!(
+                    "set {} binding {}: {:?} x{} conflicts with {:?} x{}",
+                    binding.set, binding.binding,
+                    binding.descriptor_type, binding.count,
+                    existing.descriptor_type, existing.descriptor_count
+                ))),
+                None => bindings.push(vk::DescriptorSetLayoutBinding::builder()
+                    .binding(binding.binding)
+                    .descriptor_type(binding.descriptor_type)
+                    .descriptor_count(binding.count)
+                    .stage_flags(stages)
+                    .build())
+            }
+        }
+    }
+
+    Result::Ok(sets)
+}
+
+//Один диапазон с начала блока, видимый всем стадиям, которые его объявили
+pub fn merge_push_constant_ranges(reflections: &[&ShaderReflection]) -> Vec<vk::PushConstantRange> {
+    let mut size = 0;
+    let mut stages = vk::ShaderStageFlags::empty();
+
+    for reflection in reflections {
+        if let Some(block) = &reflection.push_constants {
+            size = size.max(block.offset + block.size);
+            stages |= reflection.stages();
+        }
+    }
+
+    if size == 0 {
+        return Vec::new();
+    }
+
+    vec![vk::PushConstantRange::builder()
+        .stage_flags(stages)
+        .offset(0)
+        .size(size)
+        .build()]
+}
+
+pub fn format_size(format: vk::Format) -> u32 {
+    match format {
+        vk::Format::R32_SFLOAT | vk::Format::R32_SINT | vk::Format::R32_UINT => 4,
+        vk::Format::R32G32_SFLOAT | vk::Format::R32G32_SINT | vk::Format::R32G32_UINT
+            | vk::Format::R64_SFLOAT => 8,
+        vk::Format::R32G32B32_SFLOAT | vk::Format::R32G32B32_SINT | vk::Format::R32G32B32_UINT => 12,
+        vk::Format::R32G32B32A32_SFLOAT | vk::Format::R32G32B32A32_SINT | vk::Format::R32G32B32A32_UINT
+            | vk::Format::R64G64_SFLOAT => 16,
+        vk::Format::R64G64B64_SFLOAT => 24,
+        vk::Format::R64G64B64A64_SFLOAT => 32,
+        _ => 0
+    }
+}
+
+impl Module {
+    fn parse(code: &[u32]) -> RqResult<Self> {
+        if code.len() < HEADER_SIZE || code[0] != MAGIC_NUMBER {
+            return Err(ReflectShaderError(String::from("not a SPIR-V module")));
+        }
+
+        let mut module = Module::default();
+        let mut position = HEADER_SIZE;
+
+        while position < code.len() {
+            let word_count = (code[position] >> 16) as usize;
+            let opcode = code[position] & 0xFFFF;
+
+            if word_count == 0 || position + word_count > code.len() {
+                return Err(ReflectShaderError(format!("broken instruction at word {}", position)));
+            }
+
+            let operands = &code[position + 1..position + word_count];
+            module.read_instruction(opcode, operands)?;
+
+            position += word_count;
+        }
+
+        Result::Ok(module)
+    }
+
+    fn read_instruction(&mut self, opcode: u32, operands: &[u32]) -> RqResult<()> {
+        let operand = |index: usize| operands.get(index).copied()
+            .ok_or_else(|| ReflectShaderError(format!("opcode {} is too short", opcode)));
+
+        match opcode {
+            OP_NAME => {
+                self.names.insert(operand(0)?, read_string(&operands[1..]));
+            }
+            OP_MEMBER_NAME => {
+                self.member_names.insert((operand(0)?, operand(1)?), read_string(&operands[2..]));
+            }
+            OP_ENTRY_POINT => {
+                operand(1)?;
+                //После имени идут id переменных интерфейса
+                let name_words = operands[2..].iter()
+                    .position(|word| word.to_le_bytes().contains(&0))
+                    .map_or(operands.len() - 2, |position| position + 1);
+
+                self.entry_points.push(EntryPoint {
+                    name: read_string(&operands[2..]),
+                    stage: execution_model_stage(operand(0)?),
+                    interface: operands[2 + name_words..].to_vec()
+                });
+            }
+            OP_TYPE_BOOL => {
+                self.types.insert(operand(0)?, SpirvType::Bool);
+            }
+            OP_TYPE_INT => {
+                self.types.insert(operand(0)?, SpirvType::Int { width: operand(1)?, signed: operand(2)? == 1 });
+            }
+            OP_TYPE_FLOAT => {
+                self.types.insert(operand(0)?, SpirvType::Float { width: operand(1)? });
+            }
+            OP_TYPE_VECTOR => {
+                self.types.insert(operand(0)?, SpirvType::Vector { component: operand(1)?, count: operand(2)? });
+            }
+            OP_TYPE_MATRIX => {
+                self.types.insert(operand(0)?, SpirvType::Matrix { column: operand(1)?, count: operand(2)? });
+            }
+            OP_TYPE_IMAGE => {
+                self.types.insert(operand(0)?, SpirvType::Image { dim: operand(2)?, sampled: operand(6)? });
+            }
+            OP_TYPE_SAMPLER => {
+                self.types.insert(operand(0)?, SpirvType::Sampler);
+            }
+            OP_TYPE_SAMPLED_IMAGE => {
+                self.types.insert(operand(0)?, SpirvType::SampledImage);
+            }
+            OP_TYPE_ARRAY => {
+                //Длина - id константы, константы объявляются раньше массива
+                let length = operand(2)?;
+                let length = self.constants.get(&length).copied().unwrap_or(1);
+                self.types.insert(operand(0)?, SpirvType::Array { element: operand(1)?, length });
+            }
+            OP_TYPE_RUNTIME_ARRAY => {
+                self.types.insert(operand(0)?, SpirvType::RuntimeArray { element: operand(1)? });
+            }
+            OP_TYPE_STRUCT => {
+                self.types.insert(operand(0)?, SpirvType::Struct { members: operands[1..].to_vec() });
+            }
+            OP_TYPE_POINTER => {
+                self.types.insert(operand(0)?, SpirvType::Pointer { pointee: operand(2)? });
+            }
+            OP_CONSTANT => {
+                //Нужны только 32-битные целые для длин массивов
+                self.constants.insert(operand(1)?, operand(2)?);
+            }
+            OP_SPEC_CONSTANT_TRUE | OP_SPEC_CONSTANT_FALSE | OP_SPEC_CONSTANT => {
+                self.spec_constants.push((operand(0)?, operand(1)?));
+            }
+            OP_VARIABLE => {
+                self.variables.push((operand(0)?, operand(1)?, operand(2)?));
+            }
+            OP_DECORATE => {
+                let decorations = self.decorations.entry(operand(0)?).or_default();
+                match operand(1)? {
+                    DECORATION_BLOCK => decorations.block = true,
+                    DECORATION_BUFFER_BLOCK => decorations.buffer_block = true,
+                    DECORATION_BUILT_IN => decorations.built_in = true,
+                    DECORATION_LOCATION => decorations.location = Some(operand(2)?),
+                    DECORATION_BINDING => decorations.binding = Some(operand(2)?),
+                    DECORATION_DESCRIPTOR_SET => decorations.set = Some(operand(2)?),
+                    DECORATION_ARRAY_STRIDE => decorations.array_stride = Some(operand(2)?),
+                    DECORATION_SPEC_ID => decorations.spec_id = Some(operand(2)?),
+                    _ => {}
+                }
+            }
+            OP_MEMBER_DECORATE => {
+                let decorations = self.member_decorations.entry((operand(0)?, operand(1)?)).or_default();
+                match operand(2)? {
+                    DECORATION_BUILT_IN => decorations.built_in = true,
+                    DECORATION_LOCATION => decorations.location = Some(operand(3)?),
+                    DECORATION_OFFSET => decorations.offset = Some(operand(3)?),
+                    DECORATION_MATRIX_STRIDE => decorations.matrix_stride = Some(operand(3)?),
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+
+        Result::Ok(())
+    }
+
+    fn name(&self, id: u32) -> String {
+        self.names.get(&id).cloned().unwrap_or_default()
+    }
+
+    fn get_type(&self, id: u32) -> RqResult<&SpirvType> {
+        self.types.get(&id)
+            .ok_or_else(|| ReflectShaderError(format!("unknown type {}", id)))
+    }
+
+    //gl_Position, gl_VertexIndex и блок gl_PerVertex не входят в интерфейс
+    fn is_built_in(&self, variable: u32, pointee: u32) -> bool {
+        if self.decorations.get(&variable).is_some_and(|decorations| decorations.built_in) {
+            return true;
+        }
+
+        let mut pointee = pointee;
+        while let Some(SpirvType::Array { element, .. }) = self.types.get(&pointee) {
+            pointee = *element;
+        }
+
+        match self.types.get(&pointee) {
+            Some(SpirvType::Struct { members }) => (0..members.len() as u32).any(|member|
+                self.member_decorations.get(&(pointee, member))
+                    .is_some_and(|decorations| decorations.built_in)
+            ),
+            _ => false
+        }
+    }
+
+    //Блок интерфейса получает location на переменной, и тогда члены идут
+    //подряд, или на каждом члене отдельно
+    fn interface_variables(
+        &self,
+        id: u32,
+        name: &str,
+        type_id: u32,
+        location: Option<u32>
+    ) -> RqResult<Vec<InterfaceVariable>> {
+        let members = match self.get_type(type_id)? {
+            SpirvType::Struct { members } => members,
+            _ => {
+                let location = location
+                    .ok_or_else(|| ReflectShaderError(format!("\"{}\" has no location", name)))?;
+
+                return Result::Ok(self.location_formats(type_id)?
+                    .into_iter()
+                    .map(|(offset, format)| InterfaceVariable {
+                        id,
+                        name: name.to_string(),
+                        location: location + offset,
+                        format
+                    })
+                    .collect());
+            }
+        };
+
+        let mut variables = Vec::new();
+        let mut next_location = location;
+        for (index, member) in members.iter().enumerate() {
+            let member_name = match self.member_names.get(&(type_id, index as u32)) {
+                Some(member_name) => format!("{}.{}", name, member_name),
+                None => format!("{}.{}", name, index)
+            };
+
+            let location = self.member_decorations.get(&(type_id, index as u32))
+                .and_then(|decorations| decorations.location)
+                .or(next_location)
+                .ok_or_else(|| ReflectShaderError(format!("\"{}\" has no location", member_name)))?;
+
+            let formats = self.location_formats(*member)?;
+            next_location = Some(location + location_count(&formats));
+
+            variables.extend(formats.into_iter().map(|(offset, format)| InterfaceVariable {
+                id,
+                name: member_name.clone(),
+                location: location + offset,
+                format
+            }));
+        }
+
+        Result::Ok(variables)
+    }
+
+    //Форматы со смещением location от начала переменной.
+    //dvec3 и dvec4 занимают по два location
+    fn location_formats(&self, type_id: u32) -> RqResult<Vec<(u32, vk::Format)>> {
+        match self.get_type(type_id)? {
+            SpirvType::Matrix { column, count } => {
+                let format = self.scalar_format(*column)?;
+                let step = format_locations(format);
+                Result::Ok((0..*count).map(|index| (index * step, format)).collect())
+            }
+            SpirvType::Array { element, length } => {
+                let formats = self.location_formats(*element)?;
+                let step = location_count(&formats);
+                Result::Ok((0..*length)
+                    .flat_map(|index| formats.iter().map(move |(offset, format)| (index * step + offset, *format)))
+                    .collect())
+            }
+            _ => Result::Ok(vec![(0, self.scalar_format(type_id)?)])
+        }
+    }
+
+    fn scalar_format(&self, type_id: u32) -> RqResult<vk::Format> {
+        let (component, count) = match self.get_type(type_id)? {
+            SpirvType::Vector { component, count } => (*component, *count),
+            _ => (type_id, 1)
+        };
+
+        let format = match (self.get_type(component)?, count) {
+            (SpirvType::Float { width: 32 }, 1) => vk::Format::R32_SFLOAT,
+            (SpirvType::Float { width: 32 }, 2) => vk::Format::R32G32_SFLOAT,
+            (SpirvType::Float { width: 32 }, 3) => vk::Format::R32G32B32_SFLOAT,
+            (SpirvType::Float { width: 32 }, 4) => vk::Format::R32G32B32A32_SFLOAT,
+            (SpirvType::Float { width: 64 }, 1) => vk::Format::R64_SFLOAT,
+            (SpirvType::Float { width: 64 }, 2) => vk::Format::R64G64_SFLOAT,
+            (SpirvType::Float { width: 64 }, 3) => vk::Format::R64G64B64_SFLOAT,
+            (SpirvType::Float { width: 64 }, 4) => vk::Format::R64G64B64A64_SFLOAT,
+            (SpirvType::Int { width: 32, signed: true }, 1) => vk::Format::R32_SINT,
+            (SpirvType::Int { width: 32, signed: true }, 2) => vk::Format::R32G32_SINT,
+            (SpirvType::Int { width: 32, signed: true }, 3) => vk::Format::R32G32B32_SINT,
+            (SpirvType::Int { width: 32, signed: true }, 4) => vk::Format::R32G32B32A32_SINT,
+            (SpirvType::Int { width: 32, signed: false }, 1) => vk::Format::R32_UINT,
+            (SpirvType::Int { width: 32, signed: false }, 2) => vk::Format::R32G32_UINT,
+            (SpirvType::Int { width: 32, signed: false }, 3) => vk::Format::R32G32B32_UINT,
+            (SpirvType::Int { width: 32, signed: false }, 4) => vk::Format::R32G32B32A32_UINT,
+            (component, count) => return Err(ReflectShaderError(
+                format!("unsupported interface type {:?} x{}", component, count)
+            ))
+        };
+
+        Result::Ok(format)
+    }
+
+    fn constant_type(&self, type_id: u32) -> RqResult<ConstantType> {
+        match self.get_type(type_id)? {
+            SpirvType::Bool => Result::Ok(ConstantType::Bool),
+            SpirvType::Int { width, signed } => Result::Ok(ConstantType::Int { width: *width, signed: *signed }),
+            SpirvType::Float { width } => Result::Ok(ConstantType::Float { width: *width }),
+            spirv_type => Err(ReflectShaderError(
+                format!("unsupported specialization constant type {:?}", spirv_type)
+            ))
+        }
+    }
+
+    fn descriptor_type(&self, type_id: u32, storage_class: u32) -> RqResult<(vk::DescriptorType, u32)> {
+        let (type_id, count) = match self.get_type(type_id)? {
+            SpirvType::Array { element, length } => (*element, *length),
+            //Массив без размера - число дескрипторов задается при создании набора
+            SpirvType::RuntimeArray { element } => (*element, 1),
+            _ => (type_id, 1)
+        };
+
+        let descriptor_type = match (self.get_type(type_id)?, storage_class) {
+            (SpirvType::Sampler, _) => vk::DescriptorType::SAMPLER,
+            (SpirvType::SampledImage, _) => vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            (SpirvType::Image { dim: DIM_BUFFER, sampled: 2 }, _) => vk::DescriptorType::STORAGE_TEXEL_BUFFER,
+            (SpirvType::Image { dim: DIM_BUFFER, .. }, _) => vk::DescriptorType::UNIFORM_TEXEL_BUFFER,
+            (SpirvType::Image { dim: DIM_SUBPASS_DATA, .. }, _) => vk::DescriptorType::INPUT_ATTACHMENT,
+            (SpirvType::Image { sampled: 2, .. }, _) => vk::DescriptorType::STORAGE_IMAGE,
+            (SpirvType::Image { .. }, _) => vk::DescriptorType::SAMPLED_IMAGE,
+            (SpirvType::Struct { .. }, STORAGE_STORAGE_BUFFER) => vk::DescriptorType::STORAGE_BUFFER,
+            (SpirvType::Struct { .. }, STORAGE_UNIFORM) => {
+                let buffer_block = self.decorations.get(&type_id)
+                    .is_some_and(|decorations| decorations.buffer_block && !decorations.block);
+
+                if buffer_block {
+                    vk::DescriptorType::STORAGE_BUFFER
+                } else {
+                    vk::DescriptorType::UNIFORM_BUFFER
+                }
+            }
+            (spirv_type, _) => return Err(ReflectShaderError(
+                format!("unsupported descriptor type {:?}", spirv_type)
+            ))
+        };
+
+        Result::Ok((descriptor_type, count))
+    }
+
+    //Размер по смещениям членов блока, как его видит std140/std430
+    //Смещение первого члена структуры, для остальных типов 0
+    fn first_member_offset(&self, type_id: u32) -> RqResult<u32> {
+        let SpirvType::Struct { members } = self.get_type(type_id)? else {
+            return Result::Ok(0);
+        };
+
+        let offset = (0..members.len() as u32)
+            .map(|index| self.member_decorations.get(&(type_id, index))
+                .and_then(|decorations| decorations.offset)
+                .unwrap_or(0))
+            .min()
+            .unwrap_or(0);
+
+        Result::Ok(offset)
+    }
+
+    fn size_of(&self, type_id: u32) -> RqResult<u32> {
+        let size = match self.get_type(type_id)? {
+            SpirvType::Bool => 4,
+            SpirvType::Int { width, .. } | SpirvType::Float { width } => width / 8,
+            SpirvType::Vector { component, count } => self.size_of(*component)? * count,
+            SpirvType::Matrix { column, count } => {
+                //Без MatrixStride столбец vec3 все равно занимает 16 байт
+                let column_size = self.size_of(*column)?;
+                column_size.next_multiple_of(16) * count
+            }
+            SpirvType::Array { element, length } => {
+                let stride = self.decorations.get(&type_id)
+                    .and_then(|decorations| decorations.array_stride);
+                match stride {
+                    Some(stride) => stride * length,
+                    None => self.size_of(*element)? * length
+                }
+            }
+            SpirvType::Struct { members } => {
+                let mut size = 0;
+                let mut offset = 0;
+                for (index, member) in members.iter().enumerate() {
+                    let decorations = self.member_decorations.get(&(type_id, index as u32));
+                    offset = decorations.and_then(|decorations| decorations.offset).unwrap_or(offset);
+
+                    let member_size = match (self.get_type(*member)?, decorations.and_then(|decorations| decorations.matrix_stride)) {
+                        (SpirvType::Matrix { count, .. }, Some(stride)) => stride * count,
+                        _ => self.size_of(*member)?
+                    };
+
+                    offset += member_size;
+                    size = size.max(offset);
+                }
+                size
+            }
+            //Размер неизвестен до создания буфера
+            SpirvType::RuntimeArray { .. } => 0,
+            spirv_type => return Err(ReflectShaderError(
+                format!("type {:?} has no size", spirv_type)
+            ))
+        };
+
+        Result::Ok(size)
+    }
+}
+
+fn format_locations(format: vk::Format) -> u32 {
+    match format {
+        vk::Format::R64G64B64_SFLOAT | vk::Format::R64G64B64A64_SFLOAT => 2,
+        _ => 1
+    }
+}
+
+//Сколько location занимают форматы из location_formats
+fn location_count(formats: &[(u32, vk::Format)]) -> u32 {
+    formats.iter()
+        .map(|(offset, format)| offset + format_locations(*format))
+        .max()
+        .unwrap_or(0)
+}
+
+fn execution_model_stage(execution_model: u32) -> vk::ShaderStageFlags {
+    match execution_model {
+        0 => vk::ShaderStageFlags::VERTEX,
+        1 => vk::ShaderStageFlags::TESSELLATION_CONTROL,
+        2 => vk::ShaderStageFlags::TESSELLATION_EVALUATION,
+        3 => vk::ShaderStageFlags::GEOMETRY,
+        4 => vk::ShaderStageFlags::FRAGMENT,
+        5 => vk::ShaderStageFlags::COMPUTE,
+        _ => vk::ShaderStageFlags::empty()
+    }
+}
+
+//Строки в SPIR-V - UTF-8 с нулем в конце, упакованные по 4 байта в слово
+fn read_string(words: &[u32]) -> String {
+    let bytes: Vec<u8> = words.iter()
+        .flat_map(|word| word.to_le_bytes())
+        .take_while(|byte| *byte != 0)
+        .collect();
+
+    String::from_utf8_lossy(&bytes).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const EXAMPLE_VERTEX: &[u8] = include_bytes!("../assets/shaders/Example.vert.spv");
+    const EXAMPLE_FRAGMENT: &[u8] = include_bytes!("../assets/shaders/Example.frag.spv");
+    const MODEL_VERTEX: &[u8] = include_bytes!("../assets/shaders/Model.vert.spv");
+    const MODEL_FRAGMENT: &[u8] = include_bytes!("../assets/shaders/Model.frag.spv");
+    const MODEL_INSTANCED_VERTEX: &[u8] = include_bytes!("../assets/shaders/Model.instanced.vert.spv");
+
+    fn words(bytes: &[u8]) -> Vec<u32> {
+        bytes.chunks_exact(4)
+            .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+            .collect()
+    }
+
+    fn string(value: &str) -> Vec<u32> {
+        let mut bytes = value.as_bytes().to_vec();
+        bytes.resize((bytes.len() / 4 + 1) * 4, 0);
+        words(&bytes)
+    }
+
+    fn op(opcode: u32, operands: &[u32]) -> Vec<u32> {
+        let mut instruction = vec![((operands.len() as u32 + 1) << 16) | opcode];
+        instruction.extend_from_slice(operands);
+        instruction
+    }
+
+    fn module(instructions: &[Vec<u32>]) -> Vec<u32> {
+        let mut code = vec![MAGIC_NUMBER, 0x0001_0000, 0, 100, 0];
+        instructions.iter().for_each(|instruction| code.extend(instruction));
+        code
+    }
+
+    //float (1), vec2 (2), vec4 (3), double (4), dvec3 (5), dvec4 (6)
+    fn scalar_types() -> Vec<Vec<u32>> {
+        vec![
+            op(OP_TYPE_FLOAT, &[1, 32]),
+            op(OP_TYPE_VECTOR, &[2, 1, 2]),
+            op(OP_TYPE_VECTOR, &[3, 1, 4]),
+            op(OP_TYPE_FLOAT, &[4, 64]),
+            op(OP_TYPE_VECTOR, &[5, 4, 3]),
+            op(OP_TYPE_VECTOR, &[6, 4, 4])
+        ]
+    }
+
+    fn locations(variables: &[InterfaceVariable]) -> Vec<(u32, vk::Format)> {
+        variables.iter().map(|variable| (variable.location, variable.format)).collect()
+    }
+
+    #[test]
+    fn reflects_example_shaders() {
+        let vertex = ShaderReflection::reflect(&words(EXAMPLE_VERTEX)).unwrap();
+        let fragment = ShaderReflection::reflect(&words(EXAMPLE_FRAGMENT)).unwrap();
+
+        assert_eq!(vertex.stages(), vk::ShaderStageFlags::VERTEX);
+        assert_eq!(vertex.entry_points[0].name, "main");
+        //gl_VertexIndex и gl_PerVertex не попадают в интерфейс
+        assert!(vertex.inputs.is_empty());
+        assert_eq!(locations(&vertex.outputs), vec![(0, vk::Format::R32G32B32_SFLOAT)]);
+        assert_eq!(vertex.outputs[0].name, "fragColor");
+
+        assert_eq!(fragment.stages(), vk::ShaderStageFlags::FRAGMENT);
+        assert_eq!(locations(&fragment.inputs), vec![(0, vk::Format::R32G32B32_SFLOAT)]);
+        assert_eq!(locations(&fragment.outputs), vec![(0, vk::Format::R32G32B32A32_SFLOAT)]);
+        assert!(fragment.descriptor_bindings.is_empty());
+        assert!(fragment.push_constants.is_none());
+
+        check_interface(&vertex, &fragment).unwrap();
+    }
+
+    #[test]
+    fn model_shaders() {
+        let vertex = ShaderReflection::reflect(&words(MODEL_VERTEX)).unwrap();
+        let fragment = ShaderReflection::reflect(&words(MODEL_FRAGMENT)).unwrap();
+
+        assert_eq!(vertex.stages(), vk::ShaderStageFlags::VERTEX);
+        assert_eq!(locations(&vertex.inputs), vec![
+            (0, vk::Format::R32G32B32_SFLOAT),
+            (1, vk::Format::R32G32B32_SFLOAT)
+        ]);
+        assert_eq!(locations(&vertex.outputs), vec![(0, vk::Format::R32G32B32_SFLOAT)]);
+        assert_eq!(vertex.descriptor_bindings.len(), 1);
+        let camera = &vertex.descriptor_bindings[0];
+        assert_eq!((camera.set, camera.binding, camera.count), (0, 0, 1));
+        assert_eq!(camera.descriptor_type, vk::DescriptorType::UNIFORM_BUFFER);
+
+        assert_eq!(fragment.stages(), vk::ShaderStageFlags::FRAGMENT);
+        assert_eq!(locations(&fragment.outputs), vec![(0, vk::Format::R32G32B32A32_SFLOAT)]);
+
+        check_interface(&vertex, &fragment).unwrap();
+    }
+
+    #[test]
+    fn instanced_model_shader() {
+        let vertex = ShaderReflection::reflect(&words(MODEL_INSTANCED_VERTEX)).unwrap();
+        let fragment = ShaderReflection::reflect(&words(MODEL_FRAGMENT)).unwrap();
+
+        //mat4 занимает четыре location по столбцам
+        assert_eq!(locations(&vertex.inputs), vec![
+            (0, vk::Format::R32G32B32_SFLOAT),
+            (1, vk::Format::R32G32B32_SFLOAT),
+            (4, vk::Format::R32G32B32A32_SFLOAT),
+            (5, vk::Format::R32G32B32A32_SFLOAT),
+            (6, vk::Format::R32G32B32A32_SFLOAT),
+            (7, vk::Format::R32G32B32A32_SFLOAT)
+        ]);
+        assert_eq!(vertex.descriptor_bindings.len(), 1);
+        assert_eq!(vertex.descriptor_bindings[0].descriptor_type, vk::DescriptorType::UNIFORM_BUFFER);
+
+        check_interface(&vertex, &fragment).unwrap();
+    }
+
+    #[test]
+    fn rejects_malformed_modules() {
+        let valid = words(EXAMPLE_VERTEX);
+
+        assert!(ShaderReflection::reflect(&[]).is_err());
+        assert!(ShaderReflection::reflect(&valid[..HEADER_SIZE - 1]).is_err());
+        assert!(ShaderReflection::reflect(&[0xDEAD_BEEF, 0, 0, 0, 0]).is_err());
+
+        //Обрыв посреди каждой многословной инструкции
+        let mut position = HEADER_SIZE;
+        while position < valid.len() {
+            let word_count = (valid[position] >> 16) as usize;
+            if word_count > 1 {
+                assert!(ShaderReflection::reflect(&valid[..position + 1]).is_err());
+            }
+            position += word_count;
+        }
+
+        //Нулевая длина инструкции
+        assert!(ShaderReflection::reflect(&module(&[vec![OP_TYPE_FLOAT]])).is_err());
+        //Операндов меньше, чем нужно коду операции
+        assert!(ShaderReflection::reflect(&module(&[op(OP_TYPE_VECTOR, &[2, 1])])).is_err());
+        assert!(ShaderReflection::reflect(&module(&[op(OP_NAME, &[])])).is_err());
+        //Переменная неизвестного типа
+        assert!(ShaderReflection::reflect(&module(&[op(OP_VARIABLE, &[7, 8, STORAGE_INPUT])])).is_err());
+    }
+
+    #[test]
+    fn rejects_interface_variable_without_location() {
+        let mut instructions = scalar_types();
+        instructions.extend([
+            op(OP_TYPE_POINTER, &[10, STORAGE_INPUT, 3]),
+            op(OP_VARIABLE, &[10, 11, STORAGE_INPUT])
+        ]);
+
+        assert!(ShaderReflection::reflect(&module(&instructions)).is_err());
+    }
+
+    #[test]
+    fn double_vectors_take_two_locations() {
+        let mut instructions = scalar_types();
+        instructions.extend([
+            //dvec3 values[2] в location 0, dmat3x4 в location 4, float после них
+            op(OP_CONSTANT, &[20, 21, 2]),
+            op(OP_TYPE_ARRAY, &[22, 5, 21]),
+            op(OP_TYPE_MATRIX, &[23, 6, 3]),
+            op(OP_TYPE_POINTER, &[30, STORAGE_INPUT, 22]),
+            op(OP_TYPE_POINTER, &[31, STORAGE_INPUT, 23]),
+            op(OP_TYPE_POINTER, &[32, STORAGE_INPUT, 1]),
+            op(OP_VARIABLE, &[30, 40, STORAGE_INPUT]),
+            op(OP_VARIABLE, &[31, 41, STORAGE_INPUT]),
+            op(OP_VARIABLE, &[32, 42, STORAGE_INPUT]),
+            op(OP_DECORATE, &[40, DECORATION_LOCATION, 0]),
+            op(OP_DECORATE, &[41, DECORATION_LOCATION, 4]),
+            op(OP_DECORATE, &[42, DECORATION_LOCATION, 10])
+        ]);
+
+        let reflection = ShaderReflection::reflect(&module(&instructions)).unwrap();
+        assert_eq!(locations(&reflection.inputs), vec![
+            (0, vk::Format::R64G64B64_SFLOAT),
+            (2, vk::Format::R64G64B64_SFLOAT),
+            (4, vk::Format::R64G64B64A64_SFLOAT),
+            (6, vk::Format::R64G64B64A64_SFLOAT),
+            (8, vk::Format::R64G64B64A64_SFLOAT),
+            (10, vk::Format::R32_SFLOAT)
+        ]);
+    }
+
+    #[test]
+    fn block_members_take_locations_from_members() {
+        let mut instructions = scalar_types();
+        instructions.extend([
+            op(OP_NAME, &[[40].as_slice(), &string("Out")].concat()),
+            op(OP_MEMBER_NAME, &[[10, 0].as_slice(), &string("color")].concat()),
+            op(OP_TYPE_STRUCT, &[10, 3, 2]),
+            op(OP_TYPE_POINTER, &[30, STORAGE_OUTPUT, 10]),
+            op(OP_VARIABLE, &[30, 40, STORAGE_OUTPUT]),
+            op(OP_DECORATE, &[10, DECORATION_BLOCK]),
+            op(OP_MEMBER_DECORATE, &[10, 0, DECORATION_LOCATION, 2]),
+            op(OP_MEMBER_DECORATE, &[10, 1, DECORATION_LOCATION, 5])
+        ]);
+
+        let reflection = ShaderReflection::reflect(&module(&instructions)).unwrap();
+        assert_eq!(locations(&reflection.outputs), vec![
+            (2, vk::Format::R32G32B32A32_SFLOAT),
+            (5, vk::Format::R32G32_SFLOAT)
+        ]);
+        assert_eq!(reflection.outputs[0].name, "Out.color");
+        assert_eq!(reflection.outputs[1].name, "Out.1");
+    }
+
+    #[test]
+    fn block_members_follow_variable_location() {
+        let mut instructions = scalar_types();
+        instructions.extend([
+            op(OP_TYPE_STRUCT, &[10, 5, 1, 2]),
+            op(OP_TYPE_POINTER, &[30, STORAGE_INPUT, 10]),
+            op(OP_VARIABLE, &[30, 40, STORAGE_INPUT]),
+            op(OP_DECORATE, &[10, DECORATION_BLOCK]),
+            op(OP_DECORATE, &[40, DECORATION_LOCATION, 3]),
+            //Явный location члена переопределяет порядок
+            op(OP_MEMBER_DECORATE, &[10, 2, DECORATION_LOCATION, 8])
+        ]);
+
+        let reflection = ShaderReflection::reflect(&module(&instructions)).unwrap();
+        assert_eq!(locations(&reflection.inputs), vec![
+            (3, vk::Format::R64G64B64_SFLOAT),
+            (5, vk::Format::R32_SFLOAT),
+            (8, vk::Format::R32G32_SFLOAT)
+        ]);
+    }
+
+    #[test]
+    fn rejects_block_without_locations() {
+        let mut instructions = scalar_types();
+        instructions.extend([
+            op(OP_TYPE_STRUCT, &[10, 3]),
+            op(OP_TYPE_POINTER, &[30, STORAGE_OUTPUT, 10]),
+            op(OP_VARIABLE, &[30, 40, STORAGE_OUTPUT]),
+            op(OP_DECORATE, &[10, DECORATION_BLOCK])
+        ]);
+
+        assert!(ShaderReflection::reflect(&module(&instructions)).is_err());
+    }
+}
@@ -0,0 +1,1077 @@
+use std::collections::HashSet;
+use std::fmt;
+
+use log::debug;
+use vulkanalia::prelude::v1_0::*;
+
+use super::{has_stencil, is_depth_format, BufferId, ImageFormat, ImageId, PassContext, RecordPass, RenderGraph};
+use crate::rendering::{choose_depth_format, AttachmentImages, AttachmentInfo, RecordingInfo, RqResult, SwapChainData};
+use crate::rendering::RenderingError::{
+    CompileRenderGraphError,
+    CreateFrameBufferError,
+    CreateGraphImageError,
+    CreateRenderPassError,
+    SupportError
+};
+
+//Устройство и swap chain, под которые компилируется граф
+pub struct GraphContext<'a> {
+    pub instance: &'a Instance,
+    pub device: &'a Device,
+    pub physical_device: vk::PhysicalDevice,
+    pub swap_chain: &'a SwapChainData
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+enum Resource {
+    Image(usize),
+    Buffer(usize)
+}
+
+//Последнее использование ресурса: в каком layout он остался
+//и какие стадии и доступы нужно дождаться следующему проходу
+#[derive(Debug, Clone, Copy)]
+struct ResourceState {
+    layout: vk::ImageLayout,
+    stage: vk::PipelineStageFlags,
+    access: vk::AccessFlags
+}
+
+#[derive(Debug, Clone, Copy)]
+struct ImageBarrier {
+    image: ImageId,
+    aspect_mask: vk::ImageAspectFlags,
+    old_layout: vk::ImageLayout,
+    new_layout: vk::ImageLayout,
+    src_access: vk::AccessFlags,
+    dst_access: vk::AccessFlags
+}
+
+#[derive(Debug, Clone, Copy)]
+struct BufferBarrier {
+    buffer: BufferId,
+    src_access: vk::AccessFlags,
+    dst_access: vk::AccessFlags
+}
+
+//Один vkCmdPipelineBarrier перед проходом
+#[derive(Debug, Default)]
+struct PassBarriers {
+    src_stage: vk::PipelineStageFlags,
+    dst_stage: vk::PipelineStageFlags,
+    images: Vec<ImageBarrier>,
+    buffers: Vec<BufferBarrier>
+}
+
+impl PassBarriers {
+    fn is_empty(&self) -> bool {
+        self.images.is_empty() && self.buffers.is_empty()
+    }
+}
+
+struct CompiledPass {
+    name: String,
+    //null для проходов без вложений
+    render_pass: vk::RenderPass,
+    //По одному на изображение swap chain
+    framebuffers: Vec<vk::Framebuffer>,
+    extent: vk::Extent2D,
+    color_attachments: u32,
+    clear_values: Vec<vk::ClearValue>,
+    barriers: PassBarriers,
+    record: RecordPass
+}
+
+impl fmt::Debug for CompiledPass {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.debug_struct("CompiledPass")
+            .field("name", &self.name)
+            .field("render_pass", &self.render_pass)
+            .field("extent", &self.extent)
+            .field("barriers", &self.barriers)
+            .finish()
+    }
+}
+
+#[derive(Debug)]
+struct GraphImage {
+    format: vk::Format,
+    extent: vk::Extent2D,
+    aspect_mask: vk::ImageAspectFlags,
+    //None для backbuffer и неиспользуемых изображений
+    attachments: Option<AttachmentImages>
+}
+
+//Граф, готовый к записи в командные буферы. Пересобирается
+//при каждом пересоздании swap chain
+#[derive(Debug, Default)]
+pub struct CompiledGraph {
+    passes: Vec<CompiledPass>,
+    images: Vec<GraphImage>,
+    buffers: Vec<vk::Buffer>,
+    swap_chain_images: Vec<vk::Image>,
+    swap_chain_extent: vk::Extent2D,
+    present_barriers: PassBarriers
+}
+
+impl RenderGraph {
+    pub unsafe fn compile(&self, context: &GraphContext) -> RqResult<CompiledGraph> {
+        self.validate()?;
+
+        let order = self.execution_order()?;
+        debug!(
+            "render graph order: {:?}",
+            order.iter().map(|pass| &self.passes[*pass].name).collect::<Vec<_>>()
+        );
+
+        let mut compiled = CompiledGraph {
+            buffers: self.buffers.iter().map(|buffer| buffer.buffer).collect(),
+            swap_chain_images: context.swap_chain.images.clone(),
+            swap_chain_extent: context.swap_chain.extent,
+            ..Default::default()
+        };
+
+        let result = self.create_images(context, &order, &mut compiled)
+            .and_then(|_| self.create_passes(context, &order, &mut compiled));
+
+        match result {
+            Ok(_) => Result::Ok(compiled),
+            Err(err) => {
+                compiled.destroy(context.device);
+                Result::Err(err)
+            }
+        }
+    }
+
+    fn validate(&self) -> RqResult<()> {
+        let backbuffer = self.backbuffer();
+
+        for pass in &self.passes {
+            let resources = &pass.resources;
+            let error = |message: &str| Err(CompileRenderGraphError(format!("pass \"{}\": {}", pass.name, message)));
+
+            let images_valid = resources.written_images()
+                .chain(resources.sampled.iter().copied())
+                .all(|image| image.0 < self.images.len());
+            let buffers_valid = resources.buffers.iter()
+                .all(|access| access.buffer.0 < self.buffers.len());
+
+            if !images_valid || !buffers_valid {
+                return error("resource belongs to another graph");
+            }
+
+            if resources.sampled.contains(&backbuffer) {
+                return error("backbuffer can not be sampled");
+            }
+
+            if resources.depth.is_some_and(|depth| !self.is_depth_image(depth.image)) {
+                return error("depth attachment must have a depth format");
+            }
+
+            if resources.colors.iter().any(|color| self.is_depth_image(color.image)) {
+                return error("color attachment has a depth format");
+            }
+
+            let feedback = resources.written_images()
+                .any(|image| resources.sampled.contains(&image));
+            if feedback {
+                return error("image is sampled and written in the same pass");
+            }
+        }
+
+        Result::Ok(())
+    }
+
+    fn is_depth_image(&self, image: ImageId) -> bool {
+        match self.images[image.0].format {
+            ImageFormat::Depth => true,
+            ImageFormat::Format(format) => is_depth_format(format),
+            ImageFormat::Swapchain => false
+        }
+    }
+
+    fn reads(&self, pass: usize) -> Vec<Resource> {
+        let resources = &self.passes[pass].resources;
+        resources.read_images()
+            .map(|image| Resource::Image(image.0))
+            .chain(resources.buffers.iter()
+                .filter(|access| !access.write)
+                .map(|access| Resource::Buffer(access.buffer.0)))
+            .collect()
+    }
+
+    fn writes(&self, pass: usize) -> Vec<Resource> {
+        let resources = &self.passes[pass].resources;
+        resources.written_images()
+            .map(|image| Resource::Image(image.0))
+            .chain(resources.buffers.iter()
+                .filter(|access| access.write)
+                .map(|access| Resource::Buffer(access.buffer.0)))
+            .collect()
+    }
+
+    //Чтение зависит от последней записи, объявленной до него,
+    //а если ее нет - от всех записей. Запись ждет предыдущую запись
+    //и чтения, которые ее используют
+    fn dependencies(&self) -> Vec<HashSet<usize>> {
+        let count = self.passes.len();
+        let reads: Vec<Vec<Resource>> = (0..count).map(|pass| self.reads(pass)).collect();
+        let writes: Vec<Vec<Resource>> = (0..count).map(|pass| self.writes(pass)).collect();
+
+        let resources: HashSet<Resource> = reads.iter().chain(writes.iter()).flatten().copied().collect();
+        let mut dependencies = vec![HashSet::new(); count];
+
+        for resource in resources {
+            let writers: Vec<usize> = (0..count)
+                .filter(|pass| writes[*pass].contains(&resource))
+                .collect();
+            let writer_before = |pass: usize| writers.iter().rev().copied().find(|writer| *writer < pass);
+
+            for pass in 0..count {
+                let reads_resource = reads[pass].contains(&resource);
+                let writes_resource = writes[pass].contains(&resource);
+
+                if reads_resource {
+                    match writer_before(pass) {
+                        Some(writer) => {
+                            dependencies[pass].insert(writer);
+                        }
+                        None if !writes_resource => dependencies[pass].extend(writers.iter().copied()),
+                        None => {}
+                    }
+                }
+
+                if writes_resource {
+                    if let Some(writer) = writer_before(pass) {
+                        dependencies[pass].insert(writer);
+
+                        let readers = (writer + 1..pass)
+                            .filter(|reader| reads[*reader].contains(&resource));
+                        dependencies[pass].extend(readers);
+                    }
+                }
+            }
+        }
+
+        dependencies
+    }
+
+    //Топологическая сортировка с сохранением порядка объявления,
+    //проходы, не влияющие на backbuffer, отбрасываются
+    fn execution_order(&self) -> RqResult<Vec<usize>> {
+        let dependencies = self.dependencies();
+        let count = self.passes.len();
+
+        let mut order = Vec::with_capacity(count);
+        let mut done = vec![false; count];
+
+        while order.len() < count {
+            let next = (0..count).find(|pass|
+                !done[*pass] && dependencies[*pass].iter().all(|dependency| done[*dependency])
+            );
+
+            match next {
+                Some(pass) => {
+                    done[pass] = true;
+                    order.push(pass);
+                }
+                None => return Err(CompileRenderGraphError(String::from("passes depend on each other cyclically")))
+            }
+        }
+
+        let mut needed: HashSet<Resource> = HashSet::from([Resource::Image(self.backbuffer().0)]);
+        let mut kept = Vec::with_capacity(count);
+
+        for pass in order.into_iter().rev() {
+            let writes = self.writes(pass);
+            if !writes.iter().any(|resource| needed.contains(resource)) {
+                debug!("render graph pass \"{}\" is not used and skipped", self.passes[pass].name);
+                continue;
+            }
+
+            //Очищенное вложение не зависит от предыдущих записей
+            for resource in writes {
+                if matches!(resource, Resource::Image(_)) {
+                    needed.remove(&resource);
+                }
+            }
+            needed.extend(self.reads(pass));
+            kept.push(pass);
+        }
+
+        kept.reverse();
+
+        if kept.is_empty() {
+            return Err(CompileRenderGraphError(String::from("nothing is drawn to the backbuffer")));
+        }
+
+        Result::Ok(kept)
+    }
+
+    unsafe fn image_format(&self, context: &GraphContext, image: ImageId) -> RqResult<vk::Format> {
+        match self.images[image.0].format {
+            ImageFormat::Swapchain => Result::Ok(context.swap_chain.format),
+            ImageFormat::Depth => choose_depth_format(context.instance, context.physical_device, false)
+                .ok_or(SupportError("suitable depth format was not found")),
+            ImageFormat::Format(format) => Result::Ok(format)
+        }
+    }
+
+    //Изображения, которые используются одним проходом и не читаются
+    //шейдерами, создаются как transient
+    unsafe fn create_images(&self, context: &GraphContext, order: &[usize], compiled: &mut CompiledGraph) -> RqResult<()> {
+        let images_count = context.swap_chain.images.len();
+
+        for (index, declaration) in self.images.iter().enumerate() {
+            let image = ImageId(index);
+            let format = self.image_format(context, image)?;
+            let extent = declaration.size.resolve(context.swap_chain.extent);
+
+            let aspect_mask = if is_depth_format(format) {
+                if has_stencil(format) {
+                    vk::ImageAspectFlags::DEPTH | vk::ImageAspectFlags::STENCIL
+                } else {
+                    vk::ImageAspectFlags::DEPTH
+                }
+            } else {
+                vk::ImageAspectFlags::COLOR
+            };
+
+            let usage = self.image_usage(image, order);
+            let attachments = if image == self.backbuffer() || usage.is_empty() {
+                None
+            } else {
+                let info = AttachmentInfo {
+                    extent,
+                    format,
+                    samples: vk::SampleCountFlags::_1,
+                    usage,
+                    aspect_mask
+                };
+
+                debug!("render graph image \"{}\" {:?} {:?} {:?}", declaration.name, format, extent, usage);
+
+                Some(AttachmentImages::create(
+                    context.instance, context.device, context.physical_device,
+                    &info, images_count, CreateGraphImageError
+                )?)
+            };
+
+            compiled.images.push(GraphImage {
+                format,
+                extent,
+                aspect_mask,
+                attachments
+            });
+        }
+
+        Result::Ok(())
+    }
+
+    //Изображение, которое использует один проход и не читают шейдеры,
+    //получает TRANSIENT_ATTACHMENT
+    fn image_usage(&self, image: ImageId, order: &[usize]) -> vk::ImageUsageFlags {
+        let mut usage = vk::ImageUsageFlags::empty();
+        let mut users = 0;
+        for pass in order {
+            let resources = &self.passes[*pass].resources;
+            let mut used = false;
+
+            if resources.colors.iter().any(|color| color.image == image) {
+                usage |= vk::ImageUsageFlags::COLOR_ATTACHMENT;
+                used = true;
+            }
+            if resources.depth.is_some_and(|depth| depth.image == image) {
+                usage |= vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT;
+                used = true;
+            }
+            if resources.sampled.contains(&image) {
+                usage |= vk::ImageUsageFlags::SAMPLED;
+                used = true;
+            }
+            if used {
+                users += 1;
+            }
+        }
+
+        if users == 1 && !usage.contains(vk::ImageUsageFlags::SAMPLED) {
+            usage |= vk::ImageUsageFlags::TRANSIENT_ATTACHMENT;
+        }
+        usage
+    }
+
+    unsafe fn create_passes(&self, context: &GraphContext, order: &[usize], compiled: &mut CompiledGraph) -> RqResult<()> {
+        let backbuffer = self.backbuffer();
+
+        let mut image_states: Vec<ResourceState> = (0..self.images.len())
+            .map(|_| ResourceState {
+                layout: vk::ImageLayout::UNDEFINED,
+                stage: vk::PipelineStageFlags::TOP_OF_PIPE,
+                access: vk::AccessFlags::empty()
+            })
+            .collect();
+        //Семафор получения изображения ожидается на этой стадии
+        image_states[backbuffer.0].stage = vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT;
+
+        let mut buffer_states: Vec<ResourceState> = (0..self.buffers.len())
+            .map(|_| ResourceState {
+                layout: vk::ImageLayout::UNDEFINED,
+                stage: vk::PipelineStageFlags::TOP_OF_PIPE,
+                access: vk::AccessFlags::empty()
+            })
+            .collect();
+
+        for (position, pass) in order.iter().enumerate() {
+            let declaration = &self.passes[*pass];
+            let resources = &declaration.resources;
+            let mut barriers = PassBarriers::default();
+
+            //Содержимое нужно сохранить, если его использует следующий проход
+            let used_later = |image: ImageId| image == backbuffer || order[position + 1..].iter()
+                .any(|later| {
+                    let later = &self.passes[*later].resources;
+                    later.written_images().any(|written| written == image) || later.sampled.contains(&image)
+                });
+
+            let mut attachments = Vec::new();
+            let mut attachment_images = Vec::new();
+            let mut clear_values = Vec::new();
+            let mut color_references = Vec::new();
+            let mut depth_reference = None;
+
+            for color in &resources.colors {
+                let load_op = load_op(color.clear.is_some(), &image_states[color.image.0]);
+
+                let layout = vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL;
+                let access = if load_op == vk::AttachmentLoadOp::LOAD {
+                    vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                } else {
+                    vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+                };
+
+                transition_image(
+                    &mut barriers,
+                    &mut image_states[color.image.0],
+                    color.image,
+                    compiled.images[color.image.0].aspect_mask,
+                    ResourceState { layout, stage: vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT, access },
+                    load_op != vk::AttachmentLoadOp::LOAD
+                );
+
+                color_references.push(vk::AttachmentReference::builder()
+                    .attachment(attachments.len() as u32)
+                    .layout(layout)
+                    .build());
+
+                attachments.push(vk::AttachmentDescription::builder()
+                    .format(compiled.images[color.image.0].format)
+                    .samples(vk::SampleCountFlags::_1)
+                    .load_op(load_op)
+                    .store_op(store_op(used_later(color.image)))
+                    .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+                    .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+                    .initial_layout(layout)
+                    .final_layout(layout)
+                    .build());
+                attachment_images.push(color.image);
+
+                clear_values.push(vk::ClearValue {
+                    color: vk::ClearColorValue {
+                        float32: color.clear.unwrap_or_default()
+                    }
+                });
+            }
+
+            if let Some(depth) = resources.depth {
+                let load_op = load_op(depth.clear.is_some(), &image_states[depth.image.0]);
+
+                let format = compiled.images[depth.image.0].format;
+                let layout = vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL;
+                let store = store_op(used_later(depth.image));
+
+                transition_image(
+                    &mut barriers,
+                    &mut image_states[depth.image.0],
+                    depth.image,
+                    compiled.images[depth.image.0].aspect_mask,
+                    ResourceState {
+                        layout,
+                        stage: vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS | vk::PipelineStageFlags::LATE_FRAGMENT_TESTS,
+                        access: vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_READ | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE
+                    },
+                    load_op != vk::AttachmentLoadOp::LOAD
+                );
+
+                depth_reference = Some(vk::AttachmentReference::builder()
+                    .attachment(attachments.len() as u32)
+                    .layout(layout)
+                    .build());
+
+                let (stencil_load_op, stencil_store_op) = if has_stencil(format) {
+                    (load_op, store)
+                } else {
+                    (vk::AttachmentLoadOp::DONT_CARE, vk::AttachmentStoreOp::DONT_CARE)
+                };
+
+                attachments.push(vk::AttachmentDescription::builder()
+                    .format(format)
+                    .samples(vk::SampleCountFlags::_1)
+                    .load_op(load_op)
+                    .store_op(store)
+                    .stencil_load_op(stencil_load_op)
+                    .stencil_store_op(stencil_store_op)
+                    .initial_layout(layout)
+                    .final_layout(layout)
+                    .build());
+                attachment_images.push(depth.image);
+
+                clear_values.push(vk::ClearValue {
+                    depth_stencil: vk::ClearDepthStencilValue {
+                        depth: depth.clear.unwrap_or(1.0),
+                        stencil: 0
+                    }
+                });
+            }
+
+            for image in &resources.sampled {
+                transition_image(
+                    &mut barriers,
+                    &mut image_states[image.0],
+                    *image,
+                    compiled.images[image.0].aspect_mask,
+                    ResourceState {
+                        layout: vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+                        stage: vk::PipelineStageFlags::FRAGMENT_SHADER,
+                        access: vk::AccessFlags::SHADER_READ
+                    },
+                    false
+                );
+            }
+
+            for access in &resources.buffers {
+                let state = &mut buffer_states[access.buffer.0];
+                if needs_barrier(state, access.access, false) {
+                    debug!(
+                        "render graph pass \"{}\" waits for buffer \"{}\"",
+                        declaration.name,
+                        self.buffers[access.buffer.0].name
+                    );
+                    barriers.src_stage |= state.stage;
+                    barriers.dst_stage |= access.stage;
+                    barriers.buffers.push(BufferBarrier {
+                        buffer: access.buffer,
+                        src_access: state.access,
+                        dst_access: access.access
+                    });
+                    state.stage = access.stage;
+                    state.access = access.access;
+                } else {
+                    state.stage |= access.stage;
+                    state.access |= access.access;
+                }
+            }
+
+            let extent = attachment_images.first()
+                .map_or(context.swap_chain.extent, |image| compiled.images[image.0].extent);
+
+            if attachment_images.iter().any(|image| compiled.images[image.0].extent != extent) {
+                return Err(CompileRenderGraphError(format!(
+                    "pass \"{}\": attachments have different sizes", declaration.name
+                )));
+            }
+
+            let mut compiled_pass = CompiledPass {
+                name: declaration.name.clone(),
+                render_pass: vk::RenderPass::null(),
+                framebuffers: Vec::new(),
+                extent,
+                color_attachments: declaration.resources.colors.len() as u32,
+                clear_values,
+                barriers,
+                record: declaration.record.clone()
+            };
+
+            if !attachments.is_empty() {
+                let subpass = vk::SubpassDescription::builder()
+                    .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+                    .color_attachments(&color_references);
+
+                let subpass = match &depth_reference {
+                    Some(depth_reference) => subpass.depth_stencil_attachment(depth_reference),
+                    None => subpass
+                };
+
+                let subpasses = &[subpass];
+                let info = vk::RenderPassCreateInfo::builder()
+                    .attachments(&attachments)
+                    .subpasses(subpasses);
+
+                compiled_pass.render_pass = context.device.create_render_pass(&info, None)
+                    .map_err(CreateRenderPassError)?;
+            }
+
+            //Проход добавляется до создания кадровых буферов,
+            //чтобы при ошибке все созданное было уничтожено
+            compiled.passes.push(compiled_pass);
+            let compiled_pass = compiled.passes.last_mut().unwrap();
+
+            if !compiled_pass.render_pass.is_null() {
+                for image_index in 0..context.swap_chain.images.len() {
+                    let views: Vec<vk::ImageView> = attachment_images.iter()
+                        .map(|image| match &compiled.images[image.0].attachments {
+                            Some(attachments) => attachments.image_views[image_index],
+                            None => context.swap_chain.image_views[image_index]
+                        })
+                        .collect();
+
+                    let info = vk::FramebufferCreateInfo::builder()
+                        .render_pass(compiled_pass.render_pass)
+                        .attachments(&views)
+                        .width(extent.width)
+                        .height(extent.height)
+                        .layers(1);
+
+                    let framebuffer = context.device.create_framebuffer(&info, None)
+                        .map_err(CreateFrameBufferError)?;
+                    compiled_pass.framebuffers.push(framebuffer);
+                }
+            }
+        }
+
+        let backbuffer_state = image_states[backbuffer.0];
+        compiled.present_barriers = PassBarriers {
+            src_stage: backbuffer_state.stage,
+            dst_stage: vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+            images: vec![ImageBarrier {
+                image: backbuffer,
+                aspect_mask: vk::ImageAspectFlags::COLOR,
+                old_layout: backbuffer_state.layout,
+                new_layout: vk::ImageLayout::PRESENT_SRC_KHR,
+                src_access: backbuffer_state.access,
+                dst_access: vk::AccessFlags::empty()
+            }],
+            buffers: Vec::new()
+        };
+
+        Result::Ok(())
+    }
+}
+
+//Без очистки сохраняется то, что нарисовано раньше, если оно есть
+fn load_op(clear: bool, state: &ResourceState) -> vk::AttachmentLoadOp {
+    if clear {
+        vk::AttachmentLoadOp::CLEAR
+    } else if state.layout != vk::ImageLayout::UNDEFINED {
+        vk::AttachmentLoadOp::LOAD
+    } else {
+        vk::AttachmentLoadOp::DONT_CARE
+    }
+}
+
+fn store_op(used_later: bool) -> vk::AttachmentStoreOp {
+    if used_later {
+        vk::AttachmentStoreOp::STORE
+    } else {
+        vk::AttachmentStoreOp::DONT_CARE
+    }
+}
+
+fn is_write(access: vk::AccessFlags) -> bool {
+    access.intersects(
+        vk::AccessFlags::SHADER_WRITE
+            | vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+            | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE
+            | vk::AccessFlags::TRANSFER_WRITE
+            | vk::AccessFlags::HOST_WRITE
+            | vk::AccessFlags::MEMORY_WRITE
+    )
+}
+
+//Барьер не нужен только между чтениями в одном layout
+fn needs_barrier(state: &ResourceState, access: vk::AccessFlags, layout_changed: bool) -> bool {
+    layout_changed
+        || is_write(state.access)
+        || (is_write(access) && !state.access.is_empty())
+}
+
+fn transition_image(
+    barriers: &mut PassBarriers,
+    state: &mut ResourceState,
+    image: ImageId,
+    aspect_mask: vk::ImageAspectFlags,
+    target: ResourceState,
+    discard: bool
+) {
+    if !needs_barrier(state, target.access, state.layout != target.layout) {
+        state.stage |= target.stage;
+        state.access |= target.access;
+        return;
+    }
+
+    barriers.src_stage |= state.stage;
+    barriers.dst_stage |= target.stage;
+    barriers.images.push(ImageBarrier {
+        image,
+        aspect_mask,
+        //Старое содержимое не нужно - драйвер может его не сохранять
+        old_layout: if discard { vk::ImageLayout::UNDEFINED } else { state.layout },
+        new_layout: target.layout,
+        src_access: state.access,
+        dst_access: target.access
+    });
+
+    *state = target;
+}
+
+impl CompiledGraph {
+    //Записывает все проходы кадра frame_index в командный буфер изображения image_index
+    pub unsafe fn execute(
+        &self,
+        device: &Device,
+        command_buffer: vk::CommandBuffer,
+        image_index: usize,
+        frame_index: usize,
+        info: &RecordingInfo
+    ) {
+        for pass in &self.passes {
+            self.record_barriers(device, command_buffer, image_index, &pass.barriers);
+
+            let context = PassContext {
+                device,
+                command_buffer,
+                render_pass: pass.render_pass,
+                extent: pass.extent,
+                image_index,
+                frame_index,
+                pipelines: info.pipelines,
+                meshes: info.meshes,
+                viewports: info.viewports,
+                uniform_sets: info.uniform_sets
+            };
+
+            if pass.render_pass.is_null() {
+                (pass.record)(&context);
+                continue;
+            }
+
+            let render_area = vk::Rect2D::builder()
+                .offset(vk::Offset2D::default())
+                .extent(pass.extent);
+
+            let begin_info = vk::RenderPassBeginInfo::builder()
+                .render_pass(pass.render_pass)
+                .framebuffer(pass.framebuffers[image_index])
+                .render_area(render_area)
+                .clear_values(&pass.clear_values);
+
+            device.cmd_begin_render_pass(command_buffer, &begin_info, vk::SubpassContents::INLINE);
+            (pass.record)(&context);
+            device.cmd_end_render_pass(command_buffer);
+        }
+
+        self.record_barriers(device, command_buffer, image_index, &self.present_barriers);
+    }
+
+    unsafe fn record_barriers(
+        &self,
+        device: &Device,
+        command_buffer: vk::CommandBuffer,
+        image_index: usize,
+        barriers: &PassBarriers
+    ) {
+        if barriers.is_empty() {
+            return;
+        }
+
+        let image_barriers: Vec<vk::ImageMemoryBarrier> = barriers.images.iter()
+            .map(|barrier| {
+                let subresource_range = vk::ImageSubresourceRange::builder()
+                    .aspect_mask(barrier.aspect_mask)
+                    .base_mip_level(0)
+                    .level_count(1)
+                    .base_array_layer(0)
+                    .layer_count(1)
+                    .build();
+
+                vk::ImageMemoryBarrier::builder()
+                    .old_layout(barrier.old_layout)
+                    .new_layout(barrier.new_layout)
+                    .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                    .image(self.image(barrier.image, image_index))
+                    .subresource_range(subresource_range)
+                    .src_access_mask(barrier.src_access)
+                    .dst_access_mask(barrier.dst_access)
+                    .build()
+            })
+            .collect();
+
+        let buffer_barriers: Vec<vk::BufferMemoryBarrier> = barriers.buffers.iter()
+            .map(|barrier| vk::BufferMemoryBarrier::builder()
+                .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+                .buffer(self.buffers[barrier.buffer.0])
+                .offset(0)
+                .size(vk::WHOLE_SIZE as u64)
+                .src_access_mask(barrier.src_access)
+                .dst_access_mask(barrier.dst_access)
+                .build())
+            .collect();
+
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            barriers.src_stage,
+            barriers.dst_stage,
+            vk::DependencyFlags::empty(),
+            &[] as &[vk::MemoryBarrier],
+            &buffer_barriers,
+            &image_barriers
+        );
+    }
+
+    fn image(&self, image: ImageId, image_index: usize) -> vk::Image {
+        match &self.images[image.0].attachments {
+            Some(attachments) => attachments.images[image_index],
+            None => self.swap_chain_images[image_index]
+        }
+    }
+
+    //Проход рендеринга, под который собираются конвейеры прохода
+    pub fn render_pass(&self, pass: &str) -> Option<vk::RenderPass> {
+        self.passes.iter()
+            .find(|compiled_pass| compiled_pass.name == pass)
+            .map(|compiled_pass| compiled_pass.render_pass)
+            .filter(|render_pass| !render_pass.is_null())
+    }
+
+    pub fn color_attachments(&self, pass: &str) -> u32 {
+        self.passes.iter()
+            .find(|compiled_pass| compiled_pass.name == pass)
+            .map_or(0, |compiled_pass| compiled_pass.color_attachments)
+    }
+
+    pub fn extent(&self) -> vk::Extent2D {
+        self.swap_chain_extent
+    }
+
+    //Выделения памяти под вложения графа
+    pub fn allocation_count(&self) -> usize {
+        self.images.iter()
+            .filter_map(|image| image.attachments.as_ref())
+            .map(|attachments| attachments.memories.len())
+            .sum()
+    }
+
+    pub unsafe fn destroy(&self, device: &Device) {
+        for pass in &self.passes {
+            pass.framebuffers.iter().for_each(
+                |framebuffer| device.destroy_framebuffer(*framebuffer, None)
+            );
+            if !pass.render_pass.is_null() {
+                device.destroy_render_pass(pass.render_pass, None);
+            }
+        }
+
+        for image in &self.images {
+            if let Some(attachments) = &image.attachments {
+                attachments.destroy(device);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::rendering::ImageSize;
+
+    const COLOR: ImageFormat = ImageFormat::Format(vk::Format::R8G8B8A8_UNORM);
+    const CLEAR: Option<[f32; 4]> = Some([0.0; 4]);
+
+    fn image(graph: &mut RenderGraph, name: &str) -> ImageId {
+        graph.create_image(name, COLOR, ImageSize::SwapchainRelative(1.0))
+    }
+
+    fn names(graph: &RenderGraph, order: &[usize]) -> Vec<String> {
+        order.iter().map(|pass| graph.passes[*pass].name.clone()).collect()
+    }
+
+    fn state(layout: vk::ImageLayout, stage: vk::PipelineStageFlags, access: vk::AccessFlags) -> ResourceState {
+        ResourceState { layout, stage, access }
+    }
+
+    #[test]
+    fn reader_declared_before_writer_runs_after_it() {
+        let mut graph = RenderGraph::new();
+        let backbuffer = graph.backbuffer();
+        let gbuffer = image(&mut graph, "gbuffer");
+
+        graph.add_pass("lighting", |pass| { pass.read_image(gbuffer).write_color(backbuffer, CLEAR); }, |_| {});
+        graph.add_pass("geometry", |pass| { pass.write_color(gbuffer, CLEAR); }, |_| {});
+
+        let order = graph.execution_order().unwrap();
+        assert_eq!(names(&graph, &order), ["geometry", "lighting"]);
+    }
+
+    #[test]
+    fn writes_keep_declaration_order() {
+        let mut graph = RenderGraph::new();
+        let backbuffer = graph.backbuffer();
+
+        graph.add_pass("scene", |pass| { pass.write_color(backbuffer, CLEAR); }, |_| {});
+        graph.add_pass("overlay", |pass| { pass.write_color(backbuffer, None); }, |_| {});
+
+        let order = graph.execution_order().unwrap();
+        assert_eq!(names(&graph, &order), ["scene", "overlay"]);
+    }
+
+    #[test]
+    fn cyclic_passes_are_rejected() {
+        let mut graph = RenderGraph::new();
+        let backbuffer = graph.backbuffer();
+        let first = image(&mut graph, "first");
+        let second = image(&mut graph, "second");
+
+        graph.add_pass("a", |pass| { pass.read_image(second).write_color(first, CLEAR); }, |_| {});
+        graph.add_pass("b", |pass| { pass.read_image(first).write_color(second, CLEAR); }, |_| {});
+        graph.add_pass("present", |pass| { pass.read_image(second).write_color(backbuffer, CLEAR); }, |_| {});
+
+        assert!(matches!(graph.execution_order(), Err(CompileRenderGraphError(_))));
+    }
+
+    #[test]
+    fn passes_not_reaching_backbuffer_are_culled() {
+        let mut graph = RenderGraph::new();
+        let backbuffer = graph.backbuffer();
+        let debug = image(&mut graph, "debug");
+        let shadow = image(&mut graph, "shadow");
+
+        graph.add_pass("debug", |pass| { pass.write_color(debug, CLEAR); }, |_| {});
+        graph.add_pass("shadow", |pass| { pass.write_color(shadow, CLEAR); }, |_| {});
+        graph.add_pass("scene", |pass| { pass.read_image(shadow).write_color(backbuffer, CLEAR); }, |_| {});
+
+        let order = graph.execution_order().unwrap();
+        assert_eq!(names(&graph, &order), ["shadow", "scene"]);
+    }
+
+    #[test]
+    fn pass_before_clear_of_same_image_is_culled() {
+        let mut graph = RenderGraph::new();
+        let backbuffer = graph.backbuffer();
+
+        graph.add_pass("overwritten", |pass| { pass.write_color(backbuffer, CLEAR); }, |_| {});
+        graph.add_pass("scene", |pass| { pass.write_color(backbuffer, CLEAR); }, |_| {});
+
+        let order = graph.execution_order().unwrap();
+        assert_eq!(names(&graph, &order), ["scene"]);
+    }
+
+    #[test]
+    fn graph_without_backbuffer_writes_is_rejected() {
+        let mut graph = RenderGraph::new();
+        let offscreen = image(&mut graph, "offscreen");
+        graph.add_pass("offscreen", |pass| { pass.write_color(offscreen, CLEAR); }, |_| {});
+
+        assert!(graph.execution_order().is_err());
+    }
+
+    #[test]
+    fn only_single_pass_unsampled_images_are_transient() {
+        let mut graph = RenderGraph::new();
+        let backbuffer = graph.backbuffer();
+        let depth = graph.create_image("depth", ImageFormat::Depth, ImageSize::SwapchainRelative(1.0));
+        let shadow = image(&mut graph, "shadow");
+
+        graph.add_pass("shadow", |pass| { pass.write_color(shadow, CLEAR); }, |_| {});
+        graph.add_pass("scene", |pass| {
+            pass.read_image(shadow)
+                .write_color(backbuffer, CLEAR)
+                .write_depth(depth, Some(1.0));
+        }, |_| {});
+
+        let order = graph.execution_order().unwrap();
+        assert_eq!(
+            graph.image_usage(depth, &order),
+            vk::ImageUsageFlags::DEPTH_STENCIL_ATTACHMENT | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT
+        );
+        assert_eq!(
+            graph.image_usage(shadow, &order),
+            vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::SAMPLED
+        );
+    }
+
+    #[test]
+    fn load_op_depends_on_clear_and_previous_content() {
+        let undefined = state(vk::ImageLayout::UNDEFINED, vk::PipelineStageFlags::TOP_OF_PIPE, vk::AccessFlags::empty());
+        let drawn = state(
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+        );
+
+        assert_eq!(load_op(true, &undefined), vk::AttachmentLoadOp::CLEAR);
+        assert_eq!(load_op(true, &drawn), vk::AttachmentLoadOp::CLEAR);
+        assert_eq!(load_op(false, &undefined), vk::AttachmentLoadOp::DONT_CARE);
+        assert_eq!(load_op(false, &drawn), vk::AttachmentLoadOp::LOAD);
+    }
+
+    #[test]
+    fn cleared_attachment_discards_previous_layout() {
+        let attachment = state(
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+        );
+        let mut current = state(
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            vk::PipelineStageFlags::FRAGMENT_SHADER,
+            vk::AccessFlags::SHADER_READ
+        );
+
+        let mut barriers = PassBarriers::default();
+        transition_image(&mut barriers, &mut current, ImageId(1), vk::ImageAspectFlags::COLOR, attachment, true);
+
+        assert_eq!(barriers.images.len(), 1);
+        assert_eq!(barriers.images[0].old_layout, vk::ImageLayout::UNDEFINED);
+        assert_eq!(barriers.images[0].new_layout, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+        assert_eq!(barriers.src_stage, vk::PipelineStageFlags::FRAGMENT_SHADER);
+        assert_eq!(barriers.dst_stage, vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT);
+        assert_eq!(current.layout, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+    }
+
+    #[test]
+    fn loaded_attachment_keeps_previous_layout_and_waits_for_writes() {
+        let target = state(
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            vk::AccessFlags::COLOR_ATTACHMENT_READ | vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+        );
+        let mut current = state(
+            vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL,
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+            vk::AccessFlags::COLOR_ATTACHMENT_WRITE
+        );
+
+        let mut barriers = PassBarriers::default();
+        transition_image(&mut barriers, &mut current, ImageId(1), vk::ImageAspectFlags::COLOR, target, false);
+
+        assert_eq!(barriers.images.len(), 1);
+        assert_eq!(barriers.images[0].old_layout, vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+        assert_eq!(barriers.images[0].src_access, vk::AccessFlags::COLOR_ATTACHMENT_WRITE);
+    }
+
+    #[test]
+    fn reads_in_same_layout_need_no_barrier() {
+        let sampled = state(
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            vk::PipelineStageFlags::FRAGMENT_SHADER,
+            vk::AccessFlags::SHADER_READ
+        );
+        let mut current = sampled;
+
+        let mut barriers = PassBarriers::default();
+        transition_image(&mut barriers, &mut current, ImageId(1), vk::ImageAspectFlags::COLOR, sampled, false);
+
+        assert!(barriers.is_empty());
+    }
+}
@@ -0,0 +1,197 @@
+use std::fmt;
+use std::rc::Rc;
+
+use bytemuck::Pod;
+use vulkanalia::prelude::v1_0::*;
+
+use super::{BufferDeclaration, BufferId, ImageDeclaration, ImageFormat, ImageId, ImageSize};
+use crate::rendering::{FrameDescriptorSets, GraphicsPipeline, Mesh, PipelineRegistry, RqResult, ViewportRect};
+
+//Все, что доступно проходу во время записи команд
+pub struct PassContext<'a> {
+    pub device: &'a Device,
+    pub command_buffer: vk::CommandBuffer,
+    //null для проходов без вложений
+    pub render_pass: vk::RenderPass,
+    pub extent: vk::Extent2D,
+    pub image_index: usize,
+    //Кадр в полете, по нему выбираются наборы из FrameDescriptorSets
+    pub frame_index: usize,
+    pub pipelines: &'a PipelineRegistry,
+    //Сетки, созданные через RenderingQueue::create_mesh
+    pub meshes: &'a [Mesh],
+    pub viewports: &'a [ViewportRect],
+    //Uniform данные очереди, привязываются через GraphicsPipeline::bind_uniform_set
+    pub uniform_sets: Option<&'a FrameDescriptorSets>
+}
+
+impl PassContext<'_> {
+    //Передача данных на отрисовку (матрица, цвет) без буферов и дескрипторов
+    pub unsafe fn push_constants<T: Pod>(&self, pipeline: &GraphicsPipeline, offset: u32, value: &T) -> RqResult<()> {
+        pipeline.push_constants(self.device, self.command_buffer, offset, value)
+    }
+
+    pub unsafe fn bind_descriptor_sets(&self, pipeline: &GraphicsPipeline, first_set: u32, sets: &[vk::DescriptorSet]) {
+        self.device.cmd_bind_descriptor_sets(
+            self.command_buffer,
+            vk::PipelineBindPoint::GRAPHICS,
+            pipeline.layout,
+            first_set,
+            sets,
+            &[]
+        );
+    }
+}
+
+pub type RecordPass = Rc<dyn Fn(&PassContext)>;
+
+#[derive(Debug, Clone, Copy)]
+pub struct ColorWrite {
+    pub image: ImageId,
+    //None - содержимое от предыдущих проходов сохраняется
+    pub clear: Option<[f32; 4]>
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct DepthWrite {
+    pub image: ImageId,
+    pub clear: Option<f32>
+}
+
+#[derive(Debug, Clone, Copy)]
+pub struct BufferAccess {
+    pub buffer: BufferId,
+    pub stage: vk::PipelineStageFlags,
+    pub access: vk::AccessFlags,
+    pub write: bool
+}
+
+//Ресурсы, которые проход читает и пишет
+#[derive(Debug, Default)]
+pub struct PassBuilder {
+    pub(super) colors: Vec<ColorWrite>,
+    pub(super) depth: Option<DepthWrite>,
+    pub(super) sampled: Vec<ImageId>,
+    pub(super) buffers: Vec<BufferAccess>
+}
+
+impl PassBuilder {
+    pub fn write_color(&mut self, image: ImageId, clear: Option<[f32; 4]>) -> &mut Self {
+        self.colors.push(ColorWrite { image, clear });
+        self
+    }
+
+    pub fn write_depth(&mut self, image: ImageId, clear: Option<f32>) -> &mut Self {
+        self.depth = Some(DepthWrite { image, clear });
+        self
+    }
+
+    //Изображение читается во фрагментном шейдере
+    pub fn read_image(&mut self, image: ImageId) -> &mut Self {
+        self.sampled.push(image);
+        self
+    }
+
+    pub fn read_buffer(&mut self, buffer: BufferId, stage: vk::PipelineStageFlags, access: vk::AccessFlags) -> &mut Self {
+        self.buffers.push(BufferAccess { buffer, stage, access, write: false });
+        self
+    }
+
+    pub fn write_buffer(&mut self, buffer: BufferId, stage: vk::PipelineStageFlags, access: vk::AccessFlags) -> &mut Self {
+        self.buffers.push(BufferAccess { buffer, stage, access, write: true });
+        self
+    }
+
+    pub(super) fn written_images(&self) -> impl Iterator<Item = ImageId> + '_ {
+        self.colors.iter()
+            .map(|color| color.image)
+            .chain(self.depth.map(|depth| depth.image))
+    }
+
+    //Вложения без очистки читают то, что нарисовано до них
+    pub(super) fn read_images(&self) -> impl Iterator<Item = ImageId> + '_ {
+        self.sampled.iter().copied()
+            .chain(self.colors.iter().filter(|color| color.clear.is_none()).map(|color| color.image))
+            .chain(self.depth.filter(|depth| depth.clear.is_none()).map(|depth| depth.image))
+    }
+}
+
+pub(super) struct PassDeclaration {
+    pub name: String,
+    pub resources: PassBuilder,
+    pub record: RecordPass
+}
+
+impl fmt::Debug for PassDeclaration {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.debug_struct("PassDeclaration")
+            .field("name", &self.name)
+            .field("resources", &self.resources)
+            .finish()
+    }
+}
+
+//Описание кадра: изображения, буферы и проходы в порядке объявления.
+//Порядок выполнения, барьеры и вложения вычисляются при компиляции
+#[derive(Debug)]
+pub struct RenderGraph {
+    pub(super) images: Vec<ImageDeclaration>,
+    pub(super) buffers: Vec<BufferDeclaration>,
+    pub(super) passes: Vec<PassDeclaration>
+}
+
+impl RenderGraph {
+    pub fn new() -> Self {
+        Self {
+            images: vec![ImageDeclaration {
+                name: String::from("backbuffer"),
+                format: ImageFormat::Swapchain,
+                size: ImageSize::SwapchainRelative(1.0)
+            }],
+            buffers: Vec::new(),
+            passes: Vec::new()
+        }
+    }
+
+    //Текущее изображение swap chain, результат кадра
+    pub fn backbuffer(&self) -> ImageId {
+        ImageId(0)
+    }
+
+    pub fn create_image(&mut self, name: &str, format: ImageFormat, size: ImageSize) -> ImageId {
+        self.images.push(ImageDeclaration {
+            name: String::from(name),
+            format,
+            size
+        });
+        ImageId(self.images.len() - 1)
+    }
+
+    pub fn import_buffer(&mut self, name: &str, buffer: vk::Buffer) -> BufferId {
+        self.buffers.push(BufferDeclaration {
+            name: String::from(name),
+            buffer
+        });
+        BufferId(self.buffers.len() - 1)
+    }
+
+    pub fn add_pass<S, R>(&mut self, name: &str, setup: S, record: R)
+        where S: FnOnce(&mut PassBuilder),
+              R: Fn(&PassContext) + 'static
+    {
+        let mut resources = PassBuilder::default();
+        setup(&mut resources);
+
+        self.passes.push(PassDeclaration {
+            name: String::from(name),
+            resources,
+            record: Rc::new(record)
+        });
+    }
+}
+
+impl Default for RenderGraph {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,7 @@
+mod resources;
+mod graph;
+mod compiled_graph;
+
+pub use resources::*;
+pub use graph::*;
+pub use compiled_graph::*;
@@ -0,0 +1,71 @@
+use vulkanalia::prelude::v1_0::*;
+
+//Индексы ресурсов графа, действительны для графа, который их выдал
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ImageId(pub(super) usize);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BufferId(pub(super) usize);
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageFormat {
+    //Формат swap chain
+    Swapchain,
+    //Первый поддерживаемый формат глубины
+    Depth,
+    Format(vk::Format)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ImageSize {
+    //Доля размера swap chain, пересчитывается при изменении размера окна
+    SwapchainRelative(f32),
+    Absolute(u32, u32)
+}
+
+impl ImageSize {
+    pub fn resolve(self, swap_chain_extent: vk::Extent2D) -> vk::Extent2D {
+        match self {
+            ImageSize::SwapchainRelative(scale) => vk::Extent2D {
+                width: ((swap_chain_extent.width as f32 * scale) as u32).max(1),
+                height: ((swap_chain_extent.height as f32 * scale) as u32).max(1)
+            },
+            ImageSize::Absolute(width, height) => vk::Extent2D { width, height }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ImageDeclaration {
+    pub name: String,
+    pub format: ImageFormat,
+    pub size: ImageSize
+}
+
+//Буферы создаются вне графа, граф только расставляет барьеры
+#[derive(Debug, Clone)]
+pub struct BufferDeclaration {
+    pub name: String,
+    pub buffer: vk::Buffer
+}
+
+pub fn is_depth_format(format: vk::Format) -> bool {
+    matches!(
+        format,
+        vk::Format::D16_UNORM
+            | vk::Format::X8_D24_UNORM_PACK32
+            | vk::Format::D32_SFLOAT
+            | vk::Format::D16_UNORM_S8_UINT
+            | vk::Format::D24_UNORM_S8_UINT
+            | vk::Format::D32_SFLOAT_S8_UINT
+    )
+}
+
+pub fn has_stencil(format: vk::Format) -> bool {
+    matches!(
+        format,
+        vk::Format::D16_UNORM_S8_UINT
+            | vk::Format::D24_UNORM_S8_UINT
+            | vk::Format::D32_SFLOAT_S8_UINT
+    )
+}
@@ -1,7 +1,11 @@
 use std::collections::LinkedList;
 use std::{env, path};
+use std::path::Path;
+use std::mem::size_of;
 use std::ops::{Deref, Range};
-use log::{debug, info, set_logger_racy};
+use std::time::Instant;
+use bytemuck::{bytes_of, Pod};
+use log::{debug, error, info, set_logger_racy, warn};
 
 use winit::raw_window_handle::{
     HasDisplayHandle,
@@ -19,10 +23,17 @@ use vulkanalia::window::create_surface;
 use vulkanalia::vk;
 use vulkanalia::vk::{ExtDebugUtilsExtension, Fence, KhrSurfaceExtension, KhrSwapchainExtension, PipelineLayout, Semaphore};
 use winit::dpi::PhysicalSize;
-use crate::rendering::RenderingError::{AcquireImageError, LoadShadersError, PresentationError, QueueSubmitError, ResetFenceError, SupportError, WaitForFencesError};
+use crate::rendering::RenderingError::{AcquireImageError, CompileRenderGraphError, CreateCommandBufferError, CreateMeshError, LoadShadersError, PresentationError, QueueSubmitError, ResetFenceError, SupportError, UniformsError, WaitForFencesError};
 
 use super::shaders::Shader;
-use super::{QueueFamilyIndices, RenderingError, SwapChainData, get_debug_info, RqResult, DeviceQueues};
+use super::{QueueFamilyIndices, RenderingError, SwapChainData, get_debug_info, RqResult, DeviceQueues, ColorBuffer, DepthBuffer};
+use super::{create_graphics_pipeline, record_command_buffers, GraphicsPipeline, PipelineCache, ShaderWatcher, same_file, PipelineDescription, PipelineHandle, PipelineRegistry, PipelineTarget, DebugRasterization, RasterizationFeatures};
+use super::{DescriptorAllocator, DescriptorLayoutCache, DescriptorWriter, FrameDescriptorSets, UniformBuffer};
+use super::{Camera, Index, IndirectBuffer, IndirectFeatures, InstanceBuffer, InstanceData, MemoryAllocator, MemoryStatistics, Mesh, MeshHandle, ModelData, Vertex};
+use super::{SamplerCache, SamplerDescription, Texture, TextureHandle, TextureOptions, UploadContext};
+use super::{CompiledGraph, GraphContext, RenderGraph};
+use super::{FrameCommands, FrameContext};
+use super::{create_framebuffers, RecordingInfo, RenderingResolution, SwapChainParameters, SwapСhainSupport, ViewportRect};
 
 #[derive(Debug)]
 pub struct RenderingQueue {
@@ -34,19 +45,46 @@ pub struct RenderingQueue {
     queues: Box<DeviceQueues>,
     surface: vk::SurfaceKHR,
     swap_chain: Box<SwapChainData>,
+    color_buffer: Box<ColorBuffer>,
+    depth_buffer: Box<DepthBuffer>,
     render_pass: vk::RenderPass,
-    pipeline: vk::Pipeline,
-    pipeline_layout: vk::PipelineLayout,
+    pipeline_cache: Box<PipelineCache>,
+    descriptor_layouts: Box<DescriptorLayoutCache>,
+    descriptor_allocator: DescriptorAllocator,
+    pipelines: Box<PipelineRegistry>,
+    uniforms: Option<UniformBuffer>,
+    //Наборы set 0 с uniform данными, привязываются к конвейерам с тем же макетом set 0
+    uniform_sets: Option<FrameDescriptorSets>,
+    uniform_data: Vec<u8>,
+    memory_allocator: MemoryAllocator,
+    textures: Vec<Texture>,
+    meshes: Vec<Mesh>,
+    samplers: SamplerCache,
+    rasterization_features: RasterizationFeatures,
+    indirect_features: IndirectFeatures,
+    debug_rasterization: DebugRasterization,
+    max_push_constants_size: u32,
+    shader_watcher: Option<ShaderWatcher>,
+    render_graph: Option<RenderGraph>,
+    compiled_graph: Option<CompiledGraph>,
     framebuffers: Vec<vk::Framebuffer>,
     command_pool: vk::CommandPool,
     command_buffers: Vec<vk::CommandBuffer>,
+    clear_color: [f32; 4],
+    viewports: Vec<ViewportRect>,
+    clear_depth: f32,
+    rendering_resolution: RenderingResolution,
+    swap_chain_outdated: bool,
     image_available_semaphores: Vec<vk::Semaphore>,
     render_finished_semaphores: Vec<vk::Semaphore>,
     frame_in_flight_fences: Vec<vk::Fence>,
     swapchain_image_fences: Vec<vk::Fence>,
+    //Командные буферы render_with, записываются заново каждый кадр
+    frame_commands: FrameCommands,
+    last_frame_time: Option<Instant>,
+    frame_delta_time: f32,
     flight_frames_count: u8,
-    current_frame_index: u8,
-    current_image_index: u8
+    current_frame_index: u8
 }
 
 impl RenderingQueue {
@@ -60,15 +98,20 @@ impl RenderingQueue {
         queues: Box<DeviceQueues>,
         surface: vk::SurfaceKHR,
         swap_chain: Box<super::SwapChainData>,
+        color_buffer: Box<ColorBuffer>,
+        depth_buffer: Box<DepthBuffer>,
         render_pass: vk::RenderPass,
-        pipeline: vk::Pipeline,
-        pipeline_layout: vk::PipelineLayout,
+        pipeline_cache: Box<PipelineCache>,
+        descriptor_layouts: Box<DescriptorLayoutCache>,
+        pipelines: Box<PipelineRegistry>,
         framebuffers: Vec<vk::Framebuffer>,
         command_pool: vk::CommandPool,
         command_buffers: Vec<vk::CommandBuffer>,
+        clear_color: [f32; 4],
         image_available_semaphores: Vec<vk::Semaphore>,
         render_finished_semaphores: Vec<vk::Semaphore>,
         frame_in_flight_fences: Vec<vk::Fence>,
+        frame_commands: FrameCommands,
         flight_frames_count: u8,
     ) -> RenderingQueue
     {
@@ -78,8 +121,29 @@ impl RenderingQueue {
             swapchain_image_fences.push(vk::Fence::null());
         }
 
+        let rasterization_features = unsafe {
+            RasterizationFeatures::query(&instance, physical_device)
+        };
+        let indirect_features = unsafe {
+            IndirectFeatures::query(&instance, physical_device)
+        };
+        let memory_allocator = unsafe {
+            MemoryAllocator::new(&instance, physical_device)
+        };
+        let samplers = unsafe {
+            SamplerCache::new(&instance, physical_device)
+        };
+        let max_push_constants_size = unsafe {
+            instance.get_physical_device_properties(physical_device).limits.max_push_constants_size
+        };
 
-        return RenderingQueue {
+        let rendering_resolution = RenderingResolution {
+            width: swap_chain.extent.width,
+            height: swap_chain.extent.height
+        };
+
+
+        let mut queue = RenderingQueue {
             entry,
             instance,
             messenger,
@@ -88,76 +152,183 @@ impl RenderingQueue {
             queues,
             surface,
             swap_chain,
+            color_buffer,
+            depth_buffer,
             render_pass,
-            pipeline,
-            pipeline_layout,
+            pipeline_cache,
+            descriptor_layouts,
+            descriptor_allocator: DescriptorAllocator::default(),
+            pipelines,
+            uniforms: None,
+            uniform_sets: None,
+            uniform_data: Vec::new(),
+            memory_allocator,
+            textures: Vec::new(),
+            meshes: Vec::new(),
+            samplers,
+            rasterization_features,
+            indirect_features,
+            debug_rasterization: DebugRasterization::default(),
+            max_push_constants_size,
+            shader_watcher: None,
+            render_graph: None,
+            compiled_graph: None,
             framebuffers,
             command_pool,
             command_buffers,
+            clear_color,
+            viewports: vec![ViewportRect::FULL],
+            clear_depth: 1.0,
+            rendering_resolution,
+            swap_chain_outdated: false,
             image_available_semaphores,
             render_finished_semaphores,
             flight_frames_count,
             frame_in_flight_fences,
             swapchain_image_fences,
-            current_frame_index: 0,
-            current_image_index: 0
-        }
+            frame_commands,
+            last_frame_time: None,
+            frame_delta_time: 0.0,
+            current_frame_index: 0
+        };
+        queue.update_external_allocations();
+
+        queue
     }
 
+    //Кадр из командных буферов, записанных заранее по кадрам в полете и изображениям swap chain
     pub fn render(&mut self)-> RqResult<()>{
+        self.render_frame(|queue, image_index, frame_index| {
+            Result::Ok(queue.command_buffers[frame_index * queue.framebuffers.len() + image_index])
+        })
+    }
+
+    //Кадр, который записывает record: основной проход начат в командном буфере
+    //текущего кадра в полете, содержимое может меняться от кадра к кадру.
+    //Граф рендеринга здесь не выполняется
+    pub fn render_with<F>(&mut self, record: F) -> RqResult<()>
+        where F: FnOnce(&FrameContext)
+    {
+        if self.compiled_graph.is_some() {
+            return Result::Err(SupportError("render_with records the main render pass, the render graph must be disabled"));
+        }
+
+        self.render_frame(|queue, image_index, frame_index| unsafe {
+            queue.record_frame(image_index, frame_index, record)
+        })
+    }
+
+    fn render_frame<F>(&mut self, commands: F) -> RqResult<()>
+        where F: FnOnce(&Self, usize, usize) -> RqResult<vk::CommandBuffer>
+    {
+        //Свернутое окно рисовать некуда
+        if self.rendering_resolution.width == 0 || self.rendering_resolution.height == 0 {
+            return Result::Ok(());
+        }
+
+        if self.swap_chain_outdated {
+            self.recreate_swap_chain()?;
+        }
+
+        self.reload_changed_shaders();
+
+        let now = Instant::now();
+        self.frame_delta_time = self.last_frame_time
+            .map_or(0.0, |last| (now - last).as_secs_f32());
+        self.last_frame_time = Some(now);
+
         unsafe {
             let current_frame = self.current_frame_index;
             self.current_frame_index = (current_frame + 1) % self.flight_frames_count;
 
-            let current_image = self.current_image_index;
-            self.current_image_index = (current_image + 1) % (self.swap_chain.images.len() as u8);
-
             let image_semaphore = self.image_available_semaphores[current_frame as usize];
             let finished_semaphore = self.render_finished_semaphores[current_frame as usize];
 
             let frame_fence = self.frame_in_flight_fences[current_frame as usize];
-            let image_fence = self.swapchain_image_fences[current_image as usize];
-
-            self.swapchain_image_fences[current_image as usize] = frame_fence;
 
-            let result = self.render_one_frame(
+            self.swap_chain_outdated = self.render_one_frame(
                 image_semaphore, finished_semaphore,
-                frame_fence, image_fence
+                frame_fence, current_frame as usize,
+                commands
             )?;
 
-            Result::Ok(result)
+            Result::Ok(())
+        }
+    }
+
+    //Swap chain будет пересоздан перед отрисовкой следующего кадра
+    pub fn resize(&mut self, rendering_resolution: RenderingResolution) {
+        self.rendering_resolution = rendering_resolution;
+        self.swap_chain_outdated = true;
+    }
+
+    //Области вывода, в каждую из которых рисуется сцена
+    pub fn set_viewports(&mut self, viewports: &[ViewportRect]) -> RqResult<()> {
+        unsafe {
+            self.logical_device.device_wait_idle()
+                .map_err(WaitForFencesError)?;
+
+            self.viewports = viewports.to_vec();
+            self.rerecord_command_buffers()
         }
     }
 
-    unsafe fn render_one_frame(&self,
+    //Значение очистки глубины основного прохода, 0.0 для камеры с reverse-Z
+    pub fn set_clear_depth(&mut self, depth: f32) -> RqResult<()> {
+        unsafe {
+            self.logical_device.device_wait_idle()
+                .map_err(WaitForFencesError)?;
+
+            self.clear_depth = depth;
+            self.rerecord_command_buffers()
+        }
+    }
+
+    //Возвращает true, если swap chain устарел и его нужно пересоздать
+    unsafe fn render_one_frame<F>(&mut self,
         image_available_semaphore: vk::Semaphore,
         render_finished_semaphore: vk::Semaphore,
         frame_fence: vk::Fence,
-        image_fence: vk::Fence
-    ) -> RqResult<()> {
+        frame_index: usize,
+        commands: F
+    ) -> RqResult<bool>
+        where F: FnOnce(&Self, usize, usize) -> RqResult<vk::CommandBuffer>
+    {
         //timeout nanoseconds
         let one_minute = 10_000_000_000;
         self.logical_device.wait_for_fences(&[frame_fence], true, one_minute)
             .map_err(|err| WaitForFencesError(err))?;
 
+        //Копию кадра читают только его командные буферы, а их прошлая отправка уже завершена
+        if let Some(uniforms) = &self.uniforms {
+            uniforms.write(frame_index, &self.uniform_data);
+        }
+
         let image_index = self.logical_device
             .acquire_next_image_khr(
                 self.swap_chain.swap_chain,
                 u64::MAX,
                 image_available_semaphore,
                 vk::Fence::null()
-            ).map_err(|err| AcquireImageError(err))?;
-        let image_index = image_index.0 as usize;
+            );
+        let image_index = match image_index {
+            Ok((image_index, _)) => image_index as usize,
+            Err(vk::ErrorCode::OUT_OF_DATE_KHR) => return Result::Ok(true),
+            Err(err) => return Result::Err(AcquireImageError(err))
+        };
 
+        //Изображение может еще рисоваться кадром, который его получил раньше
+        let image_fence = self.swapchain_image_fences[image_index];
         if !image_fence.is_null() {
             self.logical_device.wait_for_fences(&[image_fence], true, one_minute)
                 .map_err(|err| WaitForFencesError(err))?;
         }
+        self.swapchain_image_fences[image_index] = frame_fence;
 
         let wait_semaphores = &[image_available_semaphore];
         let wait_stages = &[vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
 
-        let command_buffers = &[self.command_buffers[image_index]];
+        let command_buffers = &[commands(self, image_index, frame_index)?];
         let signal_semaphores = &[render_finished_semaphore];
 
         let submit_info = vk::SubmitInfo::builder()
@@ -182,15 +353,766 @@ impl RenderingQueue {
             .image_indices(image_indices)
             .build();
 
+        let presentation = self.logical_device.queue_present_khr(self.queues.present, &present_info);
+        match presentation {
+            Ok(vk::SuccessCode::SUBOPTIMAL_KHR) => Result::Ok(true),
+            Ok(_) => Result::Ok(false),
+            Err(vk::ErrorCode::OUT_OF_DATE_KHR) => Result::Ok(true),
+            Err(err) => Result::Err(PresentationError(err))
+        }
+    }
+
+    //Забор кадра frame_index уже пройден, поэтому его пул можно сбросить
+    unsafe fn record_frame<F>(&self, image_index: usize, frame_index: usize, record: F) -> RqResult<vk::CommandBuffer>
+        where F: FnOnce(&FrameContext)
+    {
+        let command_buffer = self.frame_commands.begin(&self.logical_device, frame_index)?;
+        let extent = self.swap_chain.extent;
+
+        let clear_values = &[
+            vk::ClearValue {
+                color: vk::ClearColorValue { float32: self.clear_color }
+            },
+            vk::ClearValue {
+                depth_stencil: vk::ClearDepthStencilValue { depth: self.clear_depth, stencil: 0 }
+            }
+        ];
+        let render_area = vk::Rect2D::builder()
+            .offset(vk::Offset2D::default())
+            .extent(extent);
+        let begin_info = vk::RenderPassBeginInfo::builder()
+            .render_pass(self.render_pass)
+            .framebuffer(self.framebuffers[image_index])
+            .render_area(render_area)
+            .clear_values(clear_values);
+
+        self.logical_device.cmd_begin_render_pass(command_buffer, &begin_info, vk::SubpassContents::INLINE);
+        self.logical_device.cmd_set_viewport(command_buffer, 0, &[ViewportRect::FULL.to_viewport(extent)]);
+        self.logical_device.cmd_set_scissor(command_buffer, 0, &[ViewportRect::FULL.to_scissor(extent)]);
+
+        record(&FrameContext {
+            device: &self.logical_device,
+            command_buffer,
+            image_index,
+            frame_index,
+            extent,
+            delta_time: self.frame_delta_time,
+            pipelines: &self.pipelines,
+            meshes: &self.meshes,
+            viewports: &self.viewports,
+            uniform_sets: self.uniform_sets.as_ref()
+        });
+
+        self.logical_device.cmd_end_render_pass(command_buffer);
+        self.logical_device.end_command_buffer(command_buffer)
+            .map_err(CreateCommandBufferError)?;
+
+        Result::Ok(command_buffer)
+    }
+
+    //Пересоздает swap chain и все, что зависит от размера изображений.
+    //Конвейеры не пересоздаются - область вывода у них динамическая
+    fn recreate_swap_chain(&mut self) -> RqResult<()> {
+        unsafe {
+            self.logical_device.device_wait_idle()
+                .map_err(WaitForFencesError)?;
+
+            let support = SwapСhainSupport::create(
+                &self.instance,
+                &self.surface,
+                &self.physical_device
+            )?;
+
+            //Все созданное уничтожается при ошибке на любом шаге
+            let mut resources = PendingSwapChain::new(&self.logical_device);
+
+            let swap_chain = resources.swap_chain.insert(SwapChainData::create(
+                &self.logical_device,
+                self.surface,
+                &self.queues.indices,
+                &support,
+                SwapChainParameters {
+                    rendering_resolution: self.rendering_resolution,
+                    old_swapchain: self.swap_chain.swap_chain,
+                    transparent: self.swap_chain.is_transparent()
+                }
+            )?);
+
+            let color_buffer = resources.color_buffer.insert(ColorBuffer::create(
+                &self.instance,
+                &self.logical_device,
+                self.physical_device,
+                swap_chain,
+                self.color_buffer.samples,
+                self.color_buffer.min_sample_shading
+            )?);
+
+            let depth_buffer = resources.depth_buffer.insert(DepthBuffer::create(
+                &self.instance,
+                &self.logical_device,
+                self.physical_device,
+                swap_chain,
+                self.depth_buffer.samples,
+                self.depth_buffer.use_stencil
+            )?);
+
+            resources.framebuffers = create_framebuffers(
+                &self.logical_device,
+                self.render_pass,
+                swap_chain,
+                color_buffer,
+                depth_buffer
+            )?;
+
+            if let Some(graph) = &self.render_graph {
+                let context = self.graph_context(resources.swap_chain.as_ref().unwrap());
+                resources.compiled_graph = Some(graph.compile(&context)?);
+            }
+
+            let (swap_chain, color_buffer, depth_buffer, framebuffers, compiled_graph) = resources.take();
+
+            self.destroy_swap_chain();
+
+            *self.swap_chain = swap_chain;
+            self.compiled_graph = compiled_graph;
+            *self.color_buffer = color_buffer;
+            *self.depth_buffer = depth_buffer;
+            self.framebuffers = framebuffers;
+            self.update_external_allocations();
+
+            let images_count = self.swap_chain.images.len();
+            self.swapchain_image_fences = vec![vk::Fence::null(); images_count];
+
+            //Количество изображений могло измениться
+            //Буфер на каждое изображение в каждом кадре в полете
+            let command_buffers_count = images_count * self.flight_frames_count as usize;
+            if self.command_buffers.len() != command_buffers_count {
+                self.logical_device.free_command_buffers(self.command_pool, &self.command_buffers);
+
+                let allocate_info = vk::CommandBufferAllocateInfo::builder()
+                    .command_pool(self.command_pool)
+                    .level(vk::CommandBufferLevel::PRIMARY)
+                    .command_buffer_count(command_buffers_count as u32);
+
+                self.command_buffers = self.logical_device.allocate_command_buffers(&allocate_info)
+                    .map_err(CreateCommandBufferError)?;
+            }
+
+            self.rerecord_command_buffers()?;
+            self.swap_chain_outdated = false;
+
+            debug!("swap chain recreated {:?}", self.swap_chain.extent);
+        }
+
+        Result::Ok(())
+    }
+
+    //Вложения выделяют память в обход распределителя, но входят в общий лимит выделений
+    fn update_external_allocations(&mut self) {
+        let graph_allocations = self.compiled_graph.as_ref()
+            .map_or(0, CompiledGraph::allocation_count);
+
+        self.memory_allocator.set_external_allocations(
+            self.color_buffer.attachments.memories.len()
+                + self.depth_buffer.attachments.memories.len()
+                + graph_allocations
+        );
+    }
+
+    unsafe fn destroy_swap_chain(&mut self) {
+        if let Some(graph) = &self.compiled_graph {
+            graph.destroy(&self.logical_device);
+        }
+
+        self.framebuffers.iter().for_each(
+            |buffer| self.logical_device.destroy_framebuffer(*buffer, None)
+        );
+
+        self.color_buffer.destroy(&self.logical_device);
+        self.depth_buffer.destroy(&self.logical_device);
+        self.swap_chain.destroy(&self.logical_device);
+    }
+
+
+
+    //Добавляет конвейер во время работы и перезаписывает командные буферы
+    pub fn add_pipeline(
+        &mut self,
+        description: &PipelineDescription,
+        vertex_shader: &Shader,
+        fragment_shader: &Shader
+    ) -> RqResult<PipelineHandle> {
+        self.insert_pipeline(None, description, vertex_shader, fragment_shader)
+    }
+
+    //Конвейер для прохода графа: вложения прохода одновыборочные,
+    //поэтому конвейеры основного прохода с ним несовместимы
+    pub fn add_pass_pipeline(
+        &mut self,
+        pass: &str,
+        description: &PipelineDescription,
+        vertex_shader: &Shader,
+        fragment_shader: &Shader
+    ) -> RqResult<PipelineHandle> {
+        self.insert_pipeline(Some(pass), description, vertex_shader, fragment_shader)
+    }
+
+    fn pipeline_target(&self, pass: Option<&str>) -> RqResult<PipelineTarget> {
+        let target = match pass {
+            None => PipelineTarget {
+                render_pass: self.render_pass,
+                cache: self.pipeline_cache.cache,
+                samples: self.color_buffer.samples,
+                min_sample_shading: self.color_buffer.min_sample_shading,
+                color_attachments: 1,
+                features: self.rasterization_features,
+                debug: self.debug_rasterization,
+                max_push_constants_size: self.max_push_constants_size
+            },
+            Some(pass) => {
+                let graph = self.compiled_graph.as_ref()
+                    .ok_or_else(|| CompileRenderGraphError(String::from("render graph is not set")))?;
+                let render_pass = graph.render_pass(pass)
+                    .ok_or_else(|| CompileRenderGraphError(format!("pass \"{}\" has no attachments or does not exist", pass)))?;
+
+                PipelineTarget {
+                    render_pass,
+                    cache: self.pipeline_cache.cache,
+                    samples: vk::SampleCountFlags::_1,
+                    min_sample_shading: None,
+                    color_attachments: graph.color_attachments(pass),
+                    features: self.rasterization_features,
+                    debug: self.debug_rasterization,
+                    max_push_constants_size: self.max_push_constants_size
+                }
+            }
+        };
+
+        Result::Ok(target)
+    }
+
+    fn insert_pipeline(
+        &mut self,
+        pass: Option<&str>,
+        description: &PipelineDescription,
+        vertex_shader: &Shader,
+        fragment_shader: &Shader
+    ) -> RqResult<PipelineHandle> {
+        let target = self.pipeline_target(pass)?;
+
+        unsafe {
+            let mut pipeline = create_graphics_pipeline(
+                &self.logical_device,
+                &target,
+                &mut self.descriptor_layouts,
+                description,
+                vertex_shader,
+                fragment_shader
+            )?;
+            pipeline.pass = pass.map(String::from);
+
+            //Старый конвейер или командные буферы могут еще выполняться
+            self.logical_device.device_wait_idle()
+                .map_err(WaitForFencesError)?;
+
+            let (handle, replaced) = self.pipelines.insert(pipeline);
+            if let Some(replaced) = replaced {
+                replaced.destroy(&self.logical_device);
+            }
+
+            self.rerecord_command_buffers()?;
+
+            Result::Ok(handle)
+        }
+    }
+
+    //Кадр рисуется графом вместо основного прохода, None возвращает основной проход.
+    //Граф перекомпилируется при каждом пересоздании swap chain
+    pub fn set_render_graph(&mut self, graph: Option<RenderGraph>) -> RqResult<()> {
+        unsafe {
+            let compiled_graph = match &graph {
+                Some(graph) => Some(graph.compile(&self.graph_context(&self.swap_chain))?),
+                None => None
+            };
+
+            self.logical_device.device_wait_idle()
+                .map_err(WaitForFencesError)?;
+
+            if let Some(old_graph) = &self.compiled_graph {
+                old_graph.destroy(&self.logical_device);
+            }
+
+            self.render_graph = graph;
+            self.compiled_graph = compiled_graph;
+            self.update_external_allocations();
+
+            self.rerecord_command_buffers()
+        }
+    }
+
+    fn graph_context<'a>(&'a self, swap_chain: &'a SwapChainData) -> GraphContext<'a> {
+        GraphContext {
+            instance: &self.instance,
+            device: &self.logical_device,
+            physical_device: self.physical_device,
+            swap_chain
+        }
+    }
+
+    //Загружает описание конвейера и его шейдеры из любого пути,
+    //конвейер с тем же именем заменяется
+    pub fn load_pipeline(&mut self, path: &Path) -> RqResult<PipelineHandle> {
+        let description = PipelineDescription::load(path)?;
+
+        let mut buffer = Vec::with_capacity(4096);
+        let (vertex_shader, fragment_shader) = description.read_shaders(&mut buffer)?;
+
+        self.add_pipeline(&description, &vertex_shader, &fragment_shader)
+    }
+
+    //Включает пересборку конвейеров при изменении шейдеров в каталоге
+    pub fn watch_shaders(&mut self, directory: &Path) -> RqResult<()> {
+        self.shader_watcher = Some(ShaderWatcher::new(directory)?);
+        Result::Ok(())
+    }
+
+    //Если новый шейдер не загрузился или не собрался,
+    //остается старый конвейер
+    fn reload_changed_shaders(&mut self) {
+        let changed = match &self.shader_watcher {
+            Some(watcher) => watcher.changed_shaders(),
+            None => return
+        };
+
+        if changed.is_empty() {
+            return;
+        }
+
+        let descriptions: Vec<(PipelineDescription, Option<String>)> = self.pipelines.iter()
+            .map(|(_, pipeline)| (&pipeline.description, &pipeline.pass))
+            .filter(|(description, _)| changed.iter().any(|path|
+                same_file(path, &description.shader_path(&description.shaders.vertex))
+                    || same_file(path, &description.shader_path(&description.shaders.fragment))
+            ))
+            .map(|(description, pass)| (description.clone(), pass.clone()))
+            .collect();
+
+        let mut buffer = Vec::with_capacity(4096);
+        for (description, pass) in descriptions {
+            let reloaded = description.read_shaders(&mut buffer)
+                .and_then(|(vertex_shader, fragment_shader)|
+                    self.insert_pipeline(pass.as_deref(), &description, &vertex_shader, &fragment_shader)
+                );
+
+            match reloaded {
+                Ok(_) => info!("pipeline \"{}\" reloaded", description.name),
+                Err(err) => error!(
+                    "pipeline \"{}\" was not reloaded, keeping previous one: {:?}",
+                    description.name,
+                    err
+                )
+            }
+        }
+    }
+
+    //Каркас, точки, отключение отсечения граней для всех конвейеров сразу.
+    //Конвейеры пересобираются из сохраненных шейдеров. Если какой-то не собрался,
+    //остаются старые конвейеры и прежний режим
+    pub fn set_debug_rasterization(&mut self, debug: DebugRasterization) -> RqResult<()> {
+        if self.debug_rasterization == debug {
+            return Result::Ok(());
+        }
+
+        let pipelines: Vec<(PipelineDescription, Option<String>, Shader, Shader)> = self.pipelines.iter()
+            .map(|(_, pipeline)| (
+                pipeline.description.clone(),
+                pipeline.pass.clone(),
+                pipeline.vertex_shader.clone(),
+                pipeline.fragment_shader.clone()
+            ))
+            .collect();
+
+        let mut rebuilt = Vec::with_capacity(pipelines.len());
+        for (description, pass, vertex_shader, fragment_shader) in pipelines {
+            let pipeline = self.pipeline_target(pass.as_deref())
+                .and_then(|target| unsafe {
+                    create_graphics_pipeline(
+                        &self.logical_device,
+                        &PipelineTarget { debug, ..target },
+                        &mut self.descriptor_layouts,
+                        &description,
+                        &vertex_shader,
+                        &fragment_shader
+                    )
+                });
+
+            match pipeline {
+                Ok(mut pipeline) => {
+                    pipeline.pass = pass;
+                    rebuilt.push(pipeline);
+                }
+                Err(err) => {
+                    unsafe {
+                        rebuilt.iter().for_each(|pipeline| pipeline.destroy(&self.logical_device));
+                    }
+                    return Result::Err(err);
+                }
+            }
+        }
+
+        unsafe {
+            //Старые конвейеры могут еще выполняться
+            self.logical_device.device_wait_idle()
+                .map_err(WaitForFencesError)?;
+
+            for pipeline in rebuilt {
+                if let (_, Some(replaced)) = self.pipelines.insert(pipeline) {
+                    replaced.destroy(&self.logical_device);
+                }
+            }
+            self.debug_rasterization = debug;
+
+            self.rerecord_command_buffers()
+        }
+    }
+
+    pub fn debug_rasterization(&self) -> DebugRasterization {
+        self.debug_rasterization
+    }
+
+    //Макет из общего кэша: одинаковые привязки дают тот же макет, что и у конвейеров
+    pub fn descriptor_set_layout(&mut self, bindings: &[vk::DescriptorSetLayoutBinding]) -> RqResult<vk::DescriptorSetLayout> {
+        unsafe {
+            self.descriptor_layouts.get_or_create(&self.logical_device, bindings)
+        }
+    }
+
+    pub fn allocate_descriptor_set(&mut self, layout: vk::DescriptorSetLayout) -> RqResult<vk::DescriptorSet> {
+        unsafe {
+            self.descriptor_allocator.allocate(&self.logical_device, layout)
+        }
+    }
+
+    //Набор на каждый кадр в полете, при записи прохода
+    //берется набор по PassContext::frame_index
+    pub fn allocate_frame_descriptor_sets(&mut self, layout: vk::DescriptorSetLayout) -> RqResult<FrameDescriptorSets> {
+        unsafe {
+            FrameDescriptorSets::allocate(
+                &self.logical_device,
+                &mut self.descriptor_allocator,
+                layout,
+                self.flight_frames_count as usize
+            )
+        }
+    }
+
+    //Наборы могут использоваться записанными кадрами, поэтому запись ждет устройство
+    pub fn write_descriptor_sets(&self, sets: &[vk::DescriptorSet], writer: &DescriptorWriter) -> RqResult<()> {
+        unsafe {
+            self.logical_device.device_wait_idle()
+                .map_err(WaitForFencesError)?;
+
+            sets.iter().for_each(|set| writer.update(&self.logical_device, *set));
+        }
+
+        Result::Ok(())
+    }
+
+    //Набор кадра frame_index читают только его команды, поэтому достаточно
+    //дождаться забора этого кадра
+    pub fn write_frame_descriptor_set(
+        &self,
+        sets: &FrameDescriptorSets,
+        frame_index: usize,
+        writer: &DescriptorWriter
+    ) -> RqResult<()> {
         unsafe {
-            self.logical_device.queue_present_khr(self.queues.present, &present_info)
-                .map_err(|err| PresentationError(err));
+            self.logical_device.wait_for_fences(&[self.frame_in_flight_fences[frame_index]], true, u64::MAX)
+                .map_err(WaitForFencesError)?;
+
+            sets.write_frame(&self.logical_device, frame_index, writer);
         }
 
         Result::Ok(())
     }
 
+    pub fn write_frame_descriptor_sets(&self, sets: &FrameDescriptorSets, writer: &DescriptorWriter) -> RqResult<()> {
+        unsafe {
+            self.logical_device.wait_for_fences(&self.frame_in_flight_fences, true, u64::MAX)
+                .map_err(WaitForFencesError)?;
+
+            sets.write_all(&self.logical_device, writer);
+        }
 
+        Result::Ok(())
+    }
+
+    //Данные кадра (камера, преобразования) для следующих вызовов render().
+    //Буфер создается при первом вызове под размер T, копия на каждый кадр в полете.
+    //Pod гарантирует, что в T нет неинициализированных байт выравнивания
+    pub fn update_uniforms<T: Pod>(&mut self, value: &T) -> RqResult<()> {
+        let size = size_of::<T>();
+        match &self.uniforms {
+            Some(uniforms) if uniforms.data_size() != size as vk::DeviceSize => {
+                return Err(UniformsError(format!(
+                    "uniforms were created for {} bytes, got {}",
+                    uniforms.data_size(), size
+                )));
+            }
+            Some(_) => {}
+            None => unsafe {
+                let uniforms = UniformBuffer::create(
+                    &self.instance,
+                    &self.logical_device,
+                    self.physical_device,
+                    &mut self.memory_allocator,
+                    size as vk::DeviceSize,
+                    self.flight_frames_count as usize
+                )?;
+
+                let sets = self.uniform_layout()
+                    .and_then(|layout| uniforms.allocate_descriptor_sets(
+                        &self.logical_device,
+                        &mut self.descriptor_allocator,
+                        layout,
+                        0
+                    ));
+                let sets = match sets {
+                    Ok(sets) => sets,
+                    Err(err) => {
+                        uniforms.destroy(&self.logical_device, &mut self.memory_allocator);
+                        return Err(err);
+                    }
+                };
+
+                self.uniforms = Some(uniforms);
+                self.uniform_sets = Some(sets);
+
+                //Уже записанные буферы команд не привязывают наборы
+                self.logical_device.device_wait_idle()
+                    .map_err(WaitForFencesError)?;
+                self.rerecord_command_buffers()?;
+            }
+        }
+
+        self.uniform_data.clear();
+        self.uniform_data.extend_from_slice(bytes_of(value));
+
+        Result::Ok(())
+    }
+
+    //Матрицы камеры для текущего соотношения сторон окна через update_uniforms
+    pub fn update_camera(&mut self, camera: &Camera) -> RqResult<()> {
+        let extent = self.swap_chain.extent;
+        let aspect = extent.width as f32 / extent.height.max(1) as f32;
+        self.update_uniforms(&camera.uniforms(aspect))
+    }
+
+    //Наборы, в которых binding указывает на копию данных своего кадра в полете
+    pub fn uniform_descriptor_sets(&mut self, layout: vk::DescriptorSetLayout, binding: u32) -> RqResult<FrameDescriptorSets> {
+        let uniforms = self.uniforms.as_ref()
+            .ok_or_else(|| UniformsError(String::from("update_uniforms must be called before binding uniforms")))?;
+
+        unsafe {
+            uniforms.allocate_descriptor_sets(&self.logical_device, &mut self.descriptor_allocator, layout, binding)
+        }
+    }
+
+    //Макет set 0 для uniform данных: binding 0 в вершинном и фрагментном шейдерах.
+    //Конвейер, объявивший такой же set 0, получит его из того же кэша
+    unsafe fn uniform_layout(&mut self) -> RqResult<vk::DescriptorSetLayout> {
+        let binding = vk::DescriptorSetLayoutBinding::builder()
+            .binding(0)
+            .descriptor_type(vk::DescriptorType::UNIFORM_BUFFER)
+            .descriptor_count(1)
+            .stage_flags(vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT)
+            .build();
+        self.descriptor_layouts.get_or_create(&self.logical_device, &[binding])
+    }
+
+    fn upload_context(&mut self) -> UploadContext<'_> {
+        UploadContext {
+            instance: &self.instance,
+            device: &self.logical_device,
+            physical_device: self.physical_device,
+            allocator: &mut self.memory_allocator,
+            command_pool: self.command_pool,
+            queue: self.queues.graphics
+        }
+    }
+
+    //Текстура живет до уничтожения очереди
+    pub fn load_texture(&mut self, path: &Path, options: TextureOptions) -> RqResult<TextureHandle> {
+        let texture = unsafe {
+            Texture::load(&mut self.upload_context(), path, options)?
+        };
+        debug!("texture {} loaded {:?}, {} mip levels", path.display(), texture.extent, texture.mip_levels);
+
+        self.textures.push(texture);
+        Result::Ok(TextureHandle(self.textures.len() - 1))
+    }
+
+    pub fn create_texture(&mut self, width: u32, height: u32, pixels: &[u8], options: TextureOptions) -> RqResult<TextureHandle> {
+        let texture = unsafe {
+            Texture::from_rgba(&mut self.upload_context(), width, height, pixels, options)?
+        };
+
+        self.textures.push(texture);
+        Result::Ok(TextureHandle(self.textures.len() - 1))
+    }
+
+    pub fn texture(&self, handle: TextureHandle) -> Option<&Texture> {
+        self.textures.get(handle.0)
+    }
+
+    //Сетка рисуется каждым конвейером с подходящей раскладкой вершин,
+    //командные буферы перезаписываются
+    pub fn create_mesh<V: Vertex, I: Index>(&mut self, vertices: &[V], indices: &[I]) -> RqResult<MeshHandle> {
+        let mesh = unsafe {
+            Mesh::new(&mut self.upload_context(), vertices, indices)?
+        };
+        debug!("mesh created: {} vertices, {} indices", vertices.len(), indices.len());
+
+        self.meshes.push(mesh);
+        unsafe {
+            self.logical_device.device_wait_idle()
+                .map_err(WaitForFencesError)?;
+            self.rerecord_command_buffers()?;
+        }
+
+        Result::Ok(MeshHandle(self.meshes.len() - 1))
+    }
+
+    //Каждый примитив модели становится отдельной сеткой с ModelVertex
+    pub fn load_model(&mut self, path: &Path) -> RqResult<Vec<MeshHandle>> {
+        let model = ModelData::load(path)?;
+
+        let mut handles = Vec::with_capacity(model.primitives.len());
+        for primitive in &model.primitives {
+            let mesh = unsafe {
+                Mesh::new(&mut self.upload_context(), &primitive.vertices, &primitive.indices)?
+            };
+            debug!("primitive \"{}\": {} vertices, {} indices",
+                primitive.name, primitive.vertices.len(), primitive.indices.len());
+
+            self.meshes.push(mesh);
+            handles.push(MeshHandle(self.meshes.len() - 1));
+        }
+        debug!("model {} loaded: {} primitives", path.display(), handles.len());
+
+        let accepted = self.meshes.last()
+            .is_some_and(|mesh| self.pipelines.iter().any(|(_, pipeline)| pipeline.accepts(mesh)));
+        if !accepted {
+            warn!("no pipeline takes ModelVertex input, model {} will not be drawn", path.display());
+        }
+
+        unsafe {
+            self.logical_device.device_wait_idle()
+                .map_err(WaitForFencesError)?;
+            self.rerecord_command_buffers()?;
+        }
+
+        Result::Ok(handles)
+    }
+
+    //Сетка рисуется count раз конвейерами с binding экземпляров того же шага,
+    //прежние данные экземпляров уничтожаются. Экземпляров должно хватать косвенным командам сетки
+    pub fn set_mesh_instances<T: InstanceData>(&mut self, handle: MeshHandle, instances: &[T]) -> RqResult<()> {
+        let mesh = self.meshes.get(handle.0)
+            .ok_or_else(|| CreateMeshError(format!("mesh {} does not exist", handle.0)))?;
+        if let Some(indirect) = mesh.indirect.as_ref().filter(|indirect| indirect.instance_end as usize > instances.len()) {
+            return Err(CreateMeshError(format!(
+                "mesh {}: indirect commands read {} instances, got {}",
+                handle.0, indirect.instance_end, instances.len()
+            )));
+        }
+
+        let buffer = unsafe {
+            InstanceBuffer::new(&mut self.upload_context(), instances)?
+        };
+        debug!("mesh {}: {} instances", handle.0, buffer.count);
+
+        unsafe {
+            self.logical_device.device_wait_idle()
+                .map_err(WaitForFencesError)?;
+            if let Some(old) = self.meshes[handle.0].instances.replace(buffer) {
+                old.destroy(&self.logical_device, &mut self.memory_allocator);
+            }
+            self.rerecord_command_buffers()
+        }
+    }
+
+    //Сетка рисуется командами из буфера устройства. Буфер можно перезаписать
+    //вычислительным проходом через IndirectBuffer::descriptor_writer
+    pub fn set_mesh_indirect(&mut self, handle: MeshHandle, commands: &[vk::DrawIndexedIndirectCommand]) -> RqResult<()> {
+        let mesh = self.meshes.get(handle.0)
+            .ok_or_else(|| CreateMeshError(format!("mesh {} does not exist", handle.0)))?;
+        let index_count = mesh.index_count;
+        let instance_count = mesh.instances.as_ref().map(|instances| instances.count);
+
+        let features = self.indirect_features;
+        let buffer = unsafe {
+            IndirectBuffer::new(&mut self.upload_context(), features, commands, index_count, instance_count)?
+        };
+        debug!("mesh {}: {} indirect commands, multi draw {}", handle.0, buffer.count, features.multi_draw);
+
+        unsafe {
+            self.logical_device.device_wait_idle()
+                .map_err(WaitForFencesError)?;
+            if let Some(old) = self.meshes[handle.0].indirect.replace(buffer) {
+                old.destroy(&self.logical_device, &mut self.memory_allocator);
+            }
+            self.rerecord_command_buffers()
+        }
+    }
+
+    pub fn mesh(&self, handle: MeshHandle) -> Option<&Mesh> {
+        self.meshes.get(handle.0)
+    }
+
+    pub fn memory_statistics(&self) -> MemoryStatistics {
+        self.memory_allocator.statistics()
+    }
+
+    pub fn sampler(&mut self, description: &SamplerDescription) -> RqResult<vk::Sampler> {
+        unsafe {
+            self.samplers.get_or_create(&self.logical_device, description)
+        }
+    }
+
+    pub fn pipeline(&self, handle: PipelineHandle) -> Option<&GraphicsPipeline> {
+        self.pipelines.get(handle)
+    }
+
+    pub fn find_pipeline(&self, name: &str) -> Option<PipelineHandle> {
+        self.pipelines.find(name)
+    }
+
+    pub fn pipelines(&self) -> &PipelineRegistry {
+        &self.pipelines
+    }
+
+    unsafe fn rerecord_command_buffers(&self) -> RqResult<()> {
+        self.logical_device.reset_command_pool(self.command_pool, vk::CommandPoolResetFlags::empty())
+            .map_err(CreateCommandBufferError)?;
+
+        record_command_buffers(
+            &self.logical_device,
+            &self.command_buffers,
+            &RecordingInfo {
+                render_pass: self.render_pass,
+                framebuffers: &self.framebuffers,
+                extent: self.swap_chain.extent,
+                clear_color: self.clear_color,
+                clear_depth: self.clear_depth,
+                viewports: &self.viewports,
+                pipelines: &self.pipelines,
+                meshes: &self.meshes,
+                uniform_sets: self.uniform_sets.as_ref(),
+                graph: self.compiled_graph.as_ref()
+            }
+        )
+    }
 
     pub fn device_wait_idle(&self){
         unsafe {
@@ -214,21 +1136,30 @@ impl Drop for RenderingQueue {
                 |fence| self.logical_device.destroy_fence(*fence, None)
             );
 
-            self.framebuffers.iter().for_each(
-                |buffer| self.logical_device.destroy_framebuffer(*buffer, None)
-            );
             self.logical_device.destroy_command_pool(self.command_pool, None);
+            self.frame_commands.destroy(&self.logical_device);
 
-            self.logical_device.destroy_pipeline(self.pipeline, None);
-            self.logical_device.destroy_pipeline_layout(self.pipeline_layout, None);
-
-            self.logical_device.destroy_render_pass(self.render_pass, None);
+            //Кэш сохраняется до уничтожения устройства, ошибка записи не мешает закрытию
+            if let Err(err) = self.pipeline_cache.save(&self.logical_device) {
+                error!("pipeline cache was not saved: {:?}", err);
+            }
+            self.pipeline_cache.destroy(&self.logical_device);
 
-            for image_view in &self.swap_chain.image_views{
-                self.logical_device.destroy_image_view(*image_view, None);
+            self.pipelines.destroy(&self.logical_device);
+            self.descriptor_allocator.destroy(&self.logical_device);
+            if let Some(uniforms) = &self.uniforms {
+                uniforms.destroy(&self.logical_device, &mut self.memory_allocator);
             }
+            self.textures.drain(..).for_each(|texture| texture.destroy(&self.logical_device, &mut self.memory_allocator));
+            self.meshes.drain(..).for_each(|mesh| mesh.destroy(&self.logical_device, &mut self.memory_allocator));
+            //Статистика и утечки попадают в журнал
+            self.memory_allocator.destroy(&self.logical_device);
+            self.samplers.destroy(&self.logical_device);
+            self.descriptor_layouts.destroy(&self.logical_device);
 
-            self.logical_device.destroy_swapchain_khr(self.swap_chain.swap_chain, None);
+            self.logical_device.destroy_render_pass(self.render_pass, None);
+
+            self.destroy_swap_chain();
 
             self.instance.destroy_surface_khr(self.surface, None);
             self.logical_device.destroy_device(None);
@@ -241,4 +1172,59 @@ impl Drop for RenderingQueue {
         }
         debug!("instance destroyed");
     }
-}
\ No newline at end of file
+}
+
+//Ресурсы нового swap chain, которые еще не переданы очереди.
+//Если пересоздание прервалось ошибкой, они уничтожаются при выходе
+struct PendingSwapChain<'a> {
+    device: &'a Device,
+    swap_chain: Option<SwapChainData>,
+    color_buffer: Option<ColorBuffer>,
+    depth_buffer: Option<DepthBuffer>,
+    framebuffers: Vec<vk::Framebuffer>,
+    compiled_graph: Option<CompiledGraph>
+}
+
+impl<'a> PendingSwapChain<'a> {
+    fn new(device: &'a Device) -> Self {
+        Self {
+            device,
+            swap_chain: None,
+            color_buffer: None,
+            depth_buffer: None,
+            framebuffers: Vec::new(),
+            compiled_graph: None
+        }
+    }
+
+    //Вызывается, когда все создано: после этого уничтожать нечего
+    fn take(mut self) -> (SwapChainData, ColorBuffer, DepthBuffer, Vec<vk::Framebuffer>, Option<CompiledGraph>) {
+        (
+            self.swap_chain.take().unwrap(),
+            self.color_buffer.take().unwrap(),
+            self.depth_buffer.take().unwrap(),
+            std::mem::take(&mut self.framebuffers),
+            self.compiled_graph.take()
+        )
+    }
+}
+
+impl Drop for PendingSwapChain<'_> {
+    fn drop(&mut self) {
+        unsafe {
+            if let Some(graph) = &self.compiled_graph {
+                graph.destroy(self.device);
+            }
+            self.framebuffers.iter().for_each(|framebuffer| self.device.destroy_framebuffer(*framebuffer, None));
+            if let Some(depth_buffer) = &self.depth_buffer {
+                depth_buffer.destroy(self.device);
+            }
+            if let Some(color_buffer) = &self.color_buffer {
+                color_buffer.destroy(self.device);
+            }
+            if let Some(swap_chain) = &self.swap_chain {
+                swap_chain.destroy(self.device);
+            }
+        }
+    }
+}
@@ -0,0 +1,128 @@
+use std::env;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::process::Command;
+use std::sync::mpsc::{channel, Receiver};
+
+use log::{error, info};
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+
+use crate::rendering::RenderingError::WatchShadersError;
+use crate::rendering::RqResult;
+
+//Следит за каталогом шейдеров и сообщает об измененных .spv файлах.
+//Исходники GLSL перекомпилируются в .spv рядом с собой на потоке
+//наблюдателя, чтобы компилятор не задерживал кадр
+#[derive(Debug)]
+pub struct ShaderWatcher {
+    //Наблюдатель работает, пока жив объект
+    _watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<Event>>
+}
+
+impl ShaderWatcher {
+    pub fn new(directory: &Path) -> RqResult<Self> {
+        let (sender, events) = channel();
+
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<Event>| {
+            if let Ok(event) = &event {
+                if matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                    //Записанный компилятором .spv придет следующим событием
+                    event.paths.iter()
+                        .filter(|path| is_glsl_source(path))
+                        .for_each(|path| compile_glsl(path));
+                }
+            }
+            let _ = sender.send(event);
+        }).map_err(|err| WatchShadersError(format!("{}: {}", directory.display(), err)))?;
+
+        watcher.watch(directory, RecursiveMode::Recursive)
+            .map_err(|err| WatchShadersError(format!("{}: {}", directory.display(), err)))?;
+
+        info!("watching shaders in {}", directory.display());
+
+        Result::Ok(Self {
+            _watcher: watcher,
+            events
+        })
+    }
+
+    //Забирает накопившиеся события без ожидания
+    pub fn changed_shaders(&self) -> Vec<PathBuf> {
+        let mut changed = Vec::new();
+
+        for event in self.events.try_iter() {
+            let event = match event {
+                Ok(event) => event,
+                Err(err) => {
+                    error!("shader watcher error: {}", err);
+                    continue;
+                }
+            };
+
+            if !matches!(event.kind, EventKind::Create(_) | EventKind::Modify(_)) {
+                continue;
+            }
+
+            for path in event.paths {
+                if is_spirv(&path) && !changed.contains(&path) {
+                    changed.push(path);
+                }
+            }
+        }
+
+        changed
+    }
+}
+
+//Пути из описания и от наблюдателя могут различаться записью
+pub fn same_file(first: &Path, second: &Path) -> bool {
+    match (fs::canonicalize(first), fs::canonicalize(second)) {
+        (Ok(first), Ok(second)) => first == second,
+        _ => first == second
+    }
+}
+
+fn is_spirv(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension == "spv")
+}
+
+fn is_glsl_source(path: &Path) -> bool {
+    let extension = path.extension()
+        .and_then(|extension| extension.to_str())
+        .unwrap_or("");
+
+    matches!(extension, "vert" | "frag" | "geom" | "comp" | "tesc" | "tese")
+}
+
+//Имя результата как у сборочного скрипта: Example.vert -> Example.vert.spv.
+//Компилятор берется из GLSLC, по умолчанию glslc из PATH
+fn compile_glsl(source: &Path) {
+    let compiler = env::var("GLSLC").unwrap_or_else(|_| String::from("glslc"));
+
+    let mut output_file = source.as_os_str().to_owned();
+    output_file.push(".spv");
+    let output_file = PathBuf::from(output_file);
+
+    let output = Command::new(&compiler)
+        .arg(source)
+        .arg("-o")
+        .arg(&output_file)
+        .output();
+
+    match output {
+        Ok(output) if output.status.success() => {
+            info!("shader compiled {}", output_file.display());
+        }
+        Ok(output) => {
+            error!(
+                "shader compilation failed {}: {}",
+                source.display(),
+                String::from_utf8_lossy(&output.stderr)
+            );
+        }
+        Err(err) => {
+            error!("unable to run {}: {}", compiler, err);
+        }
+    }
+}
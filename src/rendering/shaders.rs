@@ -1,4 +1,5 @@
 use std::ffi::OsStr;
+use std::fmt;
 use std::fs;
 use std::io::Read;
 use std::path::PathBuf;
@@ -9,11 +10,41 @@ use vulkanalia::bytecode::Bytecode;
 use vulkanalia::vk::{HasBuilder, ShaderModule};
 
 use crate::rendering::RenderingError::LoadShadersError;
-use crate::rendering::RqResult;
+use crate::rendering::{EntryPoint, RqResult, ShaderReflection};
 
 pub struct Shader {
     pub bytecode: Bytecode,
-    pub name: Vec<u8>
+    pub name: Vec<u8>,
+    pub reflection: ShaderReflection,
+    //Стадия, для которой предназначен шейдер, если известна
+    pub stage: Option<vk::ShaderStageFlags>
+}
+
+//Bytecode не реализует Clone и Debug
+impl Clone for Shader {
+    fn clone(&self) -> Self {
+        let bytes: Vec<u8> = self.bytecode.code().iter()
+            .flat_map(|word| word.to_ne_bytes())
+            .collect();
+
+        Self {
+            //Длина уже кратна 4, ошибкой может быть только нехватка памяти
+            bytecode: Bytecode::new(&bytes).expect("shader bytecode allocation exception"),
+            name: self.name.clone(),
+            reflection: self.reflection.clone(),
+            stage: self.stage
+        }
+    }
+}
+
+impl fmt::Debug for Shader {
+    fn fmt(&self, formatter: &mut fmt::Formatter<'_>) -> fmt::Result {
+        formatter.debug_struct("Shader")
+            .field("name", &String::from_utf8_lossy(&self.name))
+            .field("code_size", &self.bytecode.code_size())
+            .field("stage", &self.stage)
+            .finish()
+    }
 }
 
 impl Shader{
@@ -22,23 +53,63 @@ impl Shader{
         buffer: &mut Vec<u8>,
     ) -> RqResult<Self>{
         let mut shader_file = fs::File::open(path)
-            .map_err(|err| LoadShadersError(format!("{}", err))
+            .map_err(|err| LoadShadersError(format!("{}: {}", path.display(), err))
         )?;
 
-        let file_size = shader_file.read_to_end(buffer)
-            .map_err(|err| LoadShadersError(format!("{}", err)))?;
+        //Буфер переиспользуется между шейдерами, старое содержимое не нужно
+        buffer.clear();
+        shader_file.read_to_end(buffer)
+            .map_err(|err| LoadShadersError(format!("{}: {}", path.display(), err)))?;
 
-        let bytecode = Bytecode::new(&buffer[..file_size])
+        let bytecode = Bytecode::new(buffer)
             .map_err(|err|
-                LoadShadersError(format!("Bytecode error {}", err)))?;
+                LoadShadersError(format!("{}: bytecode error {}", path.display(), err)))?;
 
         let file_name = get_file_name_or_default(path, "unknown");
+        let reflection = ShaderReflection::reflect(bytecode.code())?;
 
         Result::Ok(Self{
             bytecode,
             name: file_name,
+            reflection,
+            stage: None
         })
     }
+
+    pub fn with_stage(mut self, stage: vk::ShaderStageFlags) -> Self {
+        self.stage = Some(stage);
+        self
+    }
+
+    //Точка входа для стадии: по имени, а без имени main
+    //или единственная точка входа этой стадии
+    pub fn entry_point(&self, stage: vk::ShaderStageFlags, name: Option<&str>) -> RqResult<&EntryPoint> {
+        let shader_name = String::from_utf8_lossy(&self.name);
+
+        if self.stage.is_some_and(|own_stage| !own_stage.contains(stage)) {
+            return Err(LoadShadersError(format!(
+                "{}: shader for {:?} used as {:?}", shader_name, self.stage, stage
+            )));
+        }
+
+        let mut entry_points = self.reflection.entry_points.iter()
+            .filter(|entry_point| entry_point.stage == stage);
+
+        let entry_point = match name {
+            Some(name) => entry_points.find(|entry_point| entry_point.name == name),
+            None => {
+                let candidates: Vec<&EntryPoint> = entry_points.collect();
+                candidates.iter()
+                    .find(|entry_point| entry_point.name == "main")
+                    .or(if candidates.len() == 1 { candidates.first() } else { None })
+                    .copied()
+            }
+        };
+
+        entry_point.ok_or_else(|| LoadShadersError(format!(
+            "{}: entry point {} for {:?} was not found", shader_name, name.unwrap_or("main"), stage
+        )))
+    }
 }
 
 
@@ -49,7 +120,7 @@ fn get_file_name_or_default<'b>(
 ) -> Vec<u8> {
     let default = OsStr::new(default);
     let shader_file_name = path_to_shader.file_name()
-        .map_or(default, | value | default);
+        .unwrap_or(default);
 
     let bytes_name = shader_file_name.as_encoded_bytes();
     return bytes_name.to_vec();
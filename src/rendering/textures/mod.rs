@@ -0,0 +1,5 @@
+mod texture;
+mod sampler;
+
+pub use texture::*;
+pub use sampler::*;
@@ -0,0 +1,175 @@
+use log::warn;
+use serde::{Deserialize, Serialize};
+use vulkanalia::prelude::v1_0::*;
+
+use crate::rendering::{get_enabled_features, RqResult};
+use crate::rendering::RenderingError::CreateSamplerError;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct SamplerDescription {
+    pub mag_filter: Filter,
+    pub min_filter: Filter,
+    pub mipmap_mode: MipmapMode,
+    pub address_mode_u: AddressMode,
+    pub address_mode_v: AddressMode,
+    pub address_mode_w: AddressMode,
+    //Анизотропная фильтрация, None - выключена.
+    //Значение ограничивается пределом устройства
+    pub max_anisotropy: Option<f32>
+}
+
+impl Default for SamplerDescription {
+    fn default() -> Self {
+        Self {
+            mag_filter: Filter::Linear,
+            min_filter: Filter::Linear,
+            mipmap_mode: MipmapMode::Linear,
+            address_mode_u: AddressMode::Repeat,
+            address_mode_v: AddressMode::Repeat,
+            address_mode_w: AddressMode::Repeat,
+            max_anisotropy: None
+        }
+    }
+}
+
+impl SamplerDescription {
+    //Пиксельная графика, текстуры шрифтов
+    pub fn nearest() -> Self {
+        Self {
+            mag_filter: Filter::Nearest,
+            min_filter: Filter::Nearest,
+            mipmap_mode: MipmapMode::Nearest,
+            ..Self::default()
+        }
+    }
+
+    pub fn with_address_mode(self, address_mode: AddressMode) -> Self {
+        Self {
+            address_mode_u: address_mode,
+            address_mode_v: address_mode,
+            address_mode_w: address_mode,
+            ..self
+        }
+    }
+
+    pub fn with_anisotropy(self, max_anisotropy: f32) -> Self {
+        Self {
+            max_anisotropy: Some(max_anisotropy),
+            ..self
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Filter {
+    Nearest,
+    Linear
+}
+
+impl From<Filter> for vk::Filter {
+    fn from(filter: Filter) -> Self {
+        match filter {
+            Filter::Nearest => vk::Filter::NEAREST,
+            Filter::Linear => vk::Filter::LINEAR
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum MipmapMode {
+    Nearest,
+    Linear
+}
+
+impl From<MipmapMode> for vk::SamplerMipmapMode {
+    fn from(mode: MipmapMode) -> Self {
+        match mode {
+            MipmapMode::Nearest => vk::SamplerMipmapMode::NEAREST,
+            MipmapMode::Linear => vk::SamplerMipmapMode::LINEAR
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum AddressMode {
+    Repeat,
+    MirroredRepeat,
+    ClampToEdge,
+    ClampToBorder
+}
+
+impl From<AddressMode> for vk::SamplerAddressMode {
+    fn from(mode: AddressMode) -> Self {
+        match mode {
+            AddressMode::Repeat => vk::SamplerAddressMode::REPEAT,
+            AddressMode::MirroredRepeat => vk::SamplerAddressMode::MIRRORED_REPEAT,
+            AddressMode::ClampToEdge => vk::SamplerAddressMode::CLAMP_TO_EDGE,
+            AddressMode::ClampToBorder => vk::SamplerAddressMode::CLAMP_TO_BORDER
+        }
+    }
+}
+
+//Сэмплер не привязан к текстуре, одинаковые описания дают один сэмплер
+#[derive(Debug, Default)]
+pub struct SamplerCache {
+    samplers: Vec<(SamplerDescription, vk::Sampler)>,
+    //Предел устройства, None - возможность sampler_anisotropy не включена
+    max_anisotropy: Option<f32>
+}
+
+impl SamplerCache {
+    pub unsafe fn new(instance: &Instance, physical_device: vk::PhysicalDevice) -> Self {
+        let enabled = get_enabled_features(instance, physical_device).sampler_anisotropy == vk::TRUE;
+        let limit = instance.get_physical_device_properties(physical_device).limits.max_sampler_anisotropy;
+
+        Self {
+            samplers: Vec::new(),
+            max_anisotropy: enabled.then_some(limit)
+        }
+    }
+
+    pub unsafe fn get_or_create(&mut self, device: &Device, description: &SamplerDescription) -> RqResult<vk::Sampler> {
+        if let Some((_, sampler)) = self.samplers.iter().find(|(existing, _)| existing == description) {
+            return Result::Ok(*sampler);
+        }
+
+        let anisotropy = match (description.max_anisotropy, self.max_anisotropy) {
+            (Some(requested), Some(limit)) => Some(requested.clamp(1.0, limit)),
+            (Some(_), None) => {
+                warn!("sampler anisotropy is not supported by device, sampler will use isotropic filtering");
+                None
+            }
+            (None, _) => None
+        };
+
+        let info = vk::SamplerCreateInfo::builder()
+            .mag_filter(description.mag_filter.into())
+            .min_filter(description.min_filter.into())
+            .mipmap_mode(description.mipmap_mode.into())
+            .address_mode_u(description.address_mode_u.into())
+            .address_mode_v(description.address_mode_v.into())
+            .address_mode_w(description.address_mode_w.into())
+            .anisotropy_enable(anisotropy.is_some())
+            .max_anisotropy(anisotropy.unwrap_or(1.0))
+            .border_color(vk::BorderColor::INT_OPAQUE_BLACK)
+            .unnormalized_coordinates(false)
+            .compare_enable(false)
+            .compare_op(vk::CompareOp::ALWAYS)
+            .mip_lod_bias(0.0)
+            .min_lod(0.0)
+            .max_lod(vk::LOD_CLAMP_NONE);
+
+        let sampler = device.create_sampler(&info, None)
+            .map_err(CreateSamplerError)?;
+        self.samplers.push((*description, sampler));
+
+        Result::Ok(sampler)
+    }
+
+    pub unsafe fn destroy(&mut self, device: &Device) {
+        for (_, sampler) in self.samplers.drain(..) {
+            device.destroy_sampler(sampler, None);
+        }
+    }
+}
@@ -0,0 +1,420 @@
+use std::borrow::Cow;
+use std::ops::Range;
+use std::path::Path;
+
+use image::imageops::{self, FilterType};
+use image::RgbaImage;
+use log::warn;
+
+use vulkanalia::prelude::v1_0::*;
+
+use crate::rendering::{Allocation, DescriptorWriter, MemoryAllocator, MemoryUsage, RqResult, UploadContext};
+use crate::rendering::RenderingError::{CreateTextureError, LoadTextureError};
+
+//Цветовые текстуры хранятся в sRGB, карты нормалей и прочие данные - линейно
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum TextureFormat {
+    #[default]
+    Srgb,
+    Linear
+}
+
+impl From<TextureFormat> for vk::Format {
+    fn from(format: TextureFormat) -> Self {
+        match format {
+            TextureFormat::Srgb => vk::Format::R8G8B8A8_SRGB,
+            TextureFormat::Linear => vk::Format::R8G8B8A8_UNORM
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextureOptions {
+    pub format: TextureFormat,
+    //Полная цепочка mip-уровней до 1x1
+    pub mipmaps: bool
+}
+
+//Индекс текстуры в очереди, действителен все время жизни очереди
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TextureHandle(pub(crate) usize);
+
+//Изображение в памяти устройства, готовое к чтению в шейдере
+#[derive(Debug)]
+pub struct Texture {
+    pub image: vk::Image,
+    pub allocation: Allocation,
+    pub view: vk::ImageView,
+    pub extent: vk::Extent2D,
+    pub format: vk::Format,
+    pub mip_levels: u32
+}
+
+impl Texture {
+    //PNG, JPEG и TGA, формат определяется по содержимому файла
+    pub unsafe fn load(context: &mut UploadContext, path: &Path, options: TextureOptions) -> RqResult<Self> {
+        let image = image::open(path)
+            .map_err(|err| LoadTextureError(format!("{}: {}", path.display(), err)))?
+            .into_rgba8();
+
+        Self::from_rgba(context, image.width(), image.height(), image.as_raw(), options)
+    }
+
+    //pixels - строки RGBA по 4 байта на пиксель без выравнивания
+    pub unsafe fn from_rgba(
+        context: &mut UploadContext,
+        width: u32,
+        height: u32,
+        pixels: &[u8],
+        options: TextureOptions
+    ) -> RqResult<Self> {
+        let expected_size = width as usize * height as usize * 4;
+        if width == 0 || height == 0 || pixels.len() != expected_size {
+            return Err(LoadTextureError(format!(
+                "{}x{} texture needs {} bytes, got {}",
+                width, height, expected_size, pixels.len()
+            )));
+        }
+
+        let extent = vk::Extent2D { width, height };
+        let format = vk::Format::from(options.format);
+        let device = context.device;
+
+        let mip_levels = if options.mipmaps {
+            width.max(height).ilog2() + 1
+        } else {
+            1
+        };
+
+        //Уровни строятся на устройстве, если формат допускает линейную фильтрацию
+        //при копировании, иначе все уровни готовятся здесь и копируются вместе
+        let blit_mipmaps = mip_levels > 1 && supports_linear_blit(context, format);
+        if mip_levels > 1 && !blit_mipmaps {
+            warn!("{:?} does not support linear blit, mipmaps will be generated on CPU", format);
+        }
+
+        let (data, regions) = if mip_levels > 1 && !blit_mipmaps {
+            let (data, regions) = cpu_mip_chain(width, height, pixels, mip_levels);
+            (Cow::Owned(data), regions)
+        } else {
+            (Cow::Borrowed(pixels), vec![copy_region(0, 0, width, height)])
+        };
+
+        let staging = context.create_staging_buffer(&data)?;
+        let texture = match Self::create(context, extent, format, mip_levels) {
+            Ok(texture) => texture,
+            Err(err) => {
+                staging.destroy(device, context.allocator);
+                return Err(err);
+            }
+        };
+
+        let uploaded = context.submit(|command_buffer| {
+            transition_layout(
+                device,
+                command_buffer,
+                texture.image,
+                0..mip_levels,
+                vk::ImageLayout::UNDEFINED,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL
+            );
+
+            device.cmd_copy_buffer_to_image(
+                command_buffer,
+                staging.buffer,
+                texture.image,
+                vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                &regions
+            );
+
+            if blit_mipmaps {
+                blit_mip_chain(device, command_buffer, texture.image, extent, mip_levels);
+            } else {
+                transition_layout(
+                    device,
+                    command_buffer,
+                    texture.image,
+                    0..mip_levels,
+                    vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+                    vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL
+                );
+            }
+        });
+
+        staging.destroy(device, context.allocator);
+        if let Err(err) = uploaded {
+            texture.destroy(device, context.allocator);
+            return Err(err);
+        }
+
+        Result::Ok(texture)
+    }
+
+    unsafe fn create(
+        context: &mut UploadContext,
+        extent: vk::Extent2D,
+        format: vk::Format,
+        mip_levels: u32
+    ) -> RqResult<Self> {
+        let device = context.device;
+
+        //Уровни, построенные копированием, читаются из соседнего уровня
+        let usage = if mip_levels > 1 {
+            vk::ImageUsageFlags::TRANSFER_SRC | vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED
+        } else {
+            vk::ImageUsageFlags::TRANSFER_DST | vk::ImageUsageFlags::SAMPLED
+        };
+
+        let image_info = vk::ImageCreateInfo::builder()
+            .image_type(vk::ImageType::_2D)
+            .extent(vk::Extent3D { width: extent.width, height: extent.height, depth: 1 })
+            .mip_levels(mip_levels)
+            .array_layers(1)
+            .format(format)
+            .tiling(vk::ImageTiling::OPTIMAL)
+            .initial_layout(vk::ImageLayout::UNDEFINED)
+            .usage(usage)
+            .sharing_mode(vk::SharingMode::EXCLUSIVE)
+            .samples(vk::SampleCountFlags::_1);
+
+        let image = device.create_image(&image_info, None)
+            .map_err(CreateTextureError)?;
+
+        let allocation = match context.allocator.allocate_image(device, image, MemoryUsage::GpuOnly) {
+            Ok(allocation) => allocation,
+            Err(err) => {
+                device.destroy_image(image, None);
+                return Err(err);
+            }
+        };
+
+        let view_info = vk::ImageViewCreateInfo::builder()
+            .image(image)
+            .view_type(vk::ImageViewType::_2D)
+            .format(format)
+            .subresource_range(color_subresource_range(0..mip_levels));
+
+        match device.create_image_view(&view_info, None) {
+            Ok(view) => Result::Ok(Self {
+                image,
+                allocation,
+                view,
+                extent,
+                format,
+                mip_levels
+            }),
+            Err(err) => {
+                device.destroy_image(image, None);
+                context.allocator.free(device, &allocation);
+                Err(CreateTextureError(err))
+            }
+        }
+    }
+
+    pub fn descriptor_writer(&self, binding: u32, sampler: vk::Sampler) -> DescriptorWriter {
+        let mut writer = DescriptorWriter::new();
+        writer.write_image(
+            binding,
+            vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            self.view,
+            sampler,
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL
+        );
+        writer
+    }
+
+    pub unsafe fn destroy(&self, device: &Device, allocator: &mut MemoryAllocator) {
+        device.destroy_image_view(self.view, None);
+        device.destroy_image(self.image, None);
+        allocator.free(device, &self.allocation);
+    }
+}
+
+fn color_subresource_range(mip_levels: Range<u32>) -> vk::ImageSubresourceRange {
+    vk::ImageSubresourceRange::builder()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .base_mip_level(mip_levels.start)
+        .level_count(mip_levels.len() as u32)
+        .base_array_layer(0)
+        .layer_count(1)
+        .build()
+}
+
+fn copy_region(offset: vk::DeviceSize, mip_level: u32, width: u32, height: u32) -> vk::BufferImageCopy {
+    vk::BufferImageCopy::builder()
+        .buffer_offset(offset)
+        .buffer_row_length(0)
+        .buffer_image_height(0)
+        .image_subresource(vk::ImageSubresourceLayers::builder()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .mip_level(mip_level)
+            .base_array_layer(0)
+            .layer_count(1)
+            .build())
+        .image_offset(vk::Offset3D::default())
+        .image_extent(vk::Extent3D { width, height, depth: 1 })
+        .build()
+}
+
+unsafe fn supports_linear_blit(context: &UploadContext, format: vk::Format) -> bool {
+    context.instance
+        .get_physical_device_format_properties(context.physical_device, format)
+        .optimal_tiling_features
+        .contains(
+            vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR
+                | vk::FormatFeatureFlags::BLIT_SRC
+                | vk::FormatFeatureFlags::BLIT_DST
+        )
+}
+
+//Все уровни подряд в одном буфере и области копирования для каждого
+fn cpu_mip_chain(width: u32, height: u32, pixels: &[u8], mip_levels: u32) -> (Vec<u8>, Vec<vk::BufferImageCopy>) {
+    let mut data = Vec::with_capacity(pixels.len() * 4 / 3 + 4);
+    let mut regions = Vec::with_capacity(mip_levels as usize);
+
+    data.extend_from_slice(pixels);
+    regions.push(copy_region(0, 0, width, height));
+
+    let mut level = RgbaImage::from_raw(width, height, pixels.to_vec())
+        .expect("pixels size is checked before");
+    for mip_level in 1..mip_levels {
+        let (level_width, level_height) = ((level.width() / 2).max(1), (level.height() / 2).max(1));
+        level = imageops::resize(&level, level_width, level_height, FilterType::Triangle);
+
+        regions.push(copy_region(data.len() as vk::DeviceSize, mip_level, level_width, level_height));
+        data.extend_from_slice(level.as_raw());
+    }
+
+    (data, regions)
+}
+
+//Каждый уровень получается уменьшением предыдущего, после чего предыдущий
+//сразу переводится в раскладку для чтения шейдером
+unsafe fn blit_mip_chain(
+    device: &Device,
+    command_buffer: vk::CommandBuffer,
+    image: vk::Image,
+    extent: vk::Extent2D,
+    mip_levels: u32
+) {
+    let mut width = extent.width as i32;
+    let mut height = extent.height as i32;
+
+    for mip_level in 1..mip_levels {
+        transition_layout(
+            device,
+            command_buffer,
+            image,
+            mip_level - 1..mip_level,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL
+        );
+
+        let next_width = (width / 2).max(1);
+        let next_height = (height / 2).max(1);
+
+        let layers = |mip_level| vk::ImageSubresourceLayers::builder()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .mip_level(mip_level)
+            .base_array_layer(0)
+            .layer_count(1)
+            .build();
+
+        let blit = vk::ImageBlit::builder()
+            .src_subresource(layers(mip_level - 1))
+            .src_offsets([vk::Offset3D::default(), vk::Offset3D { x: width, y: height, z: 1 }])
+            .dst_subresource(layers(mip_level))
+            .dst_offsets([vk::Offset3D::default(), vk::Offset3D { x: next_width, y: next_height, z: 1 }])
+            .build();
+
+        device.cmd_blit_image(
+            command_buffer,
+            image,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            image,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            &[blit],
+            vk::Filter::LINEAR
+        );
+
+        transition_layout(
+            device,
+            command_buffer,
+            image,
+            mip_level - 1..mip_level,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL
+        );
+
+        width = next_width;
+        height = next_height;
+    }
+
+    transition_layout(
+        device,
+        command_buffer,
+        image,
+        mip_levels - 1..mip_levels,
+        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL
+    );
+}
+
+//Копирование пишет в TRANSFER_DST, уменьшение читает из TRANSFER_SRC,
+//шейдер читает из SHADER_READ_ONLY
+unsafe fn transition_layout(
+    device: &Device,
+    command_buffer: vk::CommandBuffer,
+    image: vk::Image,
+    mip_levels: Range<u32>,
+    old_layout: vk::ImageLayout,
+    new_layout: vk::ImageLayout
+) {
+    let (src_access, dst_access, src_stage, dst_stage) = match (old_layout, new_layout) {
+        (vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL) => (
+            vk::AccessFlags::empty(),
+            vk::AccessFlags::TRANSFER_WRITE,
+            vk::PipelineStageFlags::TOP_OF_PIPE,
+            vk::PipelineStageFlags::TRANSFER
+        ),
+        (vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::TRANSFER_SRC_OPTIMAL) => (
+            vk::AccessFlags::TRANSFER_WRITE,
+            vk::AccessFlags::TRANSFER_READ,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::TRANSFER
+        ),
+        (vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL) => (
+            vk::AccessFlags::TRANSFER_WRITE,
+            vk::AccessFlags::SHADER_READ,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::FRAGMENT_SHADER
+        ),
+        (vk::ImageLayout::TRANSFER_SRC_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL) => (
+            vk::AccessFlags::TRANSFER_READ,
+            vk::AccessFlags::SHADER_READ,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::FRAGMENT_SHADER
+        ),
+        _ => unreachable!("unsupported layout transition {:?} -> {:?}", old_layout, new_layout)
+    };
+
+    let barrier = vk::ImageMemoryBarrier::builder()
+        .old_layout(old_layout)
+        .new_layout(new_layout)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .image(image)
+        .subresource_range(color_subresource_range(mip_levels))
+        .src_access_mask(src_access)
+        .dst_access_mask(dst_access);
+
+    device.cmd_pipeline_barrier(
+        command_buffer,
+        src_stage,
+        dst_stage,
+        vk::DependencyFlags::empty(),
+        &[] as &[vk::MemoryBarrier],
+        &[] as &[vk::BufferMemoryBarrier],
+        &[barrier]
+    );
+}
@@ -0,0 +1,111 @@
+use std::ptr;
+
+use vulkanalia::prelude::v1_0::*;
+
+use crate::rendering::{Buffer, MemoryAllocator, MemoryUsage, RqResult};
+use crate::rendering::RenderingError::UploadError;
+
+//Все, что нужно для разовой загрузки данных на устройство
+pub struct UploadContext<'a> {
+    pub instance: &'a Instance,
+    pub device: &'a Device,
+    pub physical_device: vk::PhysicalDevice,
+    pub allocator: &'a mut MemoryAllocator,
+    pub command_pool: vk::CommandPool,
+    pub queue: vk::Queue
+}
+
+impl UploadContext<'_> {
+    //Записывает команды в отдельный буфер, отправляет и ждет завершения
+    pub unsafe fn submit<F>(&self, record: F) -> RqResult<()>
+        where F: FnOnce(vk::CommandBuffer)
+    {
+        let allocate_info = vk::CommandBufferAllocateInfo::builder()
+            .command_pool(self.command_pool)
+            .level(vk::CommandBufferLevel::PRIMARY)
+            .command_buffer_count(1);
+
+        let command_buffer = self.device.allocate_command_buffers(&allocate_info)
+            .map_err(UploadError)?[0];
+
+        let result = self.record_and_submit(command_buffer, record);
+        self.device.free_command_buffers(self.command_pool, &[command_buffer]);
+
+        result
+    }
+
+    unsafe fn record_and_submit<F>(&self, command_buffer: vk::CommandBuffer, record: F) -> RqResult<()>
+        where F: FnOnce(vk::CommandBuffer)
+    {
+        let begin_info = vk::CommandBufferBeginInfo::builder()
+            .flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+        self.device.begin_command_buffer(command_buffer, &begin_info)
+            .map_err(UploadError)?;
+
+        record(command_buffer);
+
+        self.device.end_command_buffer(command_buffer)
+            .map_err(UploadError)?;
+
+        let command_buffers = &[command_buffer];
+        let submit_info = vk::SubmitInfo::builder()
+            .command_buffers(command_buffers);
+
+        self.device.queue_submit(self.queue, &[submit_info], vk::Fence::null())
+            .map_err(UploadError)?;
+        self.device.queue_wait_idle(self.queue)
+            .map_err(UploadError)
+    }
+
+    //Буфер в памяти хоста, из которого данные копируются на устройство
+    pub unsafe fn create_staging_buffer(&mut self, data: &[u8]) -> RqResult<Buffer> {
+        let buffer = Buffer::create(
+            self.device,
+            self.allocator,
+            data.len() as vk::DeviceSize,
+            vk::BufferUsageFlags::TRANSFER_SRC,
+            MemoryUsage::Upload
+        )?;
+
+        let mapped = buffer.mapped_ptr().expect("upload memory is mapped");
+        ptr::copy_nonoverlapping(data.as_ptr(), mapped, data.len());
+
+        Result::Ok(buffer)
+    }
+
+    //Буфер в памяти устройства, заполненный через промежуточный
+    pub unsafe fn create_device_buffer(&mut self, data: &[u8], usage: vk::BufferUsageFlags) -> RqResult<Buffer> {
+        let staging = self.create_staging_buffer(data)?;
+
+        let buffer = match Buffer::create(
+            self.device,
+            self.allocator,
+            data.len() as vk::DeviceSize,
+            usage | vk::BufferUsageFlags::TRANSFER_DST,
+            MemoryUsage::GpuOnly
+        ) {
+            Ok(buffer) => buffer,
+            Err(err) => {
+                staging.destroy(self.device, self.allocator);
+                return Err(err);
+            }
+        };
+
+        let uploaded = self.submit(|command_buffer| {
+            let region = vk::BufferCopy::builder()
+                .src_offset(0)
+                .dst_offset(0)
+                .size(buffer.size);
+            self.device.cmd_copy_buffer(command_buffer, staging.buffer, buffer.buffer, &[region]);
+        });
+        staging.destroy(self.device, self.allocator);
+
+        match uploaded {
+            Ok(()) => Result::Ok(buffer),
+            Err(err) => {
+                buffer.destroy(self.device, self.allocator);
+                Err(err)
+            }
+        }
+    }
+}
@@ -0,0 +1,56 @@
+use vulkanalia::prelude::v1_0::*;
+
+//Прямоугольник области вывода в долях от размера изображения,
+//поэтому он не зависит от размера окна
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewportRect {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32
+}
+
+impl ViewportRect {
+    pub const FULL: ViewportRect = ViewportRect {
+        x: 0.0,
+        y: 0.0,
+        width: 1.0,
+        height: 1.0
+    };
+
+    pub fn new(x: f32, y: f32, width: f32, height: f32) -> Self {
+        Self { x, y, width, height }
+    }
+
+    pub fn to_viewport(self, extent: vk::Extent2D) -> vk::Viewport {
+        vk::Viewport::builder()
+            .x(self.x * extent.width as f32)
+            .y(self.y * extent.height as f32)
+            .width(self.width * extent.width as f32)
+            .height(self.height * extent.height as f32)
+            .min_depth(0.0)
+            .max_depth(1.0)
+            .build()
+    }
+
+    pub fn to_scissor(self, extent: vk::Extent2D) -> vk::Rect2D {
+        let viewport = self.to_viewport(extent);
+
+        vk::Rect2D::builder()
+            .offset(vk::Offset2D {
+                x: viewport.x.round() as i32,
+                y: viewport.y.round() as i32
+            })
+            .extent(vk::Extent2D {
+                width: viewport.width.round() as u32,
+                height: viewport.height.round() as u32
+            })
+            .build()
+    }
+}
+
+impl Default for ViewportRect {
+    fn default() -> Self {
+        Self::FULL
+    }
+}